@@ -2,17 +2,35 @@
 //
 // Reads `viewer/public/map_data_export.json` and converts all coordinates
 // using the WorldPositionTransformer, outputting `viewer/public/map_data_processed.json`
+//
+// The input is either pre-split (`Bonfires` / `MapPoints`) or a single combined
+// array of icons. Combined input needs `--bonfire-icons <id,id,...>`: icons with
+// one of those icon ids go to `bonfires`, everything else to `map_points`.
+//
+// The output records the CSV it was converted with in `datasetVersion` (file
+// name and content hash), or the value passed with `--dataset-version <name>`.
+//
+// With `--split-by-map`, `map_data_m60.json` and `map_data_m61.json` are written
+// instead, each with only the icons of that map layer and its own statistics.
 
 // Include the coordinate_transformer module directly
+// (this tool only uses part of its API)
+#[allow(dead_code)]
 #[path = "../coordinate_transformer.rs"]
 mod coordinate_transformer;
 
-use coordinate_transformer::WorldPositionTransformer;
-use serde::{Deserialize, Serialize};
+// Output types are shared with the tracker (which reads the processed file back)
+#[allow(dead_code)]
+#[path = "../map_icons.rs"]
+mod map_icons;
+
+use coordinate_transformer::{TransformError, WorldPositionTransformer};
+use map_icons::{MapIconText, OutputMapData, OutputMapIcon};
+use serde::Deserialize;
 use std::collections::HashMap;
 use std::fs::{self, File};
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 // =============================================================================
 // INPUT DATA STRUCTURES (matching map_data_export.json)
@@ -35,16 +53,10 @@ struct InputMapIcon {
     pos_y: f32,
     #[serde(rename = "PosZ")]
     pos_z: f32,
-    texts: Vec<InputText>,
-}
-
-#[derive(Debug, Clone, Deserialize, Serialize)]
-#[serde(rename_all = "PascalCase")]
-struct InputText {
-    text_id: u64,
-    text_type: u32,
-    text: Option<String>,
-    source: String,
+    /// Sub-index of the tile (the DD byte of the map_id), 0 when the export has none
+    #[serde(default)]
+    sub_no: u8,
+    texts: Vec<MapIconText>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -55,43 +67,82 @@ struct InputMapData {
     map_points: Vec<InputMapIcon>,
 }
 
-// =============================================================================
-// OUTPUT DATA STRUCTURES (with global coordinates)
-// =============================================================================
+/// Input file layout: pre-split lists, or one flat array of every icon
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum InputFile {
+    Split(InputMapData),
+    Combined(Vec<InputMapIcon>),
+}
 
-#[derive(Debug, Clone, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct OutputMapIcon {
-    id: u64,
-    icon_id: u32,
-    event_flag_id: u64,
-    // Original local coordinates
-    area_no: u8,
-    grid_x_no: u8,
-    grid_z_no: u8,
-    pos_x: f32,
-    pos_y: f32,
-    pos_z: f32,
-    // Converted global coordinates
-    global_x: f32,
-    global_y: f32,
-    global_z: f32,
-    // Map string (e.g., "m60" or "m61")
-    map_id: String,
-    // Text data
-    texts: Vec<InputText>,
+impl InputFile {
+    /// Resolve to bonfires / map points, classifying combined input by icon id
+    fn into_map_data(self, bonfire_icons: Option<&[u32]>) -> Result<InputMapData, String> {
+        match (self, bonfire_icons) {
+            (InputFile::Split(data), _) => Ok(data),
+            (InputFile::Combined(icons), Some(bonfire_icons)) => {
+                Ok(split_by_icon_id(icons, bonfire_icons))
+            }
+            (InputFile::Combined(_), None) => Err(
+                "Input is a single combined array; pass --bonfire-icons <id,id,...> to split it"
+                    .to_string(),
+            ),
+        }
+    }
+}
+
+/// Route icons whose `icon_id` is in `bonfire_icons` to bonfires, the rest to map points
+fn split_by_icon_id(icons: Vec<InputMapIcon>, bonfire_icons: &[u32]) -> InputMapData {
+    let (bonfires, map_points) = icons
+        .into_iter()
+        .partition(|icon| bonfire_icons.contains(&icon.icon_id));
+    InputMapData { bonfires, map_points }
+}
+
+/// Value of `--<flag> <value>` (or `--<flag>=<value>`) in the arguments
+fn flag_value<I: IntoIterator<Item = String>>(args: I, flag: &str) -> Result<Option<String>, String> {
+    let mut args = args.into_iter();
+    let prefix = format!("{}=", flag);
+    while let Some(arg) = args.next() {
+        if arg == flag {
+            return args.next().map(Some).ok_or_else(|| format!("{} needs a value", flag));
+        }
+        if let Some(value) = arg.strip_prefix(&prefix) {
+            return Ok(Some(value.to_string()));
+        }
+    }
+    Ok(None)
+}
+
+/// Parse `--bonfire-icons <id,id,...>` (or `--bonfire-icons=<id,id,...>`) from the arguments
+fn parse_bonfire_icons<I: IntoIterator<Item = String>>(args: I) -> Result<Option<Vec<u32>>, String> {
+    let Some(list) = flag_value(args, "--bonfire-icons")
+        .map_err(|_| "--bonfire-icons needs a comma-separated list of icon ids".to_string())?
+    else {
+        return Ok(None);
+    };
+
+    list.split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| id.parse::<u32>().map_err(|e| format!("Invalid icon id '{}': {}", id, e)))
+        .collect::<Result<Vec<_>, _>>()
+        .map(Some)
 }
 
-#[derive(Debug, Clone, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct OutputMapData {
-    bonfires: Vec<OutputMapIcon>,
-    map_points: Vec<OutputMapIcon>,
-    // Statistics
-    total_count: usize,
-    converted_count: usize,
-    failed_count: usize,
-    failed_maps: Vec<String>,
+/// Identify a CSV dataset by file name and FNV-1a hash of its contents
+///
+/// Changes whenever the data changes, unlike the file name alone, so stale
+/// processed files can be spotted.
+fn dataset_version(csv_path: &Path) -> std::io::Result<String> {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let hash = fs::read(csv_path)?
+        .iter()
+        .fold(FNV_OFFSET_BASIS, |hash, &byte| (hash ^ byte as u64).wrapping_mul(FNV_PRIME));
+    let name = csv_path.file_name().unwrap_or_default().to_string_lossy();
+    Ok(format!("{}#{:016x}", name, hash))
 }
 
 // =============================================================================
@@ -101,6 +152,23 @@ struct OutputMapData {
 fn main() {
     println!("=== Map Icons Coordinate Converter ===\n");
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let bonfire_icons = match parse_bonfire_icons(args.iter().cloned()) {
+        Ok(ids) => ids,
+        Err(e) => {
+            eprintln!("ERROR: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let split_by_map = args.iter().any(|arg| arg == "--split-by-map");
+    let dataset_version_override = match flag_value(args, "--dataset-version") {
+        Ok(version) => version,
+        Err(e) => {
+            eprintln!("ERROR: {}", e);
+            std::process::exit(1);
+        }
+    };
+
     // Paths
     let csv_path = Path::new("src/WorldMapLegacyConvParam.csv");
     let input_path = Path::new("viewer/public/map_data_export.json");
@@ -122,6 +190,14 @@ fn main() {
             std::process::exit(1);
         }
     };
+    let dataset_version = match dataset_version_override.map_or_else(|| dataset_version(csv_path), Ok) {
+        Ok(version) => version,
+        Err(e) => {
+            eprintln!("ERROR: Failed to read CSV: {}", e);
+            std::process::exit(1);
+        }
+    };
+    println!("  Dataset version: {}", dataset_version);
 
     // Load input JSON
     println!("\nLoading input JSON from {:?}...", input_path);
@@ -133,7 +209,7 @@ fn main() {
         }
     };
 
-    let input_data: InputMapData = match serde_json::from_str(&input_json) {
+    let input_file: InputFile = match serde_json::from_str(&input_json) {
         Ok(d) => d,
         Err(e) => {
             eprintln!("ERROR: Failed to parse JSON: {}", e);
@@ -141,12 +217,119 @@ fn main() {
         }
     };
 
+    let input_data = match input_file.into_map_data(bonfire_icons.as_deref()) {
+        Ok(d) => d,
+        Err(e) => {
+            eprintln!("ERROR: {}", e);
+            std::process::exit(1);
+        }
+    };
+
     println!(
         "  Found {} bonfires, {} map points",
         input_data.bonfires.len(),
         input_data.map_points.len()
     );
 
+    let sub_indexed = nonzero_sub_index_icons(&input_data);
+    if !sub_indexed.is_empty() {
+        let mut maps: Vec<String> = sub_indexed
+            .iter()
+            .map(|icon| WorldPositionTransformer::format_map_id(icon_map_id(icon)))
+            .collect();
+        maps.sort();
+        maps.dedup();
+        println!(
+            "  WARNING: {} icons have a nonzero sub-index, converted with the sub-index 0 anchors ({})",
+            sub_indexed.len(),
+            maps.join(", ")
+        );
+    }
+
+    let outputs: Vec<(PathBuf, InputMapData)> = if split_by_map {
+        let output_dir = output_path.parent().unwrap_or(Path::new("."));
+        split_by_target_map(input_data)
+            .into_iter()
+            .map(|(map, data)| (output_dir.join(format!("map_data_{}.json", map)), data))
+            .collect()
+    } else {
+        vec![(output_path.to_path_buf(), input_data)]
+    };
+
+    for (output_path, input_data) in outputs {
+        let (output_data, failed_maps) = convert_map_data(&input_data, &transformer, dataset_version.clone());
+
+        // Write output
+        println!("\nWriting output to {:?}...", output_path);
+        write_map_data(&output_path, &output_data).expect("Failed to write output file");
+        print_summary(&output_data, &failed_maps);
+        println!("\nOutput written to: {:?}", output_path);
+    }
+}
+
+/// Write processed map data as pretty JSON
+fn write_map_data(path: &Path, output_data: &OutputMapData) -> std::io::Result<()> {
+    let output_json = serde_json::to_string_pretty(output_data).map_err(std::io::Error::other)?;
+    let mut file = File::create(path)?;
+    file.write_all(output_json.as_bytes())
+}
+
+/// Print the conversion statistics and the maps that failed most
+fn print_summary(output_data: &OutputMapData, failed_maps: &HashMap<String, usize>) {
+    println!("\n=== Conversion Complete ===");
+    println!("  Total icons:     {}", output_data.total_count);
+    println!("  Converted:       {}", output_data.converted_count);
+    println!("  Failed:          {}", output_data.failed_count);
+    if !failed_maps.is_empty() {
+        println!("\n  Failed maps (count):");
+        let mut sorted: Vec<_> = failed_maps.iter().collect();
+        sorted.sort_by(|a, b| b.1.cmp(a.1));
+        for (map, count) in sorted.iter().take(10) {
+            println!("    {}: {}", map, count);
+        }
+        if sorted.len() > 10 {
+            println!("    ... and {} more", sorted.len() - 10);
+        }
+    }
+}
+
+/// Global map layer an icon is shown on
+///
+/// area_no 60 = base game overworld (m60)
+/// area_no 61 = DLC overworld (m61)
+/// area_no 20-29 = DLC interiors → m61
+/// area_no 40-49 = DLC interiors (catacombs, etc.) → m61
+/// All other areas = base game interiors → m60
+fn target_map(area_no: u8) -> &'static str {
+    match area_no {
+        61 | 20..=29 | 40..=49 => "m61",
+        _ => "m60",
+    }
+}
+
+/// Split the input into one set of icons per map layer (see `target_map`)
+fn split_by_target_map(input_data: InputMapData) -> Vec<(&'static str, InputMapData)> {
+    ["m60", "m61"]
+        .into_iter()
+        .map(|map| {
+            let on_map = |icons: &[InputMapIcon]| -> Vec<InputMapIcon> {
+                icons.iter().filter(|icon| target_map(icon.area_no) == map).cloned().collect()
+            };
+            let data = InputMapData {
+                bonfires: on_map(&input_data.bonfires),
+                map_points: on_map(&input_data.map_points),
+            };
+            (map, data)
+        })
+        .collect()
+}
+
+/// Convert all icons to global coordinates, with the failure count per map
+fn convert_map_data(
+    input_data: &InputMapData,
+    transformer: &WorldPositionTransformer,
+    dataset_version: String,
+) -> (OutputMapData, HashMap<String, usize>) {
     // Track statistics
     let mut converted_count = 0usize;
     let mut failed_count = 0usize;
@@ -157,8 +340,9 @@ fn main() {
     let bonfires: Vec<OutputMapIcon> = input_data
         .bonfires
         .iter()
-        .filter_map(|icon| {
-            convert_icon(icon, &transformer, &mut converted_count, &mut failed_count, &mut failed_maps)
+        .zip(convert_positions(&input_data.bonfires, transformer))
+        .filter_map(|(icon, position)| {
+            convert_icon(icon, position, &mut converted_count, &mut failed_count, &mut failed_maps)
         })
         .collect();
 
@@ -167,8 +351,9 @@ fn main() {
     let map_points: Vec<OutputMapIcon> = input_data
         .map_points
         .iter()
-        .filter_map(|icon| {
-            convert_icon(icon, &transformer, &mut converted_count, &mut failed_count, &mut failed_maps)
+        .zip(convert_positions(&input_data.map_points, transformer))
+        .filter_map(|(icon, position)| {
+            convert_icon(icon, position, &mut converted_count, &mut failed_count, &mut failed_maps)
         })
         .collect();
 
@@ -183,40 +368,67 @@ fn main() {
         converted_count,
         failed_count,
         failed_maps: failed_maps_list,
+        dataset_version: Some(dataset_version),
     };
-
-    // Write output
-    println!("\nWriting output to {:?}...", output_path);
-    let output_json = serde_json::to_string_pretty(&output_data).expect("Failed to serialize");
-    let mut file = File::create(output_path).expect("Failed to create output file");
-    file.write_all(output_json.as_bytes())
-        .expect("Failed to write output file");
-
-    // Summary
-    println!("\n=== Conversion Complete ===");
-    println!("  Total icons:     {}", total_count);
-    println!("  Converted:       {}", converted_count);
-    println!("  Failed:          {}", failed_count);
-    if !failed_maps.is_empty() {
-        println!("\n  Failed maps (count):");
-        let mut sorted: Vec<_> = failed_maps.iter().collect();
-        sorted.sort_by(|a, b| b.1.cmp(a.1));
-        for (map, count) in sorted.iter().take(10) {
-            println!("    {}: {}", map, count);
-        }
-        if sorted.len() > 10 {
-            println!("    ... and {} more", sorted.len() - 10);
-        }
-    }
-    println!("\nOutput written to: {:?}", output_path);
+    (output_data, failed_maps)
 }
 
 // Icon IDs to exclude from the output
 const EXCLUDED_ICON_IDS: &[u32] = &[0, 83];
 
+/// Tile of an icon as a map_id: 0xWWXXYYDD where WW=area, XX=gridX, YY=gridZ, DD=sub-index
+fn icon_map_id(icon: &InputMapIcon) -> u32 {
+    ((icon.area_no as u32) << 24)
+        | ((icon.grid_x_no as u32) << 16)
+        | ((icon.grid_z_no as u32) << 8)
+        | icon.sub_no as u32
+}
+
+/// Icons with a nonzero sub-index
+///
+/// The transformer keys anchors by area/gridX/gridZ only, so these are converted
+/// with the anchors of sub-index 0 and may land in the wrong place.
+fn nonzero_sub_index_icons(input_data: &InputMapData) -> Vec<&InputMapIcon> {
+    input_data
+        .bonfires
+        .iter()
+        .chain(&input_data.map_points)
+        .filter(|icon| icon.sub_no != 0)
+        .collect()
+}
+
+/// Global positions of icons, in input order
+///
+/// Icons are sorted by tile first, so `local_to_world_batch` resolves each tile
+/// once instead of once per icon, then the results are put back in input order.
+fn convert_positions(
+    icons: &[InputMapIcon],
+    transformer: &WorldPositionTransformer,
+) -> Vec<Result<(f32, f32, f32), TransformError>> {
+    let mut order: Vec<usize> = (0..icons.len()).collect();
+    order.sort_by_key(|&index| icon_map_id(&icons[index]));
+
+    let samples: Vec<(u32, f32, f32, f32)> = order
+        .iter()
+        .map(|&index| {
+            let icon = &icons[index];
+            (icon_map_id(icon), icon.pos_x, icon.pos_y, icon.pos_z)
+        })
+        .collect();
+
+    let mut positions: Vec<Option<_>> = (0..icons.len()).map(|_| None).collect();
+    for (index, result) in order.into_iter().zip(transformer.local_to_world_batch(&samples)) {
+        positions[index] = Some(result.map(|(x, y, z, _)| (x, y, z)));
+    }
+    positions
+        .into_iter()
+        .map(|position| position.expect("every icon is converted"))
+        .collect()
+}
+
 fn convert_icon(
     icon: &InputMapIcon,
-    transformer: &WorldPositionTransformer,
+    position: Result<(f32, f32, f32), TransformError>,
     converted_count: &mut usize,
     failed_count: &mut usize,
     failed_maps: &mut HashMap<String, usize>,
@@ -226,37 +438,13 @@ fn convert_icon(
         return None;
     }
 
-    // Build map_id: 0xWWXXYYDD where WW=area, XX=gridX, YY=gridZ, DD=0
-    let map_id = ((icon.area_no as u32) << 24)
-        | ((icon.grid_x_no as u32) << 16)
-        | ((icon.grid_z_no as u32) << 8)
-        | 0;
-
-    let map_id_str = WorldPositionTransformer::format_map_id(map_id);
+    let map_id_str = WorldPositionTransformer::format_map_id(icon_map_id(icon));
 
-    // Convert coordinates
-    match transformer.local_to_world_first(map_id, icon.pos_x, icon.pos_y, icon.pos_z) {
+    // Converted coordinates
+    match position {
         Ok((global_x, global_y, global_z)) => {
             *converted_count += 1;
 
-            // Determine which map this belongs to (m60 or m61)
-            // area_no 60 = base game overworld (m60)
-            // area_no 61 = DLC overworld (m61)
-            // area_no 20-29 = DLC interiors → m61
-            // area_no 40-49 = DLC interiors (catacombs, etc.) → m61
-            // All other areas = base game interiors → m60
-            let target_map = if icon.area_no == 60 {
-                "m60".to_string()
-            } else if icon.area_no == 61 {
-                "m61".to_string()
-            } else if (icon.area_no >= 20 && icon.area_no < 30) || (icon.area_no >= 40 && icon.area_no < 50) {
-                // DLC interior maps (area 20-29 and 40-49) always go to m61
-                "m61".to_string()
-            } else {
-                // Base game interior maps
-                "m60".to_string()
-            };
-
             Some(OutputMapIcon {
                 id: icon.id,
                 icon_id: icon.icon_id,
@@ -270,7 +458,7 @@ fn convert_icon(
                 global_x,
                 global_y,
                 global_z,
-                map_id: target_map,
+                map_id: target_map(icon.area_no).to_string(),
                 texts: icon.texts.clone(),
             })
         }
@@ -282,3 +470,178 @@ fn convert_icon(
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn icon_json(id: u64, icon_id: u32) -> String {
+        format!(
+            r#"{{"Id":{},"IconId":{},"EventFlagId":0,"AreaNo":60,"GridXNo":40,"GridZNo":35,"PosX":0.0,"PosY":0.0,"PosZ":0.0,"Texts":[]}}"#,
+            id, icon_id
+        )
+    }
+
+    #[test]
+    fn test_combined_input_split_by_icon_id() {
+        let json = format!(
+            "[{},{},{},{}]",
+            icon_json(1, 10),
+            icon_json(2, 20),
+            icon_json(3, 11),
+            icon_json(4, 10)
+        );
+        let input: InputFile = serde_json::from_str(&json).unwrap();
+        assert!(input.clone().into_map_data(None).is_err());
+
+        let data = input.into_map_data(Some(&[10, 11])).unwrap();
+        let ids = |icons: &[InputMapIcon]| icons.iter().map(|i| i.id).collect::<Vec<_>>();
+        assert_eq!(ids(&data.bonfires), vec![1, 3, 4]);
+        assert_eq!(ids(&data.map_points), vec![2]);
+    }
+
+    #[test]
+    fn test_parse_bonfire_icons() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(parse_bonfire_icons(args(&[])).unwrap(), None);
+        assert_eq!(
+            parse_bonfire_icons(args(&["--bonfire-icons", "1, 2,3"])).unwrap(),
+            Some(vec![1, 2, 3])
+        );
+        assert_eq!(parse_bonfire_icons(args(&["--bonfire-icons=7"])).unwrap(), Some(vec![7]));
+        assert!(parse_bonfire_icons(args(&["--bonfire-icons", "x"])).is_err());
+    }
+
+    #[test]
+    fn test_output_records_dataset_version() {
+        let csv_path = std::env::temp_dir()
+            .join(format!("convert_map_icons_dataset_{}.csv", std::process::id()));
+        fs::write(&csv_path, "ID,Name\n").unwrap();
+        let version = dataset_version(&csv_path).unwrap();
+        assert!(version.starts_with(&format!("{}#", csv_path.file_name().unwrap().to_string_lossy())));
+
+        // Content change gives a new version
+        fs::write(&csv_path, "ID,Name\n1,x\n").unwrap();
+        assert_ne!(dataset_version(&csv_path).unwrap(), version);
+        fs::remove_file(&csv_path).unwrap();
+
+        let input: InputFile = serde_json::from_str(&format!("[{}]", icon_json(1, 10))).unwrap();
+        let data = input.into_map_data(Some(&[10])).unwrap();
+        let (output, _) = convert_map_data(&data, &WorldPositionTransformer::empty(), version.clone());
+        assert_eq!(output.converted_count, 1);
+
+        let json = serde_json::to_value(&output).unwrap();
+        assert_eq!(json["datasetVersion"], version);
+    }
+
+    #[test]
+    fn test_dataset_version_flag() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(flag_value(args(&["--bonfire-icons", "1"]), "--dataset-version").unwrap(), None);
+        assert_eq!(
+            flag_value(args(&["--dataset-version", "1.16"]), "--dataset-version").unwrap(),
+            Some("1.16".to_string())
+        );
+        assert_eq!(
+            flag_value(args(&["--dataset-version=1.16"]), "--dataset-version").unwrap(),
+            Some("1.16".to_string())
+        );
+        assert!(flag_value(args(&["--dataset-version"]), "--dataset-version").is_err());
+    }
+
+    #[test]
+    fn test_grouped_conversion_matches_per_icon() {
+        let csv_path = std::env::temp_dir()
+            .join(format!("convert_map_icons_grouped_{}.csv", std::process::id()));
+        fs::write(
+            &csv_path,
+            "header\n0,0,0,0,0,10,0,0,0,1.0,2.0,3.0,60,40,35,0,100.0,50.0,100.0\n",
+        )
+        .unwrap();
+        let transformer = WorldPositionTransformer::from_csv(&csv_path).unwrap();
+        fs::remove_file(&csv_path).unwrap();
+
+        // Tiles interleaved: m60 overworld, m10 interior (anchored) and m11 (unknown)
+        let icons: Vec<InputMapIcon> = (0..12u64)
+            .map(|i| {
+                let (area, grid_x, grid_z) = [(60, 40, 35), (10, 0, 0), (11, 0, 0), (60, 41, 36)][i as usize % 4];
+                serde_json::from_str(&format!(
+                    r#"{{"Id":{},"IconId":10,"EventFlagId":0,"AreaNo":{},"GridXNo":{},"GridZNo":{},"PosX":{}.5,"PosY":1.0,"PosZ":-{}.0,"Texts":[]}}"#,
+                    i, area, grid_x, grid_z, i, i
+                ))
+                .unwrap()
+            })
+            .collect();
+
+        let grouped = convert_positions(&icons, &transformer);
+        assert_eq!(grouped.len(), icons.len());
+        for (icon, position) in icons.iter().zip(&grouped) {
+            let single = transformer.local_to_world_first(icon_map_id(icon), icon.pos_x, icon.pos_y, icon.pos_z);
+            assert_eq!(position.as_ref().ok(), single.as_ref().ok(), "icon {}", icon.id);
+        }
+        assert_eq!(grouped.iter().filter(|position| position.is_err()).count(), 3);
+    }
+
+    #[test]
+    fn test_sub_index_packed_and_flagged() {
+        let with_sub = r#"{"Id":2,"IconId":10,"EventFlagId":0,"AreaNo":10,"GridXNo":1,"GridZNo":2,"SubNo":3,"PosX":0.0,"PosY":0.0,"PosZ":0.0,"Texts":[]}"#;
+        let input_data = InputMapData {
+            bonfires: vec![serde_json::from_str(&icon_json(1, 10)).unwrap()],
+            map_points: vec![serde_json::from_str(with_sub).unwrap()],
+        };
+
+        // Missing sub-index defaults to 0
+        assert_eq!(icon_map_id(&input_data.bonfires[0]), 0x3C282300);
+        assert_eq!(icon_map_id(&input_data.map_points[0]), 0x0A010203);
+
+        let flagged: Vec<u64> = nonzero_sub_index_icons(&input_data).iter().map(|icon| icon.id).collect();
+        assert_eq!(flagged, vec![2]);
+    }
+
+    #[test]
+    fn test_split_by_map_writes_one_file_per_layer() {
+        let csv_path = std::env::temp_dir()
+            .join(format!("convert_map_icons_split_{}.csv", std::process::id()));
+        fs::write(&csv_path, "header\n").unwrap();
+        let transformer = WorldPositionTransformer::from_csv(&csv_path).unwrap();
+        fs::remove_file(&csv_path).unwrap();
+
+        // Overworld tiles convert with the grid formula alone; m21 is a DLC interior without anchors
+        let icon = |id: u64, area: u8| -> InputMapIcon {
+            serde_json::from_str(&format!(
+                r#"{{"Id":{},"IconId":10,"EventFlagId":0,"AreaNo":{},"GridXNo":40,"GridZNo":35,"PosX":0.0,"PosY":0.0,"PosZ":0.0,"Texts":[]}}"#,
+                id, area
+            ))
+            .unwrap()
+        };
+        let input_data = InputMapData {
+            bonfires: vec![icon(1, 60), icon(2, 61)],
+            map_points: vec![icon(3, 61), icon(4, 60), icon(5, 21)],
+        };
+
+        let dir = std::env::temp_dir().join(format!("convert_map_icons_split_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut written = Vec::new();
+        for (map, data) in split_by_target_map(input_data) {
+            let (output_data, _) = convert_map_data(&data, &transformer, "test".to_string());
+            let path = dir.join(format!("map_data_{}.json", map));
+            write_map_data(&path, &output_data).unwrap();
+            written.push(path);
+        }
+
+        let read = |name: &str| -> OutputMapData {
+            serde_json::from_str(&fs::read_to_string(dir.join(name)).unwrap()).unwrap()
+        };
+        let ids = |icons: &[OutputMapIcon]| icons.iter().map(|icon| icon.id).collect::<Vec<_>>();
+        let m60 = read("map_data_m60.json");
+        let m61 = read("map_data_m61.json");
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(written.len(), 2);
+        assert_eq!((ids(&m60.bonfires), ids(&m60.map_points)), (vec![1], vec![4]));
+        assert_eq!((m60.total_count, m60.converted_count, m60.failed_count), (2, 2, 0));
+        assert_eq!((ids(&m61.bonfires), ids(&m61.map_points)), (vec![2], vec![3]));
+        assert_eq!((m61.total_count, m61.converted_count, m61.failed_count), (3, 2, 1));
+        assert_eq!(m61.failed_maps, vec!["m21_40_35_00".to_string()]);
+        assert!(m61.bonfires.iter().chain(&m61.map_points).all(|icon| icon.map_id == "m61"));
+    }
+}
@@ -0,0 +1,151 @@
+// Check a WorldMapLegacyConvParam CSV for conversion problems
+//
+// Usage: validate-csv [path/to/WorldMapLegacyConvParam.csv]
+//
+// Prints the anchor / map counts, then tiles that can't reach a global map,
+// tiles only present as anchor destinations, anchors dropped as conflicting
+// duplicates and tiles anchored to several global maps. Exits with 1 when unreachable or ambiguous tiles are found
+// (2 if the CSV can't be loaded), so it can gate dataset changes in CI.
+
+// Include the coordinate_transformer module directly
+// (this tool only uses part of its API)
+#[allow(dead_code)]
+#[path = "../coordinate_transformer.rs"]
+mod coordinate_transformer;
+
+use coordinate_transformer::WorldPositionTransformer;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Exit code when the CSV has unreachable or ambiguous tiles
+const EXIT_PROBLEMS: i32 = 1;
+
+/// Exit code when the CSV can't be loaded
+const EXIT_LOAD_ERROR: i32 = 2;
+
+fn main() {
+    let csv_path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "src/WorldMapLegacyConvParam.csv".to_string());
+
+    let code = match validate_csv(Path::new(&csv_path), &mut io::stdout()) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("ERROR: Failed to write report: {}", e);
+            EXIT_LOAD_ERROR
+        }
+    };
+    std::process::exit(code);
+}
+
+/// Write the diagnostics of `csv_path` to `out` and return the exit code
+fn validate_csv(csv_path: &Path, out: &mut dyn Write) -> io::Result<i32> {
+    let transformer = match WorldPositionTransformer::from_csv(csv_path) {
+        Ok(t) => t,
+        Err(e) => {
+            writeln!(out, "ERROR: Failed to load {:?}: {}", csv_path, e)?;
+            return Ok(EXIT_LOAD_ERROR);
+        }
+    };
+
+    writeln!(out, "=== {:?} ===", csv_path)?;
+    writeln!(out, "{} maps, {} anchors", transformer.map_count(), transformer.anchor_count())?;
+
+    let unreachable = transformer.unreachable_tiles();
+    writeln!(out, "\nUnreachable tiles (no path to a global map): {}", unreachable.len())?;
+    for map_id in &unreachable {
+        writeln!(out, "  {}", WorldPositionTransformer::format_map_id(*map_id))?;
+    }
+
+    // Informational: these convert through the generated inverse anchors
+    let orphans = transformer.orphan_destinations();
+    writeln!(out, "\nOrphan destinations (only reached by inverse anchors): {}", orphans.len())?;
+    for map_id in &orphans {
+        writeln!(out, "  {}", WorldPositionTransformer::format_map_id(*map_id))?;
+    }
+
+    // Informational: the loader keeps the duplicate with the consistent grid
+    let discarded = transformer.discarded_duplicates();
+    writeln!(out, "\nDiscarded duplicate anchors: {}", discarded.len())?;
+    for warning in discarded {
+        writeln!(out, "  WARNING: {}", warning)?;
+    }
+
+    let warnings = transformer.validate_global_separation();
+    writeln!(out, "\nAmbiguous anchors: {}", warnings.len())?;
+    for warning in &warnings {
+        writeln!(out, "  WARNING: {}", warning)?;
+    }
+
+    if unreachable.is_empty() && warnings.is_empty() {
+        writeln!(out, "\nOK")?;
+        Ok(0)
+    } else {
+        writeln!(out, "\nFAILED: {} unreachable, {} ambiguous", unreachable.len(), warnings.len())?;
+        Ok(EXIT_PROBLEMS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validate_fixture(name: &str, csv: &str) -> (i32, String) {
+        let csv_path = std::env::temp_dir().join(format!("validate_csv_{}_{}.csv", name, std::process::id()));
+        std::fs::write(&csv_path, csv).unwrap();
+        let mut out = Vec::new();
+        let code = validate_csv(&csv_path, &mut out).unwrap();
+        std::fs::remove_file(&csv_path).unwrap();
+        (code, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_clean_csv_passes() {
+        // m10_00_00_00 -> m60_40_35_00 (plus its inverse from m60_40_35_00)
+        let (code, report) = validate_fixture(
+            "clean",
+            "header\n\
+             0,0,0,0,0,10,0,0,0,1.0,2.0,3.0,60,40,35,0,100.0,50.0,100.0\n",
+        );
+        assert_eq!(code, 0, "{}", report);
+        assert!(report.contains("2 maps, 2 anchors"));
+        assert!(report.contains("Discarded duplicate anchors: 0"));
+    }
+
+    #[test]
+    fn test_discarded_duplicates_reported() {
+        // Same global destination twice, the first with the grid rounded down
+        let (code, report) = validate_fixture(
+            "duplicates",
+            "header\n\
+             0,0,0,0,0,10,0,0,0,10.0,0.0,10.0,60,40,35,0,300.0,5.0,20.0\n\
+             0,0,0,0,0,10,0,0,0,10.0,0.0,10.0,60,41,35,0,44.0,5.0,20.0\n",
+        );
+        assert_eq!(code, 0, "{}", report);
+        assert!(report.contains("Discarded duplicate anchors: 1"));
+        assert!(report.contains("WARNING: m10_00_00_00 -> m60_40_35_00 at (300.0, 5.0, 20.0)"), "{}", report);
+    }
+
+    #[test]
+    fn test_unreachable_and_ambiguous_tiles_fail() {
+        // m10_00 <-> m10_01 never reach a global map; m12_00 is anchored to m60 and m61
+        let (code, report) = validate_fixture(
+            "problems",
+            "header\n\
+             0,0,0,0,0,10,0,0,0,1.0,2.0,3.0,10,1,0,0,10.0,0.0,10.0\n\
+             0,0,0,0,0,12,0,0,0,1.0,2.0,3.0,60,40,35,0,100.0,50.0,100.0\n\
+             0,0,0,0,0,12,0,0,0,5.0,2.0,3.0,61,45,40,0,100.0,50.0,100.0\n",
+        );
+        assert_eq!(code, EXIT_PROBLEMS);
+        assert!(report.contains("Unreachable tiles (no path to a global map): 2"));
+        assert!(report.contains("  m10_00_00_00\n  m10_01_00_00\n"));
+        assert!(report.contains("WARNING: m12_00_00_00"));
+    }
+
+    #[test]
+    fn test_missing_csv() {
+        let mut out = Vec::new();
+        let code = validate_csv(Path::new("does/not/exist.csv"), &mut out).unwrap();
+        assert_eq!(code, EXIT_LOAD_ERROR);
+    }
+}
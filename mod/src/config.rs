@@ -3,11 +3,15 @@
 
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use windows::Win32::Foundation::HINSTANCE;
 use windows::Win32::System::LibraryLoader::GetModuleFileNameW;
 use windows::Win32::UI::Input::KeyboardAndMouse::GetAsyncKeyState;
 
+use crate::coordinate_transformer::{MapBounds, DEFAULT_GLOBAL_AREAS};
+use crate::route::TELEPORT_SPEED;
+use crate::route_export::AxisMapping;
+
 // =============================================================================
 // KEY CODE MAPPING
 // =============================================================================
@@ -142,12 +146,19 @@ impl Modifiers {
     const VK_MENU: i32 = 0x12; // Alt key
 
     /// Check if the required modifiers are currently held down
+    /// 
+    /// Extra modifiers are ignored, so "ctrl+s" also fires on Ctrl+Shift+S.
     pub fn are_held(&self) -> bool {
         let ctrl_ok = !self.ctrl || Self::is_key_down(Self::VK_CONTROL);
         let shift_ok = !self.shift || Self::is_key_down(Self::VK_SHIFT);
         let alt_ok = !self.alt || Self::is_key_down(Self::VK_MENU);
         ctrl_ok && shift_ok && alt_ok
     }
+    
+    /// Whether every modifier required here is also required by `other`
+    pub fn is_subset_of(&self, other: &Modifiers) -> bool {
+        (!self.ctrl || other.ctrl) && (!self.shift || other.shift) && (!self.alt || other.alt)
+    }
 
     /// Check if a key is currently held down
     fn is_key_down(key_code: i32) -> bool {
@@ -192,11 +203,10 @@ impl Hotkey {
     }
 
     /// Check if this hotkey was just pressed (key edge + modifiers held)
-    pub fn is_just_pressed(&self) -> bool {
-        // Check if main key was just pressed (edge detection)
-        let key_pressed = (unsafe { GetAsyncKeyState(self.key) } as u16 & 1) != 0;
-        // Check if required modifiers are held
-        key_pressed && self.modifiers.are_held()
+    /// 
+    /// `pressed` holds this frame's key edges from `KeyBindings::poll_pressed`.
+    pub fn is_just_pressed(&self, pressed: &[i32]) -> bool {
+        pressed.contains(&self.key) && self.modifiers.are_held()
     }
 }
 
@@ -286,6 +296,26 @@ pub struct KeyBindings {
     pub clear_route: Hotkey,
     /// Key to save recorded route to file
     pub save_route: Hotkey,
+    /// Key to capture a single point (`mode = "manual"`)
+    #[serde(default = "default_capture_point")]
+    pub capture_point: Hotkey,
+    /// Key to stop recording and streaming, flush the realtime client and save
+    #[serde(default = "default_finalize")]
+    pub finalize: Hotkey,
+}
+
+fn default_capture_point() -> Hotkey {
+    Hotkey {
+        key: 0x74, // F5
+        modifiers: Modifiers::default(),
+    }
+}
+
+fn default_finalize() -> Hotkey {
+    Hotkey {
+        key: 0x46, // F
+        modifiers: Modifiers { ctrl: true, shift: true, alt: false },
+    }
 }
 
 impl Default for KeyBindings {
@@ -311,40 +341,200 @@ impl Default for KeyBindings {
                 key: 0x53, // S
                 modifiers: Modifiers { ctrl: true, shift: false, alt: false },
             },
+            capture_point: default_capture_point(),
+            finalize: default_finalize(),
         }
     }
 }
 
+impl KeyBindings {
+    /// All bindings, in no particular order
+    pub fn all(&self) -> [&Hotkey; 7] {
+        [
+            &self.toggle_ui,
+            &self.toggle_recording,
+            &self.toggle_streaming,
+            &self.clear_route,
+            &self.save_route,
+            &self.capture_point,
+            &self.finalize,
+        ]
+    }
+    
+    /// Keys of the bindings pressed since the last poll
+    /// 
+    /// The "pressed since last call" bit clears when read, so it is read once per
+    /// key here; bindings sharing a key with disjoint modifiers (e.g. "ctrl+s" and
+    /// "alt+s") then all see the press. Bindings whose modifiers are a subset of
+    /// another's on the same key ("ctrl+s" and "ctrl+shift+s") both fire, so the
+    /// defaults avoid them.
+    pub fn poll_pressed(&self) -> Vec<i32> {
+        let mut keys: Vec<i32> = self.all().iter().map(|hotkey| hotkey.key).collect();
+        keys.sort_unstable();
+        keys.dedup();
+        keys.retain(|&key| (unsafe { GetAsyncKeyState(key) } as u16 & 1) != 0);
+        keys
+    }
+}
+
 /// Recording settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RecordingSettings {
     /// Interval between position records in milliseconds
     pub record_interval_ms: u64,
+    /// How points are recorded ("interval" or "manual")
+    #[serde(default)]
+    pub mode: RecordMode,
+    /// Only record while in gameplay (not in the main menu / loading)
+    #[serde(default)]
+    pub require_ingame: bool,
+    /// Which position to track (only "player" for now)
+    #[serde(default)]
+    pub position_source: PositionSource,
+    /// Record Sites of Grace passed within this distance (0 = disabled)
+    #[serde(default)]
+    pub snap_to_bonfires_radius: f32,
+    /// Save the route automatically when recording stops
+    #[serde(default)]
+    pub save_on_stop: bool,
+    /// Drop points whose global x/z fall outside these bounds (glitched reads); None = keep all
+    #[serde(default)]
+    pub world_bounds: Option<MapBounds>,
+    /// Store the whole position array (including its two unknown components) in each point
+    #[serde(default)]
+    pub capture_raw_array: bool,
+    /// Implied speed (global units per second) above which a move between two
+    /// points is a teleport: used by route statistics, resampling, adaptive
+    /// recording and the KML export alike
+    #[serde(default = "default_teleport_speed")]
+    pub teleport_speed: f32,
+}
+
+fn default_teleport_speed() -> f32 {
+    TELEPORT_SPEED
 }
 
 impl Default for RecordingSettings {
     fn default() -> Self {
         Self {
             record_interval_ms: 100, // 10 points per second
+            mode: RecordMode::default(),
+            require_ingame: false,
+            position_source: PositionSource::default(),
+            snap_to_bonfires_radius: 0.0,
+            save_on_stop: false,
+            world_bounds: None,
+            capture_raw_array: false,
+            teleport_speed: default_teleport_speed(),
         }
     }
 }
 
+/// How points are added to the route while recording
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordMode {
+    /// Sample every `record_interval_ms` (default)
+    #[default]
+    Interval,
+    /// Only record a point when the `capture_point` key is pressed
+    Manual,
+    /// Sample faster while moving fast, slower while slow or standing still
+    /// 
+    /// The interval is `max_ms / (1 + speed / speed_scale)`, clamped to
+    /// `min_ms`, so `speed_scale` is the speed (units/s) that halves it.
+    Adaptive {
+        min_ms: u64,
+        max_ms: u64,
+        speed_scale: f32,
+    },
+}
+
+/// Position tracked for recording and streaming
+/// 
+/// libeldenring's `Pointers` only expose the player position, so other sources
+/// (e.g. "camera") are rejected when the config is parsed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PositionSource {
+    /// Player character (default)
+    #[default]
+    Player,
+}
+
 /// Output settings for saving routes
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OutputSettings {
     /// Directory where route files will be saved
     pub routes_directory: String,
+    /// Split saved routes into files of at most this many minutes (0 = single file)
+    #[serde(default)]
+    pub split_minutes: u64,
+    /// Save one file per map tile segment (takes precedence over `split_minutes`)
+    #[serde(default)]
+    pub split_by_map: bool,
+    /// Round saved coordinates to this many decimals (None = full precision)
+    #[serde(default)]
+    pub coordinate_decimals: Option<u32>,
 }
 
 impl Default for OutputSettings {
     fn default() -> Self {
         Self {
             routes_directory: "routes".to_string(),
+            split_minutes: 0,
+            split_by_map: false,
+            coordinate_decimals: None,
+        }
+    }
+}
+
+/// Header used to send the push key to the backend
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AuthHeader {
+    /// `X-Push-Key: <key>` (default)
+    #[default]
+    #[serde(rename = "x-push-key")]
+    XPushKey,
+    /// `Authorization: Bearer <key>` (for proxies that strip custom headers)
+    #[serde(rename = "bearer")]
+    Bearer,
+}
+
+impl AuthHeader {
+    /// Build the (header name, header value) pair for a push key
+    pub fn header(&self, push_key: &str) -> (&'static str, String) {
+        match self {
+            AuthHeader::XPushKey => ("X-Push-Key", push_key.to_string()),
+            AuthHeader::Bearer => ("Authorization", format!("Bearer {}", push_key)),
         }
     }
 }
 
+/// How points are sent to the backend
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RealtimeTransport {
+    /// One POST per batch of points (default)
+    #[default]
+    Batch,
+    /// One long-lived chunked POST with a JSON line per point
+    HttpStream,
+    /// A JSON line per point written to a local named pipe (Windows) or Unix socket
+    Ipc { path: String },
+}
+
+/// Encoding of point batches sent with the `batch` transport
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PayloadFormat {
+    /// JSON array of points (default)
+    #[default]
+    Json,
+    /// Protobuf `RoutePointBatch` (see `realtime_client` for the schema)
+    Protobuf,
+}
+
 /// Real-time streaming settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RealtimeSettings {
@@ -354,6 +544,42 @@ pub struct RealtimeSettings {
     pub backend_url: String,
     /// Push key for sending route points (get one from the backend)
     pub push_key: Option<String>,
+    /// Send the push key; false for local backends without authentication
+    #[serde(default = "default_auth_required")]
+    pub auth_required: bool,
+    /// Header used to send the push key ("x-push-key" or "bearer")
+    #[serde(default)]
+    pub auth_header: AuthHeader,
+    /// How points are sent ("batch" or "http_stream")
+    #[serde(default)]
+    pub transport: RealtimeTransport,
+    /// Encoding of point batches ("json" or "protobuf"), batch transport only
+    #[serde(default)]
+    pub payload_format: PayloadFormat,
+    /// Send quantized deltas between points instead of full coordinates
+    #[serde(default)]
+    pub delta_encoding: bool,
+    /// Stop sending positions while the overlay is hidden (recording continues)
+    #[serde(default)]
+    pub pause_when_ui_hidden: bool,
+    /// Longest wait (seconds) honored from a rate-limited response's `Retry-After`
+    #[serde(default = "default_max_retry_after_secs")]
+    pub max_retry_after_secs: u64,
+    /// Queued points above which streaming slows down until the backlog drains (0 = never)
+    #[serde(default = "default_throttle_queue_depth")]
+    pub throttle_queue_depth: usize,
+}
+
+fn default_auth_required() -> bool {
+    true
+}
+
+fn default_max_retry_after_secs() -> u64 {
+    30
+}
+
+fn default_throttle_queue_depth() -> usize {
+    200
 }
 
 impl Default for RealtimeSettings {
@@ -362,6 +588,58 @@ impl Default for RealtimeSettings {
             enabled: false,
             backend_url: "http://localhost:5000".to_string(),
             push_key: None,
+            auth_required: default_auth_required(),
+            auth_header: AuthHeader::default(),
+            transport: RealtimeTransport::default(),
+            payload_format: PayloadFormat::default(),
+            delta_encoding: false,
+            pause_when_ui_hidden: false,
+            max_retry_after_secs: default_max_retry_after_secs(),
+            throttle_queue_depth: default_throttle_queue_depth(),
+        }
+    }
+}
+
+/// Coordinate conversion settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversionSettings {
+    /// Convert tiles without anchors through the nearest tile of the same area (best effort)
+    #[serde(default)]
+    pub borrow_neighbor_anchors: bool,
+    /// Precompute conversion paths on all CPU cores when loading the CSV
+    #[serde(default)]
+    pub parallel_precompute: bool,
+    /// Global maps conversions may end on (60 = Lands Between, 61 = Shadow Realm)
+    #[serde(default = "default_allowed_global_maps")]
+    pub allowed_global_maps: Vec<u8>,
+}
+
+fn default_allowed_global_maps() -> Vec<u8> {
+    DEFAULT_GLOBAL_AREAS.to_vec()
+}
+
+impl Default for ConversionSettings {
+    fn default() -> Self {
+        Self {
+            borrow_neighbor_anchors: false,
+            parallel_precompute: false,
+            allowed_global_maps: default_allowed_global_maps(),
+        }
+    }
+}
+
+/// Route export settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportSettings {
+    /// How game axes map to east/north/up in exported files
+    #[serde(default)]
+    pub axis_mapping: AxisMapping,
+}
+
+impl Default for ExportSettings {
+    fn default() -> Self {
+        Self {
+            axis_mapping: AxisMapping::default(),
         }
     }
 }
@@ -378,6 +656,12 @@ pub struct Config {
     /// Real-time streaming settings
     #[serde(default)]
     pub realtime: RealtimeSettings,
+    /// Coordinate conversion settings
+    #[serde(default)]
+    pub conversion: ConversionSettings,
+    /// Route export settings
+    #[serde(default)]
+    pub export: ExportSettings,
 }
 
 impl Default for Config {
@@ -387,6 +671,8 @@ impl Default for Config {
             recording: RecordingSettings::default(),
             output: OutputSettings::default(),
             realtime: RealtimeSettings::default(),
+            conversion: ConversionSettings::default(),
+            export: ExportSettings::default(),
         }
     }
 }
@@ -406,6 +692,8 @@ pub enum ConfigError {
     ReadError(std::io::Error),
     /// Failed to parse the config file
     ParseError(toml::de::Error),
+    /// Failed to parse a JSON config file
+    JsonParseError(serde_json::Error),
 }
 
 impl std::fmt::Display for ConfigError {
@@ -417,6 +705,7 @@ impl std::fmt::Display for ConfigError {
             }
             ConfigError::ReadError(e) => write!(f, "Failed to read config file: {}", e),
             ConfigError::ParseError(e) => write!(f, "Failed to parse config file: {}", e),
+            ConfigError::JsonParseError(e) => write!(f, "Failed to parse config file: {}", e),
         }
     }
 }
@@ -425,6 +714,9 @@ impl Config {
     /// Config file name
     pub const CONFIG_FILENAME: &'static str = "route_tracker_config.toml";
 
+    /// Environment variable overriding the config file path
+    pub const CONFIG_PATH_ENV: &'static str = "ER_ROUTE_CONFIG";
+
     /// Get the DLL's directory path using its HMODULE
     pub fn get_dll_directory(hmodule: HINSTANCE) -> Option<PathBuf> {
         let mut buffer = [0u16; 260]; // MAX_PATH
@@ -439,28 +731,209 @@ impl Config {
         path.parent().map(|p| p.to_path_buf())
     }
 
-    /// Get the config file path (next to the DLL)
+    /// Get the config file path from the `ER_ROUTE_CONFIG` environment variable, if set
+    pub fn env_config_path() -> Option<PathBuf> {
+        std::env::var_os(Self::CONFIG_PATH_ENV)
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+    }
+
+    /// Get the config file path (`ER_ROUTE_CONFIG` if set, otherwise next to the DLL)
+    ///
+    /// Next to the DLL, `route_tracker_config.json` is used when there is no `.toml` file.
     pub fn config_path(hmodule: HINSTANCE) -> Option<PathBuf> {
+        if let Some(path) = Self::env_config_path() {
+            return Some(path);
+        }
+
         let dir = Self::get_dll_directory(hmodule)?;
-        Some(dir.join(Self::CONFIG_FILENAME))
+        let toml_path = dir.join(Self::CONFIG_FILENAME);
+        let json_path = toml_path.with_extension("json");
+        if !toml_path.exists() && json_path.exists() {
+            return Some(json_path);
+        }
+        Some(toml_path)
     }
 
-    /// Load configuration from file next to the DLL
+    /// Load configuration from `ER_ROUTE_CONFIG` or the file next to the DLL
     /// Returns an error if the file does not exist or cannot be parsed
     pub fn load(hmodule: HINSTANCE) -> Result<Self, ConfigError> {
         let config_path = Self::config_path(hmodule).ok_or(ConfigError::PathError)?;
+        Self::load_from_path(&config_path)
+    }
 
+    /// Load configuration from a given file
+    ///
+    /// `.json` files are parsed as JSON, anything else as TOML.
+    pub fn load_from_path(config_path: &Path) -> Result<Self, ConfigError> {
         hudhook::tracing::info!("Looking for config at: {}", config_path.display());
 
         if !config_path.exists() {
-            return Err(ConfigError::FileNotFound(config_path));
+            return Err(ConfigError::FileNotFound(config_path.to_path_buf()));
         }
 
-        let contents = fs::read_to_string(&config_path).map_err(ConfigError::ReadError)?;
+        let contents = fs::read_to_string(config_path).map_err(ConfigError::ReadError)?;
 
-        let config: Config = toml::from_str(&contents).map_err(ConfigError::ParseError)?;
+        let is_json = config_path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+        let config: Config = if is_json {
+            serde_json::from_str(&contents).map_err(ConfigError::JsonParseError)?
+        } else {
+            toml::from_str(&contents).map_err(ConfigError::ParseError)?
+        };
 
         hudhook::tracing::info!("Loaded config from {}", config_path.display());
         Ok(config)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auth_header_default_is_push_key() {
+        let settings: RealtimeSettings = toml::from_str(
+            "enabled = true\nbackend_url = \"http://localhost:5000\"\npush_key = \"abc\"",
+        )
+        .unwrap();
+        assert_eq!(settings.auth_header, AuthHeader::XPushKey);
+        assert_eq!(
+            settings.auth_header.header("abc"),
+            ("X-Push-Key", "abc".to_string())
+        );
+    }
+
+    #[test]
+    fn test_auth_header_bearer() {
+        let settings: RealtimeSettings = toml::from_str(
+            "enabled = true\nbackend_url = \"http://localhost:5000\"\nauth_header = \"bearer\"",
+        )
+        .unwrap();
+        assert_eq!(settings.auth_header, AuthHeader::Bearer);
+        assert_eq!(
+            settings.auth_header.header("abc"),
+            ("Authorization", "Bearer abc".to_string())
+        );
+    }
+
+    #[test]
+    fn test_config_path_from_env() {
+        let config_path = std::env::temp_dir()
+            .join(format!("route_tracker_config_test_{}.toml", std::process::id()));
+        let mut contents = toml::to_string(&Config::default()).unwrap();
+        contents = contents.replace("record_interval_ms = 100", "record_interval_ms = 250");
+        fs::write(&config_path, contents).unwrap();
+
+        std::env::set_var(Config::CONFIG_PATH_ENV, &config_path);
+        let env_path = Config::env_config_path();
+        std::env::remove_var(Config::CONFIG_PATH_ENV);
+
+        assert_eq!(env_path.as_deref(), Some(config_path.as_path()));
+        let config = Config::load_from_path(&config_path).unwrap();
+        assert_eq!(config.recording.record_interval_ms, 250);
+
+        fs::remove_file(&config_path).unwrap();
+    }
+
+    #[test]
+    fn test_load_toml_and_json() {
+        let mut settings = Config::default();
+        settings.recording.record_interval_ms = 250;
+        settings.keybindings.toggle_ui = parse_hotkey("ctrl+f10").unwrap();
+        settings.realtime.auth_header = AuthHeader::Bearer;
+        settings.export.axis_mapping = AxisMapping::NorthEastUp;
+
+        let base = std::env::temp_dir().join(format!("route_tracker_config_format_{}", std::process::id()));
+        let toml_path = base.with_extension("toml");
+        let json_path = base.with_extension("json");
+        fs::write(&toml_path, toml::to_string(&settings).unwrap()).unwrap();
+        fs::write(&json_path, serde_json::to_string_pretty(&settings).unwrap()).unwrap();
+
+        let from_toml = Config::load_from_path(&toml_path).unwrap();
+        let from_json = Config::load_from_path(&json_path).unwrap();
+        fs::remove_file(&toml_path).unwrap();
+        fs::remove_file(&json_path).unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&from_toml).unwrap(),
+            serde_json::to_value(&from_json).unwrap()
+        );
+        assert_eq!(from_json.recording.record_interval_ms, 250);
+        assert_eq!(from_json.keybindings.toggle_ui.name(), "Ctrl+F10");
+    }
+
+    #[test]
+    fn test_axis_mapping_presets_and_matrix() {
+        let settings: ExportSettings = toml::from_str("axis_mapping = \"east_south_up\"").unwrap();
+        assert_eq!(settings.axis_mapping, AxisMapping::EastSouthUp);
+
+        let settings: ExportSettings =
+            toml::from_str("axis_mapping = { custom = [[1, 0, 0], [0, 0, -1], [0, 1, 0]] }").unwrap();
+        assert_eq!(
+            settings.axis_mapping,
+            AxisMapping::Custom([[1.0, 0.0, 0.0], [0.0, 0.0, -1.0], [0.0, 1.0, 0.0]])
+        );
+
+        let settings: ExportSettings = toml::from_str("").unwrap();
+        assert_eq!(settings.axis_mapping, AxisMapping::EastNorthUp);
+    }
+
+    #[test]
+    fn test_ipc_transport_from_toml() {
+        let realtime: RealtimeSettings = toml::from_str(
+            r#"
+            enabled = true
+            backend_url = "http://localhost:5000"
+            transport = { ipc = { path = '\\.\pipe\er-route-tracker' } }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(realtime.transport, RealtimeTransport::Ipc { path: r"\\.\pipe\er-route-tracker".to_string() });
+    }
+
+    #[test]
+    fn test_adaptive_record_mode_from_toml() {
+        let settings: RecordingSettings = toml::from_str(
+            "record_interval_ms = 100\nmode = { adaptive = { min_ms = 50, max_ms = 1000, speed_scale = 5.0 } }",
+        )
+        .unwrap();
+        assert_eq!(
+            settings.mode,
+            RecordMode::Adaptive { min_ms: 50, max_ms: 1000, speed_scale: 5.0 }
+        );
+    }
+
+    #[test]
+    fn test_camera_position_source_rejected() {
+        let settings: RecordingSettings =
+            toml::from_str("record_interval_ms = 100\nposition_source = \"player\"").unwrap();
+        assert_eq!(settings.position_source, PositionSource::Player);
+
+        let error = toml::from_str::<RecordingSettings>("record_interval_ms = 100\nposition_source = \"camera\"")
+            .unwrap_err();
+        assert!(error.to_string().contains("unknown variant `camera`"), "{}", error);
+    }
+
+    #[test]
+    fn test_default_bindings_do_not_shadow_each_other() {
+        // Modifiers are a minimum: a binding whose modifiers are a subset of
+        // another's on the same key fires along with it
+        let shipped: Config = toml::from_str(include_str!("../route_tracker_config.toml")).unwrap();
+        for bindings in [KeyBindings::default(), shipped.keybindings] {
+            let all = bindings.all();
+            for (i, a) in all.iter().enumerate() {
+                for b in &all[i + 1..] {
+                    assert!(
+                        a.key != b.key
+                            || !(a.modifiers.is_subset_of(&b.modifiers) || b.modifiers.is_subset_of(&a.modifiers)),
+                        "{} collides with {}",
+                        a.name(),
+                        b.name()
+                    );
+                }
+            }
+        }
+    }
+}
@@ -6,7 +6,13 @@
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs::File;
 use std::io::{BufRead, BufReader};
+use std::num::NonZeroUsize;
 use std::path::Path;
+use std::thread;
+
+use flate2::read::GzDecoder;
+use hudhook::tracing::warn;
+use serde::{Deserialize, Serialize};
 
 // =============================================================================
 // DATA STRUCTURES
@@ -25,6 +31,17 @@ pub struct Anchor {
     pub dst_grid_z: u8,
     /// Destination position (local to the m60 tile, NOT global!)
     pub dst_pos: (f32, f32, f32),
+    /// Where this anchor comes from (CSV row, or generated inverse)
+    pub origin: AnchorOrigin,
+}
+
+/// Where an anchor comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnchorOrigin {
+    /// Parsed from a CSV row (or added with `update_anchor`)
+    Csv,
+    /// Generated by inverting another anchor
+    Inverse,
 }
 
 /// A step in a path from a tile to m60
@@ -43,8 +60,32 @@ struct PathToGlobalMap {
     final_global_tile: (u8, u8, u8),
 }
 
+/// How a map tile is converted to global coordinates (resolved once per tile)
+#[derive(Debug, Clone, Copy)]
+enum Conversion<'a> {
+    /// Tile already on a global map: grid formula only
+    GlobalTile { area_no: u8, grid_x: u8, grid_z: u8 },
+    /// Single anchor to a global map tile
+    /// 
+    /// `grid_offset` is added to local x/z first (non-zero when borrowing a neighbor tile's anchor).
+    DirectAnchor { anchor: &'a Anchor, global_map_id: u8, grid_offset: (f32, f32) },
+    /// Pre-computed multi-step path to a global map tile
+    Path { path: &'a PathToGlobalMap, global_map_id: u8, grid_offset: (f32, f32) },
+}
+
+impl Conversion<'_> {
+    /// Number of anchors applied by this conversion (0 for global tiles)
+    fn hops(&self) -> usize {
+        match self {
+            Conversion::GlobalTile { .. } => 0,
+            Conversion::DirectAnchor { .. } => 1,
+            Conversion::Path { path, .. } => path.steps.len(),
+        }
+    }
+}
+
 /// Error type for coordinate transformation
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum TransformError {
     UnknownMap(String),
     IoError(String),
@@ -59,16 +100,133 @@ impl std::fmt::Display for TransformError {
     }
 }
 
+/// Suspicious anchor data found by validation
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnchorWarning {
+    /// Interior tile (packed map_id) with direct anchors to several global maps
+    MultipleGlobalMaps { map_id: u32, global_areas: Vec<u8> },
+    /// Anchor dropped at load time as a duplicate of another anchor of the same tile
+    /// with a different destination grid (packed map_ids, destination local position)
+    DiscardedDuplicate { map_id: u32, dst_map_id: u32, dst_pos: (f32, f32, f32) },
+}
+
+impl std::fmt::Display for AnchorWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnchorWarning::MultipleGlobalMaps { map_id, global_areas } => {
+                let areas: Vec<String> = global_areas.iter().map(|area| format!("m{:02}", area)).collect();
+                write!(
+                    f,
+                    "{} has direct anchors to several global maps: {}",
+                    WorldPositionTransformer::format_map_id(*map_id),
+                    areas.join(", ")
+                )
+            }
+            AnchorWarning::DiscardedDuplicate { map_id, dst_map_id, dst_pos } => write!(
+                f,
+                "{} -> {} at {:?} discarded as a conflicting duplicate",
+                WorldPositionTransformer::format_map_id(*map_id),
+                WorldPositionTransformer::format_map_id(*dst_map_id),
+                dst_pos
+            ),
+        }
+    }
+}
+
+/// How a tile resolves to a global map (see `tile_report`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    /// Single anchor to a global map tile
+    Direct,
+    /// Pre-computed path with this many anchors
+    Path(usize),
+    /// No way to a global map
+    None,
+}
+
+/// Anchor coverage of a source tile
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TileReport {
+    /// Packed map_id of the tile (0xWWXXYY00)
+    pub map_id: u32,
+    /// Anchors starting from this tile (generated inverses included)
+    pub anchor_count: usize,
+    pub resolution: Resolution,
+}
+
+/// Extent of a global map in world coordinates (x/z plane)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MapBounds {
+    pub min_x: f32,
+    pub max_x: f32,
+    pub min_z: f32,
+    pub max_z: f32,
+}
+
+impl MapBounds {
+    /// Known world bounds of the map images used by the website (m62 shares the m60 space)
+    pub fn default_for(global_map_id: u8) -> Option<Self> {
+        match global_map_id {
+            60 | 62 => Some(Self { min_x: 7042.0, max_x: 16687.0, min_z: 7387.0, max_z: 16506.0 }),
+            61 => Some(Self { min_x: 10078.0, max_x: 14957.0, min_z: 8708.0, max_z: 14648.0 }),
+            _ => None,
+        }
+    }
+    
+    /// Position as `(u, v)` fractions of the extent, clamped to `[0,1]`
+    /// 
+    /// `u` grows with x (west to east), `v` grows as z decreases (north to south),
+    /// matching pixel rows of the map image.
+    pub fn normalize(&self, x: f32, z: f32) -> (f32, f32) {
+        let u = (x - self.min_x) / (self.max_x - self.min_x);
+        let v = (self.max_z - z) / (self.max_z - self.min_z);
+        (u.clamp(0.0, 1.0), v.clamp(0.0, 1.0))
+    }
+    
+    /// Whether a position lies within the extent (bounds included, NaN never does)
+    pub fn contains(&self, x: f32, z: f32) -> bool {
+        (self.min_x..=self.max_x).contains(&x) && (self.min_z..=self.max_z).contains(&z)
+    }
+}
+
 // =============================================================================
 // WORLD POSITION TRANSFORMER
 // =============================================================================
 
+/// First bytes of a gzip stream
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Number of CSV columns needed to read an anchor (last one used is dstPosZ, index 18)
+const MIN_CSV_FIELDS: usize = 19;
+
+/// Default global map areas (60 = Lands Between, 61 = Shadow Realm), in order of preference
+pub const DEFAULT_GLOBAL_AREAS: &[u8] = &[60, 61];
+
+/// Width of a map tile in world units, for areas without a `with_tile_size` override
+pub const DEFAULT_TILE_SIZE: f32 = 256.0;
+
 /// Transforms local coordinates to world coordinates
 pub struct WorldPositionTransformer {
     /// Lookup table: (area_no, grid_x, grid_z) -> list of anchors
     anchors: HashMap<(u8, u8, u8), Vec<Anchor>>,
     /// Pre-computed paths to global maps (m60 or m61) for tiles without direct links
     paths_to_global: HashMap<(u8, u8, u8), PathToGlobalMap>,
+    /// Area numbers treated as global maps (grid formula applies, BFS terminates there)
+    global_areas: Vec<u8>,
+    /// Accumulate anchor offsets in f64 (converted back to f32 at the end)
+    high_precision: bool,
+    /// Convert tiles without anchors through the nearest tile of the same area
+    borrow_neighbor_anchors: bool,
+    /// World bounds overriding `MapBounds::default_for`, by global map id
+    map_bounds: HashMap<u8, MapBounds>,
+    /// Tile width overriding `DEFAULT_TILE_SIZE`, by area number
+    tile_sizes: HashMap<u8, f32>,
+    /// Spread the path search over all CPU cores when (re)loading the CSV
+    parallel_precompute: bool,
+    /// Global maps conversions may end on (None = any of `global_areas`)
+    allowed_global_maps: Option<Vec<u8>>,
+    /// Anchors dropped by the duplicate check, in load order
+    discarded_duplicates: Vec<AnchorWarning>,
 }
 
 impl WorldPositionTransformer {
@@ -77,16 +235,55 @@ impl WorldPositionTransformer {
         Self {
             anchors: HashMap::new(),
             paths_to_global: HashMap::new(),
+            global_areas: DEFAULT_GLOBAL_AREAS.to_vec(),
+            high_precision: false,
+            borrow_neighbor_anchors: false,
+            map_bounds: HashMap::new(),
+            tile_sizes: HashMap::new(),
+            parallel_precompute: false,
+            allowed_global_maps: None,
+            discarded_duplicates: Vec::new(),
         }
     }
     
+    /// Test transformer from hand-built anchors and paths, all settings at their defaults
+    #[cfg(test)]
+    fn from_parts(
+        anchors: HashMap<(u8, u8, u8), Vec<Anchor>>,
+        paths_to_global: HashMap<(u8, u8, u8), PathToGlobalMap>,
+    ) -> Self {
+        Self { anchors, paths_to_global, ..Self::empty() }
+    }
+    
     /// Create a new transformer by loading the CSV file
+    /// 
+    /// Gzip-compressed files (`.csv.gz`) are decompressed transparently.
     pub fn from_csv<P: AsRef<Path>>(csv_path: P) -> Result<Self, TransformError> {
-        let file = File::open(csv_path.as_ref()).map_err(|e| {
-            TransformError::IoError(format!("Failed to open CSV: {}", e))
-        })?;
-        
-        let reader = BufReader::new(file);
+        Self::from_csv_with_global_areas(csv_path, DEFAULT_GLOBAL_AREAS)
+    }
+    
+    /// Create a new transformer by loading the CSV file, with a custom set of global areas
+    /// 
+    /// `global_areas` lists the area numbers that are treated as global maps, in order of
+    /// preference when a tile has direct anchors to several of them.
+    pub fn from_csv_with_global_areas<P: AsRef<Path>>(
+        csv_path: P,
+        global_areas: &[u8],
+    ) -> Result<Self, TransformError> {
+        Self::from_csv_with_options(csv_path, global_areas, false)
+    }
+    
+    /// Create a new transformer by loading the CSV file, choosing how paths are precomputed
+    /// 
+    /// With `parallel_precompute`, the per-tile path searches run on all CPU cores
+    /// (the bulk of the load time for a full CSV). The result is the same as the
+    /// serial search; the setting is kept for `reload_csv`.
+    pub fn from_csv_with_options<P: AsRef<Path>>(
+        csv_path: P,
+        global_areas: &[u8],
+        parallel_precompute: bool,
+    ) -> Result<Self, TransformError> {
+        let reader = Self::open_csv(csv_path.as_ref())?;
         let mut anchors: HashMap<(u8, u8, u8), Vec<Anchor>> = HashMap::new();
         
         for (line_num, line_result) in reader.lines().enumerate() {
@@ -110,9 +307,10 @@ impl WorldPositionTransformer {
             // We need at least these columns:
             // 5: srcAreaNo, 6: srcGridXNo, 7: srcGridZNo
             // 9: srcPosX, 10: srcPosY, 11: srcPosZ
-            // 12: dstAreaNo
+            // 12: dstAreaNo, 13: dstGridXNo, 14: dstGridZNo
             // 16: dstPosX, 17: dstPosY, 18: dstPosZ
-            if fields.len() < 18 {
+            // Shorter lines are skipped
+            if fields.len() < MIN_CSV_FIELDS {
                 continue;
             }
             
@@ -179,6 +377,7 @@ impl WorldPositionTransformer {
                 dst_grid_x,
                 dst_grid_z,
                 dst_pos: (dst_pos_x, dst_pos_y, dst_pos_z),
+                origin: AnchorOrigin::Csv,
             };
             
             anchors.entry(key).or_default().push(anchor);
@@ -186,19 +385,138 @@ impl WorldPositionTransformer {
         
         // Generate inverse mappings for bidirectional navigation
         // This allows finding tiles that are only referenced as destinations (like m10_01_00_00)
-        Self::add_inverse_anchors(&mut anchors);
+        let discarded_duplicates = Self::add_inverse_anchors(&mut anchors);
         
         // Pre-compute paths to global maps (m60 or m61) for all tiles without direct links
-        let paths_to_global = Self::precompute_paths_to_global(&anchors);
+        let paths_to_global = if parallel_precompute {
+            Self::precompute_paths_to_global_parallel(&anchors, global_areas, global_areas)
+        } else {
+            Self::precompute_paths_to_global(&anchors, global_areas, global_areas)
+        };
+        
+        Ok(Self {
+            anchors,
+            paths_to_global,
+            global_areas: global_areas.to_vec(),
+            high_precision: false,
+            borrow_neighbor_anchors: false,
+            map_bounds: HashMap::new(),
+            tile_sizes: HashMap::new(),
+            parallel_precompute,
+            allowed_global_maps: None,
+            discarded_duplicates,
+        })
+    }
+    
+    /// Open a CSV file for reading, decompressing it if it starts with the gzip header
+    fn open_csv(csv_path: &Path) -> Result<Box<dyn BufRead>, TransformError> {
+        let file = File::open(csv_path).map_err(|e| {
+            TransformError::IoError(format!("Failed to open CSV: {}", e))
+        })?;
         
-        Ok(Self { anchors, paths_to_global })
+        // Check the magic bytes rather than the extension, so renamed files work too
+        let mut reader = BufReader::new(file);
+        let is_gzip = reader
+            .fill_buf()
+            .map(|buf| buf.starts_with(&GZIP_MAGIC))
+            .map_err(|e| TransformError::IoError(format!("Failed to read CSV: {}", e)))?;
+        
+        if is_gzip {
+            Ok(Box::new(BufReader::new(GzDecoder::new(reader))))
+        } else {
+            Ok(Box::new(reader))
+        }
+    }
+    
+    /// Enable or disable f64 computation of anchor and path offsets
+    /// 
+    /// In f32, subtracting and adding large anchor positions and `* 256.0` grid offsets
+    /// loses sub-unit precision far from the origin. In high precision mode the whole
+    /// chain is computed in f64 and rounded to f32 only once at the end.
+    pub fn with_high_precision(mut self, enabled: bool) -> Self {
+        self.high_precision = enabled;
+        self
+    }
+    
+    /// Enable or disable borrowing anchors from neighboring tiles
+    /// 
+    /// When a tile has neither a direct anchor nor a path to a global map, the nearest
+    /// convertible tile of the same area is used instead, shifting the local position
+    /// by the grid difference (assuming 256-unit tiles). Best effort: the result is only
+    /// right if both tiles share the same layout.
+    pub fn with_neighbor_fallback(mut self, enabled: bool) -> Self {
+        self.borrow_neighbor_anchors = enabled;
+        self
+    }
+    
+    /// Override the world bounds used by `normalized_position` for a global map
+    pub fn with_map_bounds(mut self, global_map_id: u8, bounds: MapBounds) -> Self {
+        self.map_bounds.insert(global_map_id, bounds);
+        self
+    }
+    
+    /// Override the tile width (world units per grid step) of an area
+    /// 
+    /// Used for the grid offsets of tiles in that area: global map tiles and anchor
+    /// destinations in it, and neighbor fallback shifts. Duplicate anchor detection
+    /// at load time keeps assuming `DEFAULT_TILE_SIZE`.
+    pub fn with_tile_size(mut self, area_no: u8, tile_size: f32) -> Self {
+        self.tile_sizes.insert(area_no, tile_size);
+        self
+    }
+    
+    /// Only convert to the given global maps (area numbers, e.g. `[60]` for base game only)
+    /// 
+    /// Tiles of other global maps and anchors to them are skipped, and paths are
+    /// searched again so they only end on allowed maps: a tile with a direct anchor
+    /// to a disallowed map still converts through a longer path to an allowed one.
+    /// A tile that can only reach disallowed maps fails with `UnknownMap`.
+    pub fn with_allowed_global_maps(mut self, global_maps: &[u8]) -> Self {
+        self.allowed_global_maps = Some(global_maps.to_vec());
+        self.rebuild_paths();
+        self
+    }
+    
+    /// Whether conversions may end on this global map
+    fn is_global_map_allowed(&self, area_no: u8) -> bool {
+        self.allowed_global_maps
+            .as_ref()
+            .is_none_or(|allowed| allowed.contains(&area_no))
+    }
+    
+    /// Global areas path searches may end on (`global_areas` minus disallowed maps)
+    fn path_targets(&self) -> Vec<u8> {
+        self.global_areas
+            .iter()
+            .copied()
+            .filter(|&area| self.is_global_map_allowed(area))
+            .collect()
+    }
+    
+    /// Search every path again, ending only on allowed global maps
+    fn rebuild_paths(&mut self) {
+        let targets = self.path_targets();
+        self.paths_to_global = if self.parallel_precompute {
+            Self::precompute_paths_to_global_parallel(&self.anchors, &self.global_areas, &targets)
+        } else {
+            Self::precompute_paths_to_global(&self.anchors, &self.global_areas, &targets)
+        };
+    }
+    
+    /// Tile width of an area (`DEFAULT_TILE_SIZE` unless overridden)
+    pub fn tile_size(&self, area_no: u8) -> f32 {
+        self.tile_sizes.get(&area_no).copied().unwrap_or(DEFAULT_TILE_SIZE)
     }
     
     /// Add inverse anchors for bidirectional navigation
     /// 
     /// For each anchor A → B, creates an inverse anchor B → A if it doesn't already exist.
     /// This enables finding paths to m60 for tiles that have no direct source entry in the CSV.
-    fn add_inverse_anchors(anchors: &mut HashMap<(u8, u8, u8), Vec<Anchor>>) {
+    /// Returns the conflicting duplicates dropped beforehand.
+    fn add_inverse_anchors(anchors: &mut HashMap<(u8, u8, u8), Vec<Anchor>>) -> Vec<AnchorWarning> {
+        // Drop conflicting duplicates first so they don't get inverses of their own
+        let discarded = Self::drop_conflicting_duplicates(anchors);
+        
         // Collect all inverse anchors to add (to avoid modifying while iterating)
         let mut inverses_to_add: Vec<((u8, u8, u8), Anchor)> = Vec::new();
         
@@ -208,13 +526,7 @@ impl WorldPositionTransformer {
                 let inverse_key = (anchor.dst_area_no, anchor.dst_grid_x, anchor.dst_grid_z);
                 
                 // Create the inverse anchor (swap src and dst)
-                let inverse_anchor = Anchor {
-                    src_pos: anchor.dst_pos,
-                    dst_area_no: src_area_no,
-                    dst_grid_x: src_grid_x,
-                    dst_grid_z: src_grid_z,
-                    dst_pos: anchor.src_pos,
-                };
+                let inverse_anchor = Self::inverse_of((src_area_no, src_grid_x, src_grid_z), anchor);
                 
                 inverses_to_add.push((inverse_key, inverse_anchor));
             }
@@ -225,18 +537,128 @@ impl WorldPositionTransformer {
             let existing_list = anchors.entry(key).or_default();
             
             // Check if this inverse already exists (same destination and positions)
-            let already_exists = existing_list.iter().any(|existing| {
-                existing.dst_area_no == inverse_anchor.dst_area_no
-                    && existing.dst_grid_x == inverse_anchor.dst_grid_x
-                    && existing.dst_grid_z == inverse_anchor.dst_grid_z
-                    && Self::positions_equal(existing.src_pos, inverse_anchor.src_pos)
-                    && Self::positions_equal(existing.dst_pos, inverse_anchor.dst_pos)
-            });
-            
-            if !already_exists {
+            if !Self::contains_anchor(existing_list, &inverse_anchor) {
                 existing_list.push(inverse_anchor);
             }
         }
+        
+        discarded
+    }
+    
+    /// The generated anchor going back from the destination of `anchor` to `src_key`
+    fn inverse_of(src_key: (u8, u8, u8), anchor: &Anchor) -> Anchor {
+        Anchor {
+            src_pos: anchor.dst_pos,
+            dst_area_no: src_key.0,
+            dst_grid_x: src_key.1,
+            dst_grid_z: src_key.2,
+            dst_pos: anchor.src_pos,
+            origin: AnchorOrigin::Inverse,
+        }
+    }
+    
+    /// Whether `list` already holds an anchor with the same destination and positions
+    fn contains_anchor(list: &[Anchor], anchor: &Anchor) -> bool {
+        list.iter().any(|existing| Self::same_anchor(existing, anchor))
+    }
+    
+    /// Whether two anchors have the same destination and positions (origin ignored)
+    fn same_anchor(a: &Anchor, b: &Anchor) -> bool {
+        a.dst_area_no == b.dst_area_no
+            && a.dst_grid_x == b.dst_grid_x
+            && a.dst_grid_z == b.dst_grid_z
+            && Self::positions_equal(a.src_pos, b.src_pos)
+            && Self::positions_equal(a.dst_pos, b.dst_pos)
+    }
+    
+    /// Keep one anchor out of each group of duplicates that only differ in destination grid
+    /// 
+    /// Two anchors from the same tile to the same area are duplicates when their source
+    /// positions match and their destinations match either locally or globally (a grid
+    /// index off by one with the local position shifted by a tile). The anchor whose
+    /// destination position lies closest to its own tile is kept; on a tie the first one
+    /// wins. Discarded anchors are logged and returned, sorted by tile.
+    fn drop_conflicting_duplicates(anchors: &mut HashMap<(u8, u8, u8), Vec<Anchor>>) -> Vec<AnchorWarning> {
+        let mut tiles: Vec<_> = anchors.iter_mut().collect();
+        tiles.sort_unstable_by_key(|(&tile_key, _)| tile_key);
+        
+        let mut discarded_duplicates = Vec::new();
+        for (&key, anchor_list) in tiles {
+            let mut kept: Vec<Anchor> = Vec::with_capacity(anchor_list.len());
+            
+            for anchor in anchor_list.drain(..) {
+                if let Some(discarded) = Self::keep_better_anchor(&mut kept, anchor) {
+                    discarded_duplicates.push(Self::discard_warning(key, &discarded));
+                }
+            }
+            
+            *anchor_list = kept;
+        }
+        discarded_duplicates
+    }
+    
+    /// Add `anchor` to `list` unless it conflicts with one already there
+    /// 
+    /// Of two conflicting anchors the one whose destination lies closest to its own
+    /// tile is kept (the one already in `list` on a tie). Returns the discarded anchor.
+    fn keep_better_anchor(list: &mut Vec<Anchor>, anchor: Anchor) -> Option<Anchor> {
+        let Some(existing) = list.iter_mut().find(|existing| Self::conflicts_with(existing, &anchor)) else {
+            list.push(anchor);
+            return None;
+        };
+        
+        if Self::grid_mismatch(&anchor) < Self::grid_mismatch(existing) {
+            Some(std::mem::replace(existing, anchor))
+        } else {
+            Some(anchor)
+        }
+    }
+    
+    /// Log an anchor dropped by the duplicate check and describe it for `discarded_duplicates`
+    fn discard_warning(src_key: (u8, u8, u8), discarded: &Anchor) -> AnchorWarning {
+        let tile_map_id = |area_no: u8, grid_x: u8, grid_z: u8| {
+            ((area_no as u32) << 24) | ((grid_x as u32) << 16) | ((grid_z as u32) << 8)
+        };
+        let warning = AnchorWarning::DiscardedDuplicate {
+            map_id: tile_map_id(src_key.0, src_key.1, src_key.2),
+            dst_map_id: tile_map_id(discarded.dst_area_no, discarded.dst_grid_x, discarded.dst_grid_z),
+            dst_pos: discarded.dst_pos,
+        };
+        warn!("Discarding anchor: {}", warning);
+        warning
+    }
+    
+    /// Whether `a` and `b` are the same anchor with different destination grid indices
+    /// 
+    /// Runs while the CSV is loaded, before any `with_tile_size` override can be set,
+    /// so destination grid offsets use `DEFAULT_TILE_SIZE`. Into an area whose tile
+    /// size is overridden later, the global comparison is off by the size difference:
+    /// rely on the local destination check there.
+    fn conflicts_with(a: &Anchor, b: &Anchor) -> bool {
+        if a.dst_area_no != b.dst_area_no
+            || (a.dst_grid_x == b.dst_grid_x && a.dst_grid_z == b.dst_grid_z)
+            || !Self::positions_equal(a.src_pos, b.src_pos)
+        {
+            return false;
+        }
+        
+        let global_dst = |anchor: &Anchor| {
+            (
+                anchor.dst_pos.0 + (anchor.dst_grid_x as f32) * DEFAULT_TILE_SIZE,
+                anchor.dst_pos.1,
+                anchor.dst_pos.2 + (anchor.dst_grid_z as f32) * DEFAULT_TILE_SIZE,
+            )
+        };
+        Self::positions_equal(a.dst_pos, b.dst_pos) || Self::positions_equal(global_dst(a), global_dst(b))
+    }
+    
+    /// How far the destination position lies outside its own tile (0 when inside)
+    /// 
+    /// Local tile coordinates are centered, so a consistent anchor stays within half a
+    /// tile of the origin on X and Z.
+    fn grid_mismatch(anchor: &Anchor) -> f32 {
+        const HALF_TILE: f32 = DEFAULT_TILE_SIZE / 2.0;
+        (anchor.dst_pos.0.abs() - HALF_TILE).max(0.0) + (anchor.dst_pos.2.abs() - HALF_TILE).max(0.0)
     }
     
     /// Compare two positions with floating point tolerance
@@ -249,45 +671,193 @@ impl WorldPositionTransformer {
     
     /// Pre-compute paths to global maps (m60 or m61) for all tiles that don't have a direct link
     /// 
-    /// Uses BFS to find the shortest path from each tile to any global map tile (m60 or m61).
-    /// This is called once at load time for O(1) lookups during runtime.
+    /// Uses BFS to find the shortest path from each tile to any global map tile in `targets`
+    /// (all of `global_areas` unless some are disallowed). This is called once at load time
+    /// for O(1) lookups during runtime.
     fn precompute_paths_to_global(
         anchors: &HashMap<(u8, u8, u8), Vec<Anchor>>,
+        global_areas: &[u8],
+        targets: &[u8],
     ) -> HashMap<(u8, u8, u8), PathToGlobalMap> {
         let mut paths: HashMap<(u8, u8, u8), PathToGlobalMap> = HashMap::new();
         
         // Find all tiles that need path computation (no direct global map link)
         for &tile_key in anchors.keys() {
-            // Skip global map tiles - they don't need paths
-            if tile_key.0 == 60 || tile_key.0 == 61 {
-                continue;
+            if let Some(path) = Self::compute_path_for_tile(tile_key, anchors, global_areas, targets) {
+                paths.insert(tile_key, path);
             }
+        }
+        
+        paths
+    }
+    
+    /// Same as `precompute_paths_to_global`, with the tiles split across threads
+    /// 
+    /// Each tile's BFS only reads the anchor map, so the chunks are independent and
+    /// the merged result is identical to the serial one.
+    fn precompute_paths_to_global_parallel(
+        anchors: &HashMap<(u8, u8, u8), Vec<Anchor>>,
+        global_areas: &[u8],
+        targets: &[u8],
+    ) -> HashMap<(u8, u8, u8), PathToGlobalMap> {
+        let tiles: Vec<(u8, u8, u8)> = anchors.keys().copied().collect();
+        let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+        let chunk_size = tiles.len().div_ceil(threads).max(1);
+        
+        thread::scope(|scope| {
+            let workers: Vec<_> = tiles
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .filter_map(|&tile_key| {
+                                Self::compute_path_for_tile(tile_key, anchors, global_areas, targets)
+                                    .map(|path| (tile_key, path))
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
             
-            // Check if this tile has a direct link to a global map (m60 or m61)
-            let has_direct_global = anchors
-                .get(&tile_key)
-                .map(|list| list.iter().any(|a| a.dst_area_no == 60 || a.dst_area_no == 61))
-                .unwrap_or(false);
-            
-            if has_direct_global {
-                continue;
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().expect("Path precompute thread panicked"))
+                .collect()
+        })
+    }
+    
+    /// Path to a global map for a single tile, or `None` if it doesn't need one (or has none)
+    /// 
+    /// The path may only end on `targets`; a direct anchor to another global area does
+    /// not count as a link.
+    fn compute_path_for_tile(
+        tile_key: (u8, u8, u8),
+        anchors: &HashMap<(u8, u8, u8), Vec<Anchor>>,
+        global_areas: &[u8],
+        targets: &[u8],
+    ) -> Option<PathToGlobalMap> {
+        // Skip global map tiles - they don't need paths
+        if global_areas.contains(&tile_key.0) {
+            return None;
+        }
+        
+        // Check if this tile has a direct link to a global map (m60 or m61)
+        let has_direct_global = anchors
+            .get(&tile_key)
+            .map(|list| list.iter().any(|a| targets.contains(&a.dst_area_no)))
+            .unwrap_or(false);
+        
+        if has_direct_global {
+            return None;
+        }
+        
+        // Use BFS to find path to global map (m60 or m61)
+        Self::bfs_find_path_to_global(tile_key, anchors, global_areas, targets)
+    }
+    
+    // =========================================================================
+    // INCREMENTAL UPDATES
+    // =========================================================================
+    
+    /// Re-read the CSV file and rebuild all anchors and paths
+    /// 
+    /// Global areas, allowed global maps, precision, neighbor fallback and parallel
+    /// precompute settings are kept. On error the transformer is left unchanged.
+    pub fn reload_csv<P: AsRef<Path>>(&mut self, csv_path: P) -> Result<(), TransformError> {
+        let reloaded = Self::from_csv_with_options(csv_path, &self.global_areas, self.parallel_precompute)?;
+        self.anchors = reloaded.anchors;
+        self.paths_to_global = reloaded.paths_to_global;
+        self.discarded_duplicates = reloaded.discarded_duplicates;
+        if self.allowed_global_maps.is_some() {
+            self.rebuild_paths();
+        }
+        Ok(())
+    }
+    
+    /// Add a single anchor from `src_map_id` and update only the paths it can affect
+    /// 
+    /// The anchor goes through the same duplicate check as a CSV load: when it
+    /// conflicts with an anchor of the tile, only the better one is kept (see
+    /// `keep_better_anchor`). The inverse of a kept anchor is added, tagged
+    /// `AnchorOrigin::Inverse`, and the inverse of a replaced one removed. Only tiles
+    /// that can reach the affected tiles are re-searched; every other path stays as it was.
+    pub fn update_anchor(&mut self, src_map_id: u32, anchor: Anchor) {
+        let (area_no, grid_x, grid_z, _) = Self::parse_map_id(src_map_id);
+        let src_key = (area_no, grid_x, grid_z);
+        let dst_key = (anchor.dst_area_no, anchor.dst_grid_x, anchor.dst_grid_z);
+        let inverse_anchor = Self::inverse_of(src_key, &anchor);
+        let mut affected = vec![src_key, dst_key];
+        
+        let src_list = self.anchors.entry(src_key).or_default();
+        if Self::contains_anchor(src_list, &anchor) {
+            return;
+        }
+        if let Some(discarded) = Self::keep_better_anchor(src_list, anchor.clone()) {
+            let warning = Self::discard_warning(src_key, &discarded);
+            self.discarded_duplicates.push(warning);
+            if Self::same_anchor(&discarded, &anchor) {
+                return; // The tile's existing anchor is better: nothing changes
             }
             
-            // Use BFS to find path to global map (m60 or m61)
-            if let Some(path) = Self::bfs_find_path_to_global(tile_key, anchors) {
-                paths.insert(tile_key, path);
+            // The new anchor replaced `discarded`: drop the inverse generated for it
+            let old_dst_key = (discarded.dst_area_no, discarded.dst_grid_x, discarded.dst_grid_z);
+            let old_inverse = Self::inverse_of(src_key, &discarded);
+            if let Some(list) = self.anchors.get_mut(&old_dst_key) {
+                list.retain(|a| a.origin != AnchorOrigin::Inverse || !Self::same_anchor(a, &old_inverse));
+            }
+            affected.push(old_dst_key);
+        }
+        let dst_list = self.anchors.entry(dst_key).or_default();
+        if !Self::contains_anchor(dst_list, &inverse_anchor) {
+            dst_list.push(inverse_anchor);
+        }
+        
+        let targets = self.path_targets();
+        for tile_key in self.tiles_reaching(&affected) {
+            match Self::compute_path_for_tile(tile_key, &self.anchors, &self.global_areas, &targets) {
+                Some(path) => self.paths_to_global.insert(tile_key, path),
+                None => self.paths_to_global.remove(&tile_key),
+            };
+        }
+    }
+    
+    /// All tiles with an anchor chain leading to one of `targets` (targets included)
+    fn tiles_reaching(&self, targets: &[(u8, u8, u8)]) -> HashSet<(u8, u8, u8)> {
+        // Reverse adjacency: destination tile -> tiles with an anchor to it
+        let mut incoming: HashMap<_, Vec<_>> = HashMap::new();
+        for (&key, list) in &self.anchors {
+            for anchor in list {
+                incoming
+                    .entry((anchor.dst_area_no, anchor.dst_grid_x, anchor.dst_grid_z))
+                    .or_default()
+                    .push(key);
             }
         }
         
-        paths
+        let mut reached: HashSet<(u8, u8, u8)> = targets.iter().copied().collect();
+        let mut queue: VecDeque<(u8, u8, u8)> = targets.iter().copied().collect();
+        while let Some(tile) = queue.pop_front() {
+            for &from in incoming.get(&tile).into_iter().flatten() {
+                if reached.insert(from) {
+                    queue.push_back(from);
+                }
+            }
+        }
+        
+        reached
     }
     
-    /// BFS to find the shortest path from a tile to any global map (m60 or m61)
+    /// BFS to find the shortest path from a tile to any global map (m60 or m61 by default)
     /// 
+    /// The search terminates on the first anchor whose destination area is in `targets`, and
+    /// never continues through tiles of the other `global_areas` (disallowed global maps).
     /// Returns the sequence of anchors to apply to transform coordinates.
     fn bfs_find_path_to_global(
         start: (u8, u8, u8),
         anchors: &HashMap<(u8, u8, u8), Vec<Anchor>>,
+        global_areas: &[u8],
+        targets: &[u8],
     ) -> Option<PathToGlobalMap> {
         // Queue entries: (current_tile, path_so_far)
         let mut queue: VecDeque<((u8, u8, u8), Vec<PathStep>)> = VecDeque::new();
@@ -312,13 +882,18 @@ impl WorldPositionTransformer {
                 });
                 
                 // Check if we reached a global map (m60 or m61)
-                if anchor.dst_area_no == 60 || anchor.dst_area_no == 61 {
+                if targets.contains(&anchor.dst_area_no) {
                     return Some(PathToGlobalMap {
                         steps: new_path,
                         final_global_tile: next_tile,
                     });
                 }
                 
+                // Disallowed global map: don't convert through it
+                if global_areas.contains(&anchor.dst_area_no) {
+                    continue;
+                }
+                
                 // Continue BFS if not visited
                 if !visited.contains(&next_tile) {
                     visited.insert(next_tile);
@@ -346,11 +921,35 @@ impl WorldPositionTransformer {
     }
     
     /// Format a map_id as a string "mWW_XX_YY_DD"
+    /// 
+    /// Fields are zero-padded to two digits; values above 99 are written with
+    /// three digits (e.g. "m60_150_35_00"), which `parse_map_id_str` accepts.
     pub fn format_map_id(map_id: u32) -> String {
         let (ww, xx, yy, dd) = Self::parse_map_id(map_id);
         format!("m{:02}_{:02}_{:02}_{:02}", ww, xx, yy, dd)
     }
     
+    /// Parse a "mWW_XX_YY_DD" string back into a packed map_id
+    /// 
+    /// Inverse of `format_map_id`: each field is split on '_' rather than read
+    /// at a fixed width, so any value of 0-255 round-trips. Returns None for
+    /// anything else.
+    pub fn parse_map_id_str(map_id_str: &str) -> Option<u32> {
+        let mut fields = map_id_str.strip_prefix('m')?.split('_');
+        let mut map_id = 0u32;
+        for _ in 0..4 {
+            let field = fields.next()?;
+            if field.is_empty() || !field.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            map_id = (map_id << 8) | field.parse::<u8>().ok()? as u32;
+        }
+        if fields.next().is_some() {
+            return None;
+        }
+        Some(map_id)
+    }
+    
     /// Convert local coordinates to world coordinates (returns best result)
     /// 
     /// Prioritizes anchors that point to global maps (dstAreaNo == 60 or 61).
@@ -361,6 +960,7 @@ impl WorldPositionTransformer {
     /// 1. Find anchor in CSV for the source map
     /// 2. Calculate position local to destination global map tile: P_local = (x,y,z) - src + dst
     /// 3. Convert to global using global map grid: P_global = P_local + (dstGridX * 256, 0, dstGridZ * 256)
+    ///    (256 being the default tile size, see `with_tile_size`)
     pub fn local_to_world_first(&self, map_id: u32, x: f32, y: f32, z: f32) -> Result<(f32, f32, f32), TransformError> {
         let result = self.local_to_world_with_global_map(map_id, x, y, z)?;
         Ok((result.0, result.1, result.2))
@@ -371,63 +971,246 @@ impl WorldPositionTransformer {
     /// Returns (global_x, global_y, global_z, global_map_area_no)
     /// where global_map_area_no is 60 for Lands Between, 61 for Shadow Realm, or 62 for Underground
     pub fn local_to_world_with_global_map(&self, map_id: u32, x: f32, y: f32, z: f32) -> Result<(f32, f32, f32, u8), TransformError> {
+        let conversion = self.resolve_conversion(map_id)?;
+        Ok(self.apply_conversion(conversion, x, y, z))
+    }
+    
+    /// Convert local coordinates to world coordinates relative to a custom origin
+    /// 
+    /// Same as `local_to_world_with_global_map`, with `origin` (in global coordinates)
+    /// subtracted from the result.
+    pub fn local_to_world_relative(
+        &self,
+        map_id: u32,
+        x: f32,
+        y: f32,
+        z: f32,
+        origin: (f32, f32, f32),
+    ) -> Result<(f32, f32, f32, u8), TransformError> {
+        let (gx, gy, gz, global_map_id) = self.local_to_world_with_global_map(map_id, x, y, z)?;
+        Ok((gx - origin.0, gy - origin.1, gz - origin.2, global_map_id))
+    }
+    
+    /// Convert to world coordinates, or return the local input if the tile is unknown
+    /// 
+    /// The trailing bool is true when a real conversion happened. On fallback the
+    /// global map is guessed with `fallback_global_map`.
+    pub fn local_to_world_or_local(&self, map_id: u32, x: f32, y: f32, z: f32) -> (f32, f32, f32, u8, bool) {
+        match self.local_to_world_with_global_map(map_id, x, y, z) {
+            Ok((gx, gy, gz, global_map_id)) => (gx, gy, gz, global_map_id, true),
+            Err(_) => (x, y, z, Self::fallback_global_map(map_id), false),
+        }
+    }
+    
+    /// Best guess of the global map for a tile that cannot be converted
+    /// 
+    /// Underground areas (m12) map to 62, global areas to themselves, anything else to 60.
+    pub fn fallback_global_map(map_id: u32) -> u8 {
+        let (area_no, _, _, _) = Self::parse_map_id(map_id);
+        if area_no == 12 {
+            62 // Underground (m62)
+        } else if area_no == 60 || area_no == 61 {
+            area_no
+        } else {
+            60 // Default to m60 if unknown
+        }
+    }
+    
+    /// Position as `(u, v)` in `[0,1]` on the global map containing it (see `MapBounds::normalize`)
+    /// 
+    /// Returns None if the tile cannot be converted or the global map has no known bounds.
+    pub fn normalized_position(&self, map_id: u32, x: f32, y: f32, z: f32) -> Option<(f32, f32)> {
+        let (gx, _, gz, global_map_id) = self.local_to_world_with_global_map(map_id, x, y, z).ok()?;
+        let bounds = self
+            .map_bounds
+            .get(&global_map_id)
+            .copied()
+            .or_else(|| MapBounds::default_for(global_map_id))?;
+        Some(bounds.normalize(gx, gz))
+    }
+    
+    /// Convert a batch of (map_id, x, y, z) samples to world coordinates
+    /// 
+    /// Equivalent to calling `local_to_world_with_global_map` for each sample, but the
+    /// tile lookup is only done once for each run of consecutive samples on the same map.
+    /// Results are returned in input order.
+    pub fn local_to_world_batch(
+        &self,
+        samples: &[(u32, f32, f32, f32)],
+    ) -> Vec<Result<(f32, f32, f32, u8), TransformError>> {
+        let mut results = Vec::with_capacity(samples.len());
+        let mut cached: Option<(u32, Option<Conversion<'_>>)> = None;
+        
+        for &(map_id, x, y, z) in samples {
+            let conversion = match cached {
+                Some((cached_map_id, conversion)) if cached_map_id == map_id => conversion,
+                _ => {
+                    let conversion = self.resolve_conversion(map_id).ok();
+                    cached = Some((map_id, conversion));
+                    conversion
+                }
+            };
+            
+            results.push(match conversion {
+                Some(conversion) => Ok(self.apply_conversion(conversion, x, y, z)),
+                None => Err(TransformError::UnknownMap(Self::format_map_id(map_id))),
+            });
+        }
+        
+        results
+    }
+    
+    /// Number of anchors applied when converting this map tile
+    /// 
+    /// 0 for global map tiles, 1 for a direct anchor, the path length for
+    /// multi-step conversions, None if the tile cannot be converted.
+    pub fn conversion_hops(&self, map_id: u32) -> Option<usize> {
+        self.resolve_conversion(map_id).ok().map(|conversion| conversion.hops())
+    }
+    
+    /// Find how a map tile converts to global coordinates
+    fn resolve_conversion(&self, map_id: u32) -> Result<Conversion<'_>, TransformError> {
         let (area_no, grid_x, grid_z, _) = Self::parse_map_id(map_id);
         
         // Case 1: Global map tiles (m60|61_XX_YY_00) - simple grid formula (60 == base game, 61 == DLC)
-        if area_no == 60  || area_no == 61 {
-            let gx = x + (grid_x as f32) * 256.0;
-            let gy = y;
-            let gz = z + (grid_z as f32) * 256.0;
-            return Ok((gx, gy, gz, area_no));
+        if self.global_areas.contains(&area_no) {
+            if !self.is_global_map_allowed(area_no) {
+                return Err(TransformError::UnknownMap(Self::format_map_id(map_id)));
+            }
+            return Ok(Conversion::GlobalTile { area_no, grid_x, grid_z });
         }
         
         let key = (area_no, grid_x, grid_z);
         
-        // Case 2: Direct anchor to global map (prefer m60, then m61)
-        if let Some(anchor_list) = self.anchors.get(&key) {
-            // Try to find a direct anchor to m60 first
-            if let Some(anchor) = anchor_list.iter().find(|a| a.dst_area_no == 60) {
-                let (gx, gy, gz) = Self::apply_anchor_and_convert_to_global(x, y, z, anchor);
-                // Special case: area_no 12 (Underground) maps to m60 coordinates but should be identified as m62
-                let global_map_id = if area_no == 12 { 62 } else { 60 };
-                return Ok((gx, gy, gz, global_map_id));
+        // Cases 2 and 3: direct anchor or pre-computed path
+        if let Some(conversion) = self.resolve_tile(key, (0.0, 0.0)) {
+            return Ok(conversion);
+        }
+        
+        // Case 4 (opt-in): borrow the nearest convertible tile of the same area
+        if self.borrow_neighbor_anchors {
+            let nearest = self.anchors
+                .keys()
+                .filter(|&&(other_area, _, _)| other_area == area_no)
+                .filter_map(|&(_, other_x, other_z)| {
+                    let tile_size = self.tile_size(area_no);
+                    let grid_offset = (
+                        (grid_x as f32 - other_x as f32) * tile_size,
+                        (grid_z as f32 - other_z as f32) * tile_size,
+                    );
+                    let conversion = self.resolve_tile((area_no, other_x, other_z), grid_offset)?;
+                    let dx = other_x as i32 - grid_x as i32;
+                    let dz = other_z as i32 - grid_z as i32;
+                    Some(((dx * dx + dz * dz, other_x, other_z), conversion))
+                })
+                .min_by_key(|&(distance, _)| distance);
+            
+            if let Some((_, conversion)) = nearest {
+                return Ok(conversion);
             }
-            // Then try m61
-            if let Some(anchor) = anchor_list.iter().find(|a| a.dst_area_no == 61) {
-                let (gx, gy, gz) = Self::apply_anchor_and_convert_to_global(x, y, z, anchor);
-                return Ok((gx, gy, gz, 61));
+        }
+        
+        Err(TransformError::UnknownMap(Self::format_map_id(map_id)))
+    }
+    
+    /// Find the direct anchor or path converting a non-global tile
+    fn resolve_tile(&self, key: (u8, u8, u8), grid_offset: (f32, f32)) -> Option<Conversion<'_>> {
+        let area_no = key.0;
+        
+        // Direct anchor to global map (prefer m60, then m61)
+        if let Some(anchor_list) = self.anchors.get(&key) {
+            // Try global areas in order of preference
+            for &global_area in self.global_areas.iter().filter(|&&area| self.is_global_map_allowed(area)) {
+                if let Some(anchor) = anchor_list.iter().find(|a| a.dst_area_no == global_area) {
+                    // Special case: area_no 12 (Underground) maps to m60 coordinates but should be identified as m62
+                    let global_map_id = if area_no == 12 && global_area == 60 { 62 } else { global_area };
+                    return Some(Conversion::DirectAnchor { anchor, global_map_id, grid_offset });
+                }
             }
         }
         
-        // Case 3: Use pre-computed path to global map
-        if let Some(path) = self.paths_to_global.get(&key) {
-            let (gx, gy, gz) = self.apply_path_to_global(x, y, z, path);
+        // Pre-computed path to global map
+        if let Some(path) = self.paths_to_global.get(&key).filter(|path| self.is_global_map_allowed(path.final_global_tile.0)) {
             let global_map_area = path.final_global_tile.0;
             // Special case: area_no 12 (Underground) should be identified as m62
             let global_map_id = if area_no == 12 && global_map_area == 60 { 62 } else { global_map_area };
-            return Ok((gx, gy, gz, global_map_id));
+            return Some(Conversion::Path { path, global_map_id, grid_offset });
         }
         
-        Err(TransformError::UnknownMap(Self::format_map_id(map_id)))
+        None
+    }
+    
+    /// Apply a resolved conversion to local coordinates
+    fn apply_conversion(&self, conversion: Conversion<'_>, x: f32, y: f32, z: f32) -> (f32, f32, f32, u8) {
+        match conversion {
+            Conversion::GlobalTile { area_no, grid_x, grid_z } => {
+                let tile_size = self.tile_size(area_no);
+                let gx = x + (grid_x as f32) * tile_size;
+                let gy = y;
+                let gz = z + (grid_z as f32) * tile_size;
+                (gx, gy, gz, area_no)
+            }
+            Conversion::DirectAnchor { anchor, global_map_id, grid_offset: (ox, oz) } => {
+                let tile_size = self.tile_size(anchor.dst_area_no);
+                let (gx, gy, gz) = Self::apply_anchor_and_convert_to_global(
+                    x + ox,
+                    y,
+                    z + oz,
+                    anchor,
+                    tile_size,
+                    self.high_precision,
+                );
+                (gx, gy, gz, global_map_id)
+            }
+            Conversion::Path { path, global_map_id, grid_offset: (ox, oz) } => {
+                let (gx, gy, gz) = self.apply_path_to_global(x + ox, y, z + oz, path);
+                (gx, gy, gz, global_map_id)
+            }
+        }
     }
     
     /// Apply an anchor transformation and convert to global coordinates
-    fn apply_anchor_and_convert_to_global(x: f32, y: f32, z: f32, anchor: &Anchor) -> (f32, f32, f32) {
+    /// 
+    /// `tile_size` is the tile width of the anchor's destination area.
+    fn apply_anchor_and_convert_to_global(
+        x: f32,
+        y: f32,
+        z: f32,
+        anchor: &Anchor,
+        tile_size: f32,
+        high_precision: bool,
+    ) -> (f32, f32, f32) {
+        if high_precision {
+            let local_x = x as f64 - anchor.src_pos.0 as f64 + anchor.dst_pos.0 as f64;
+            let local_y = y as f64 - anchor.src_pos.1 as f64 + anchor.dst_pos.1 as f64;
+            let local_z = z as f64 - anchor.src_pos.2 as f64 + anchor.dst_pos.2 as f64;
+            
+            let gx = local_x + (anchor.dst_grid_x as f64) * tile_size as f64;
+            let gz = local_z + (anchor.dst_grid_z as f64) * tile_size as f64;
+            
+            return (gx as f32, local_y as f32, gz as f32);
+        }
+        
         // Calculate position local to the destination global map tile (m60 or m61)
         let local_x = x - anchor.src_pos.0 + anchor.dst_pos.0;
         let local_y = y - anchor.src_pos.1 + anchor.dst_pos.1;
         let local_z = z - anchor.src_pos.2 + anchor.dst_pos.2;
         
         // Convert to global using the global map grid formula (works for both m60 and m61)
-        let gx = local_x + (anchor.dst_grid_x as f32) * 256.0;
+        let gx = local_x + (anchor.dst_grid_x as f32) * tile_size;
         let gy = local_y;
-        let gz = local_z + (anchor.dst_grid_z as f32) * 256.0;
+        let gz = local_z + (anchor.dst_grid_z as f32) * tile_size;
         
         (gx, gy, gz)
     }
     
     /// Apply a pre-computed path to transform coordinates to global map coordinates
     fn apply_path_to_global(&self, x: f32, y: f32, z: f32, path: &PathToGlobalMap) -> (f32, f32, f32) {
+        let tile_size = self.tile_size(path.final_global_tile.0);
+        if self.high_precision {
+            return Self::apply_path_to_global_f64(x, y, z, path, tile_size);
+        }
+        
         let mut current_x = x;
         let mut current_y = y;
         let mut current_z = z;
@@ -443,13 +1226,31 @@ impl WorldPositionTransformer {
         // The last step should have brought us to a global map tile (m60 or m61)
         // Apply the grid formula using the final global map tile coordinates
         let (_, final_grid_x, final_grid_z) = path.final_global_tile;
-        let gx = current_x + (final_grid_x as f32) * 256.0;
+        let gx = current_x + (final_grid_x as f32) * tile_size;
         let gy = current_y;
-        let gz = current_z + (final_grid_z as f32) * 256.0;
+        let gz = current_z + (final_grid_z as f32) * tile_size;
         
         (gx, gy, gz)
     }
     
+    /// Same as `apply_path_to_global`, accumulating in f64
+    fn apply_path_to_global_f64(x: f32, y: f32, z: f32, path: &PathToGlobalMap, tile_size: f32) -> (f32, f32, f32) {
+        let (mut current_x, mut current_y, mut current_z) = (x as f64, y as f64, z as f64);
+        
+        for step in &path.steps {
+            let anchor = &step.anchor;
+            current_x += anchor.dst_pos.0 as f64 - anchor.src_pos.0 as f64;
+            current_y += anchor.dst_pos.1 as f64 - anchor.src_pos.1 as f64;
+            current_z += anchor.dst_pos.2 as f64 - anchor.src_pos.2 as f64;
+        }
+        
+        let (_, final_grid_x, final_grid_z) = path.final_global_tile;
+        let gx = current_x + (final_grid_x as f64) * tile_size as f64;
+        let gz = current_z + (final_grid_z as f64) * tile_size as f64;
+        
+        (gx as f32, current_y as f32, gz as f32)
+    }
+    
     /// Get the number of loaded anchors
     pub fn anchor_count(&self) -> usize {
         self.anchors.values().map(|v| v.len()).sum()
@@ -459,17 +1260,227 @@ impl WorldPositionTransformer {
     pub fn map_count(&self) -> usize {
         self.anchors.len()
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
     
-    #[test]
-    fn test_parse_map_id() {
-        // m60_40_35_00 = 0x3C282300
-        let map_id = 0x3C282300u32;
-        let (ww, xx, yy, dd) = WorldPositionTransformer::parse_map_id(map_id);
+    /// Tiles directly reachable from `map_id` through one of its anchors
+    /// 
+    /// Same adjacency as the path search (generated inverse anchors included).
+    /// Returns packed map_ids (0xWWXXYY00), sorted and deduplicated.
+    pub fn neighbors(&self, map_id: u32) -> Vec<u32> {
+        let (area_no, grid_x, grid_z, _) = Self::parse_map_id(map_id);
+        let Some(anchor_list) = self.anchors.get(&(area_no, grid_x, grid_z)) else {
+            return Vec::new();
+        };
+        
+        let mut neighbors: Vec<u32> = anchor_list
+            .iter()
+            .map(|a| ((a.dst_area_no as u32) << 24) | ((a.dst_grid_x as u32) << 16) | ((a.dst_grid_z as u32) << 8))
+            .collect();
+        neighbors.sort_unstable();
+        neighbors.dedup();
+        neighbors
+    }
+    
+    /// Tiles with an anchor leading to `dst_map_id` (the reverse of `neighbors`)
+    /// 
+    /// Scans every anchor, generated inverse anchors included. Returns packed
+    /// map_ids (0xWWXXYY00), sorted and deduplicated.
+    pub fn incoming_anchors(&self, dst_map_id: u32) -> Vec<u32> {
+        let (area_no, grid_x, grid_z, _) = Self::parse_map_id(dst_map_id);
+        
+        let mut sources: Vec<u32> = self
+            .anchors
+            .iter()
+            .filter(|(_, list)| {
+                list.iter()
+                    .any(|a| (a.dst_area_no, a.dst_grid_x, a.dst_grid_z) == (area_no, grid_x, grid_z))
+            })
+            .map(|(&(src_area, src_x, src_z), _)| ((src_area as u32) << 24) | ((src_x as u32) << 16) | ((src_z as u32) << 8))
+            .collect();
+        sources.sort_unstable();
+        sources.dedup();
+        sources
+    }
+    
+    /// Export the tile -> tile anchor graph in Graphviz DOT format
+    /// 
+    /// Each node is a `mWW_XX_YY` tile and each edge is an anchor. Edges to global map
+    /// tiles are drawn in blue, generated inverse anchors are dashed.
+    pub fn to_dot(&self) -> String {
+        self.to_dot_filtered(None)
+    }
+    
+    /// Same as `to_dot`, only drawing anchors of the given origin (all when None)
+    pub fn to_dot_filtered(&self, origin: Option<AnchorOrigin>) -> String {
+        let label = |(area_no, grid_x, grid_z): (u8, u8, u8)| {
+            format!("m{:02}_{:02}_{:02}", area_no, grid_x, grid_z)
+        };
+        
+        let mut tiles: Vec<&(u8, u8, u8)> = self.anchors.keys().collect();
+        tiles.sort();
+        
+        let mut dot = String::from("digraph anchors {\n");
+        for &tile_key in tiles {
+            for anchor in self.anchors[&tile_key].iter().filter(|a| Self::origin_matches(a, origin)) {
+                let dst_key = (anchor.dst_area_no, anchor.dst_grid_x, anchor.dst_grid_z);
+                let mut attributes = Vec::new();
+                if self.global_areas.contains(&anchor.dst_area_no) {
+                    attributes.push("color=blue");
+                }
+                if anchor.origin == AnchorOrigin::Inverse {
+                    attributes.push("style=dashed");
+                }
+                
+                dot.push_str(&format!("    \"{}\" -> \"{}\"", label(tile_key), label(dst_key)));
+                if !attributes.is_empty() {
+                    dot.push_str(&format!(" [{}]", attributes.join(", ")));
+                }
+                dot.push_str(";\n");
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+    
+    /// Every anchor of the given origin (all when None) with its packed source map_id, sorted by map_id
+    pub fn iter_anchors(&self, origin: Option<AnchorOrigin>) -> impl Iterator<Item = (u32, &Anchor)> + '_ {
+        let mut tiles: Vec<&(u8, u8, u8)> = self.anchors.keys().collect();
+        tiles.sort();
+        
+        tiles.into_iter().flat_map(move |&(area_no, grid_x, grid_z)| {
+            let map_id = ((area_no as u32) << 24) | ((grid_x as u32) << 16) | ((grid_z as u32) << 8);
+            self.anchors[&(area_no, grid_x, grid_z)]
+                .iter()
+                .filter(move |a| Self::origin_matches(a, origin))
+                .map(move |anchor| (map_id, anchor))
+        })
+    }
+    
+    /// Whether `anchor` passes an optional origin filter
+    fn origin_matches(anchor: &Anchor, origin: Option<AnchorOrigin>) -> bool {
+        origin.is_none_or(|origin| anchor.origin == origin)
+    }
+    
+    /// List tiles that only appear as anchor destinations in the CSV (never as sources)
+    /// 
+    /// These tiles are only convertible thanks to the generated inverse anchors.
+    /// Global map tiles (m60/m61) are excluded since they use the grid formula.
+    /// Returns packed map_ids (0xWWXXYY00), sorted.
+    pub fn orphan_destinations(&self) -> Vec<u32> {
+        let mut sources: HashSet<(u8, u8, u8)> = HashSet::new();
+        let mut destinations: HashSet<(u8, u8, u8)> = HashSet::new();
+        
+        for (&tile_key, anchor_list) in &self.anchors {
+            for anchor in anchor_list.iter().filter(|a| a.origin == AnchorOrigin::Csv) {
+                sources.insert(tile_key);
+                destinations.insert((anchor.dst_area_no, anchor.dst_grid_x, anchor.dst_grid_z));
+            }
+        }
+        
+        let mut orphans: Vec<u32> = destinations
+            .difference(&sources)
+            .filter(|(area_no, _, _)| !self.global_areas.contains(area_no))
+            .map(|&(area_no, grid_x, grid_z)| {
+                ((area_no as u32) << 24) | ((grid_x as u32) << 16) | ((grid_z as u32) << 8)
+            })
+            .collect();
+        orphans.sort_unstable();
+        orphans
+    }
+    
+    /// Anchors dropped as conflicting duplicates when loading (or by `update_anchor`)
+    /// 
+    /// They are also logged, but tools without a log (e.g. `validate-csv`) read them here.
+    pub fn discarded_duplicates(&self) -> &[AnchorWarning] {
+        &self.discarded_duplicates
+    }
+    
+    /// Flag interior tiles with CSV anchors to more than one global map
+    /// 
+    /// m60 and m61 are separate coordinate spaces, so a tile anchored to both
+    /// usually means a wrong area number in the data. Sorted by map_id.
+    pub fn validate_global_separation(&self) -> Vec<AnchorWarning> {
+        let mut tiles: Vec<_> = self.anchors
+            .iter()
+            .filter(|((area_no, _, _), _)| !self.global_areas.contains(area_no))
+            .collect();
+        tiles.sort_unstable_by_key(|(&tile_key, _)| tile_key);
+        
+        tiles
+            .into_iter()
+            .filter_map(|(&(area_no, grid_x, grid_z), anchor_list)| {
+                let mut global_areas: Vec<u8> = anchor_list
+                    .iter()
+                    .filter(|a| a.origin == AnchorOrigin::Csv && self.global_areas.contains(&a.dst_area_no))
+                    .map(|a| a.dst_area_no)
+                    .collect();
+                global_areas.sort_unstable();
+                global_areas.dedup();
+                
+                (global_areas.len() > 1).then_some(AnchorWarning::MultipleGlobalMaps {
+                    map_id: ((area_no as u32) << 24) | ((grid_x as u32) << 16) | ((grid_z as u32) << 8),
+                    global_areas,
+                })
+            })
+            .collect()
+    }
+    
+    /// Anchor count and resolution of every interior tile with anchors, sorted by map_id
+    /// 
+    /// Global map tiles are left out (they use the grid formula), and so is the
+    /// neighbor fallback: a tile only resolves through its own anchors here.
+    pub fn tile_report(&self) -> Vec<TileReport> {
+        self.tile_report_filtered(None)
+    }
+    
+    /// Same as `tile_report`, only counting anchors of the given origin (all when None)
+    /// 
+    /// Resolution still uses every anchor. Tiles without a matching anchor are left out.
+    pub fn tile_report_filtered(&self, origin: Option<AnchorOrigin>) -> Vec<TileReport> {
+        let mut report: Vec<TileReport> = self.anchors
+            .iter()
+            .filter(|((area_no, _, _), _)| !self.global_areas.contains(area_no))
+            .filter_map(|(&tile_key @ (area_no, grid_x, grid_z), anchor_list)| {
+                let anchor_count = anchor_list.iter().filter(|a| Self::origin_matches(a, origin)).count();
+                if anchor_count == 0 {
+                    return None;
+                }
+                let resolution = match self.resolve_tile(tile_key, (0.0, 0.0)) {
+                    Some(Conversion::Path { path, .. }) => Resolution::Path(path.steps.len()),
+                    Some(_) => Resolution::Direct,
+                    None => Resolution::None,
+                };
+                Some(TileReport {
+                    map_id: ((area_no as u32) << 24) | ((grid_x as u32) << 16) | ((grid_z as u32) << 8),
+                    anchor_count,
+                    resolution,
+                })
+            })
+            .collect();
+        report.sort_unstable_by_key(|tile| tile.map_id);
+        report
+    }
+    
+    /// Interior tiles with anchors but no way to a global map, sorted by map_id
+    /// 
+    /// Positions on these tiles can't be converted (short of the neighbor fallback).
+    pub fn unreachable_tiles(&self) -> Vec<u32> {
+        self.tile_report()
+            .into_iter()
+            .filter(|tile| tile.resolution == Resolution::None)
+            .map(|tile| tile.map_id)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_parse_map_id() {
+        // m60_40_35_00 = 0x3C282300
+        let map_id = 0x3C282300u32;
+        let (ww, xx, yy, dd) = WorldPositionTransformer::parse_map_id(map_id);
         assert_eq!(ww, 60);
         assert_eq!(xx, 40);
         assert_eq!(yy, 35);
@@ -483,6 +1494,20 @@ mod tests {
         assert_eq!(formatted, "m60_40_35_00");
     }
     
+    #[test]
+    fn test_map_id_str_round_trip() {
+        // Grid X of 150 needs three digits
+        let map_id = 0x3C962300u32;
+        let formatted = WorldPositionTransformer::format_map_id(map_id);
+        assert_eq!(formatted, "m60_150_35_00");
+        assert_eq!(WorldPositionTransformer::parse_map_id_str(&formatted), Some(map_id));
+        
+        assert_eq!(WorldPositionTransformer::parse_map_id_str("m60_40_35_00"), Some(0x3C282300));
+        assert_eq!(WorldPositionTransformer::parse_map_id_str("m60_256_35_00"), None);
+        assert_eq!(WorldPositionTransformer::parse_map_id_str("m60_40_35"), None);
+        assert_eq!(WorldPositionTransformer::parse_map_id_str("m60_+40_35_00"), None);
+    }
+    
     #[test]
     fn test_overworld_conversion() {
         // Create empty transformer (no CSV needed for overworld)
@@ -501,6 +1526,32 @@ mod tests {
         assert_eq!(gz, 20.0 + 35.0 * 256.0);
     }
     
+    #[test]
+    fn test_tile_size_per_area() {
+        let mut transformer = WorldPositionTransformer::empty()
+            .with_tile_size(60, 256.0)
+            .with_tile_size(61, 128.0);
+        assert_eq!(transformer.tile_size(10), DEFAULT_TILE_SIZE);
+        
+        // Global tiles: m60_40_35_00 and m61_45_40_00
+        let (gx, _, gz) = transformer.local_to_world_first(0x3C282300, 10.0, 0.0, 20.0).unwrap();
+        assert_eq!((gx, gz), (10.0 + 40.0 * 256.0, 20.0 + 35.0 * 256.0));
+        let (gx, _, gz) = transformer.local_to_world_first(0x3D2D2800, 10.0, 0.0, 20.0).unwrap();
+        assert_eq!((gx, gz), (10.0 + 45.0 * 128.0, 20.0 + 40.0 * 128.0));
+        
+        // Anchors use the tile size of their destination area: m10_00_00_00 -> m61_45_40_00
+        transformer.update_anchor(0x0A000000, Anchor {
+            src_pos: (0.0, 0.0, 0.0),
+            dst_area_no: 61,
+            dst_grid_x: 45,
+            dst_grid_z: 40,
+            dst_pos: (5.0, 0.0, 5.0),
+            origin: AnchorOrigin::Csv,
+        });
+        let (gx, _, gz) = transformer.local_to_world_first(0x0A000000, 1.0, 0.0, 2.0).unwrap();
+        assert_eq!((gx, gz), (6.0 + 45.0 * 128.0, 7.0 + 40.0 * 128.0));
+    }
+    
     #[test]
     fn test_inverse_anchors_created() {
         // Create a transformer with a single anchor: m10_00_00_00 -> m10_01_00_00
@@ -513,6 +1564,7 @@ mod tests {
             dst_grid_x: 1,
             dst_grid_z: 0,
             dst_pos: (0.0, 0.0, 0.0),
+            origin: AnchorOrigin::Csv,
         };
         anchors.insert((10, 0, 0), vec![original_anchor]);
         
@@ -547,6 +1599,7 @@ mod tests {
             dst_grid_x: 1,
             dst_grid_z: 0,
             dst_pos: (200.0, 0.0, 200.0),
+            origin: AnchorOrigin::Csv,
         };
         
         // B -> A (already exists as inverse)
@@ -556,6 +1609,7 @@ mod tests {
             dst_grid_x: 0,
             dst_grid_z: 0,
             dst_pos: (100.0, 0.0, 100.0),
+            origin: AnchorOrigin::Csv,
         };
         
         anchors.insert((20, 0, 0), vec![anchor_a_to_b]);
@@ -574,6 +1628,45 @@ mod tests {
             "No duplicate should be added for m20_01_00_00");
     }
     
+    #[test]
+    fn test_conflicting_duplicate_keeps_consistent_grid() {
+        let mut anchors: HashMap<(u8, u8, u8), Vec<Anchor>> = HashMap::new();
+        
+        // Same global destination, but the first one has the grid rounded down and the
+        // local position pushed outside its tile to compensate
+        let inconsistent = Anchor {
+            src_pos: (10.0, 0.0, 10.0),
+            dst_area_no: 60,
+            dst_grid_x: 40,
+            dst_grid_z: 35,
+            dst_pos: (300.0, 5.0, 20.0),
+            origin: AnchorOrigin::Csv,
+        };
+        let consistent = Anchor {
+            src_pos: (10.0005, 0.0, 10.0),
+            dst_grid_x: 41,
+            dst_pos: (44.0, 5.0, 20.0),
+            ..inconsistent.clone()
+        };
+        anchors.insert((10, 0, 0), vec![inconsistent, consistent]);
+        
+        let discarded = WorldPositionTransformer::add_inverse_anchors(&mut anchors);
+        
+        assert_eq!(
+            discarded,
+            vec![AnchorWarning::DiscardedDuplicate {
+                map_id: 0x0A000000,
+                dst_map_id: 0x3C282300,
+                dst_pos: (300.0, 5.0, 20.0),
+            }]
+        );
+        let list = anchors.get(&(10, 0, 0)).unwrap();
+        assert_eq!(list.len(), 1);
+        assert_eq!((list[0].dst_grid_x, list[0].dst_pos.0), (41, 44.0));
+        assert!(anchors.contains_key(&(60, 41, 35)));
+        assert!(!anchors.contains_key(&(60, 40, 35)));
+    }
+    
     #[test]
     fn test_positions_equal() {
         assert!(WorldPositionTransformer::positions_equal(
@@ -606,6 +1699,7 @@ mod tests {
             dst_grid_x: 40,
             dst_grid_z: 35,
             dst_pos: (100.0, 50.0, 100.0),
+            origin: AnchorOrigin::Csv,
         }]);
         
         // m10_01_00_00 -> m10_00_00_00 (no direct global map link)
@@ -615,10 +1709,11 @@ mod tests {
             dst_grid_x: 0,
             dst_grid_z: 0,
             dst_pos: (-514.0, 28.0, 200.0),
+            origin: AnchorOrigin::Csv,
         }]);
         
         // BFS should find path from m10_01_00_00 to m60
-        let path = WorldPositionTransformer::bfs_find_path_to_global((10, 1, 0), &anchors);
+        let path = WorldPositionTransformer::bfs_find_path_to_global((10, 1, 0), &anchors, DEFAULT_GLOBAL_AREAS, DEFAULT_GLOBAL_AREAS);
         
         assert!(path.is_some(), "Should find a path from m10_01_00_00 to global map");
         let path = path.unwrap();
@@ -640,6 +1735,7 @@ mod tests {
             dst_grid_x: 40,
             dst_grid_z: 35,
             dst_pos: (100.0, 50.0, 100.0),
+            origin: AnchorOrigin::Csv,
         }]);
         
         // m10_01_00_00 -> m10_00_00_00 (no direct global map link)
@@ -649,9 +1745,10 @@ mod tests {
             dst_grid_x: 0,
             dst_grid_z: 0,
             dst_pos: (-514.0, 28.0, 200.0),
+            origin: AnchorOrigin::Csv,
         }]);
         
-        let paths = WorldPositionTransformer::precompute_paths_to_global(&anchors);
+        let paths = WorldPositionTransformer::precompute_paths_to_global(&anchors, DEFAULT_GLOBAL_AREAS, DEFAULT_GLOBAL_AREAS);
         
         // m10_00_00_00 has direct link, should NOT be in paths
         assert!(!paths.contains_key(&(10, 0, 0)), 
@@ -674,6 +1771,7 @@ mod tests {
             dst_grid_x: 40,
             dst_grid_z: 35,
             dst_pos: (100.0, 50.0, 100.0),
+            origin: AnchorOrigin::Csv,
         }]);
         
         // m10_01_00_00 -> m10_00_00_00
@@ -683,15 +1781,13 @@ mod tests {
             dst_grid_x: 0,
             dst_grid_z: 0,
             dst_pos: (10.0, 5.0, 10.0),
+            origin: AnchorOrigin::Csv,
         }]);
         
         // Pre-compute paths
-        let paths_to_global = WorldPositionTransformer::precompute_paths_to_global(&anchors);
+        let paths_to_global = WorldPositionTransformer::precompute_paths_to_global(&anchors, DEFAULT_GLOBAL_AREAS, DEFAULT_GLOBAL_AREAS);
         
-        let transformer = WorldPositionTransformer {
-            anchors,
-            paths_to_global,
-        };
+        let transformer = WorldPositionTransformer::from_parts(anchors, paths_to_global);
         
         // Convert from m10_01_00_00
         // m10_01_00_00 = 0x0A010000
@@ -723,9 +1819,10 @@ mod tests {
             dst_grid_x: 1,
             dst_grid_z: 0,
             dst_pos: (10.0, 0.0, 10.0),
+            origin: AnchorOrigin::Csv,
         }]);
         
-        let path = WorldPositionTransformer::bfs_find_path_to_global((99, 0, 0), &anchors);
+        let path = WorldPositionTransformer::bfs_find_path_to_global((99, 0, 0), &anchors, DEFAULT_GLOBAL_AREAS, DEFAULT_GLOBAL_AREAS);
         assert!(path.is_none(), "Should not find path for isolated tile");
     }
     
@@ -741,6 +1838,7 @@ mod tests {
             dst_grid_x: 10,
             dst_grid_z: 15,
             dst_pos: (100.0, 50.0, 100.0),
+            origin: AnchorOrigin::Csv,
         }]);
         
         // m20_01_00_00 -> m20_00_00_00 (no direct global map link)
@@ -750,10 +1848,11 @@ mod tests {
             dst_grid_x: 0,
             dst_grid_z: 0,
             dst_pos: (-514.0, 28.0, 200.0),
+            origin: AnchorOrigin::Csv,
         }]);
         
         // BFS should find path from m20_01_00_00 to m61
-        let path = WorldPositionTransformer::bfs_find_path_to_global((20, 1, 0), &anchors);
+        let path = WorldPositionTransformer::bfs_find_path_to_global((20, 1, 0), &anchors, DEFAULT_GLOBAL_AREAS, DEFAULT_GLOBAL_AREAS);
         
         assert!(path.is_some(), "Should find a path from m20_01_00_00 to m61");
         let path = path.unwrap();
@@ -762,5 +1861,646 @@ mod tests {
         assert_eq!(path.steps.len(), 2, "Path should have 2 steps");
         assert_eq!(path.final_global_tile.0, 61, "Should end at m61");
     }
-}
+    
+    #[test]
+    fn test_orphan_destinations() {
+        // m10_00_00_00 -> m10_01_00_00: m10_01 only appears as a destination
+        let mut anchors: HashMap<(u8, u8, u8), Vec<Anchor>> = HashMap::new();
+        anchors.insert((10, 0, 0), vec![Anchor {
+            src_pos: (0.0, 0.0, 0.0),
+            dst_area_no: 10,
+            dst_grid_x: 1,
+            dst_grid_z: 0,
+            dst_pos: (10.0, 0.0, 10.0),
+            origin: AnchorOrigin::Csv,
+        }]);
+        
+        // m11_00_00_00 -> m60_40_35_00: global map tiles are never orphans
+        anchors.insert((11, 0, 0), vec![Anchor {
+            src_pos: (0.0, 0.0, 0.0),
+            dst_area_no: 60,
+            dst_grid_x: 40,
+            dst_grid_z: 35,
+            dst_pos: (10.0, 0.0, 10.0),
+            origin: AnchorOrigin::Csv,
+        }]);
+        
+        WorldPositionTransformer::add_inverse_anchors(&mut anchors);
+        let paths_to_global = WorldPositionTransformer::precompute_paths_to_global(&anchors, DEFAULT_GLOBAL_AREAS, DEFAULT_GLOBAL_AREAS);
+        let transformer = WorldPositionTransformer::from_parts(anchors, paths_to_global);
+        
+        // Inverse generation made m10_01 a source, but it is still reported
+        assert_eq!(transformer.orphan_destinations(), vec![0x0A010000]);
+    }
+    
+    #[test]
+    fn test_bfs_terminates_at_custom_global_area() {
+        // Chain: m30_01_00_00 -> m30_00_00_00 -> m62_10_10_00 -> m60_40_35_00
+        let mut anchors: HashMap<(u8, u8, u8), Vec<Anchor>> = HashMap::new();
+        
+        anchors.insert((30, 1, 0), vec![Anchor {
+            src_pos: (0.0, 0.0, 0.0),
+            dst_area_no: 30,
+            dst_grid_x: 0,
+            dst_grid_z: 0,
+            dst_pos: (10.0, 0.0, 10.0),
+            origin: AnchorOrigin::Csv,
+        }]);
+        anchors.insert((30, 0, 0), vec![Anchor {
+            src_pos: (0.0, 0.0, 0.0),
+            dst_area_no: 62,
+            dst_grid_x: 10,
+            dst_grid_z: 10,
+            dst_pos: (20.0, 0.0, 20.0),
+            origin: AnchorOrigin::Csv,
+        }]);
+        anchors.insert((62, 10, 10), vec![Anchor {
+            src_pos: (0.0, 0.0, 0.0),
+            dst_area_no: 60,
+            dst_grid_x: 40,
+            dst_grid_z: 35,
+            dst_pos: (30.0, 0.0, 30.0),
+            origin: AnchorOrigin::Csv,
+        }]);
+        
+        // Default areas: the path continues through m62 to m60
+        let path = WorldPositionTransformer::bfs_find_path_to_global((30, 1, 0), &anchors, DEFAULT_GLOBAL_AREAS, DEFAULT_GLOBAL_AREAS)
+            .unwrap();
+        assert_eq!(path.steps.len(), 3);
+        assert_eq!(path.final_global_tile, (60, 40, 35));
+        
+        // With 62 as a global area, the BFS stops at m62
+        let path = WorldPositionTransformer::bfs_find_path_to_global((30, 1, 0), &anchors, &[60, 61, 62], &[60, 61, 62])
+            .unwrap();
+        assert_eq!(path.steps.len(), 2);
+        assert_eq!(path.final_global_tile, (62, 10, 10));
+    }
+    
+    #[test]
+    fn test_local_to_world_batch_matches_single() {
+        // m10_00_00_00 -> m60_40_35_00
+        let mut anchors: HashMap<(u8, u8, u8), Vec<Anchor>> = HashMap::new();
+        anchors.insert((10, 0, 0), vec![Anchor {
+            src_pos: (0.0, 0.0, 0.0),
+            dst_area_no: 60,
+            dst_grid_x: 40,
+            dst_grid_z: 35,
+            dst_pos: (100.0, 50.0, 100.0),
+            origin: AnchorOrigin::Csv,
+        }]);
+        let transformer = WorldPositionTransformer::from_parts(anchors, HashMap::new());
+        
+        let samples = [
+            (0x0A000000u32, 1.0, 2.0, 3.0),
+            (0x0A000000u32, 4.0, 5.0, 6.0),
+            (0x3C282300u32, 7.0, 8.0, 9.0),
+            (0x63000000u32, 0.0, 0.0, 0.0), // m99: unknown
+            (0x0A000000u32, 10.0, 11.0, 12.0),
+        ];
+        
+        let results = transformer.local_to_world_batch(&samples);
+        assert_eq!(results.len(), samples.len());
+        
+        for (&(map_id, x, y, z), result) in samples.iter().zip(&results) {
+            match transformer.local_to_world_with_global_map(map_id, x, y, z) {
+                Ok(expected) => assert_eq!(result.as_ref().unwrap(), &expected),
+                Err(_) => assert!(result.is_err()),
+            }
+        }
+    }
+    
+    #[test]
+    fn test_high_precision_path() {
+        // Multi-hop chain whose intermediate values are large (~36000) but cancel out,
+        // so the f32 rounding of the intermediate steps dominates the final error
+        let mut anchors: HashMap<(u8, u8, u8), Vec<Anchor>> = HashMap::new();
+        anchors.insert((10, 1, 0), vec![Anchor {
+            src_pos: (-12345.625, 0.0, -12345.625),
+            dst_area_no: 10,
+            dst_grid_x: 0,
+            dst_grid_z: 0,
+            dst_pos: (23456.75, 0.0, 23456.75),
+            origin: AnchorOrigin::Csv,
+        }]);
+        anchors.insert((10, 0, 0), vec![Anchor {
+            src_pos: (35802.0, 0.0, 35802.0),
+            dst_area_no: 60,
+            dst_grid_x: 0,
+            dst_grid_z: 0,
+            dst_pos: (0.5, 0.0, 0.5),
+            origin: AnchorOrigin::Csv,
+        }]);
+        let paths_to_global = WorldPositionTransformer::precompute_paths_to_global(&anchors, DEFAULT_GLOBAL_AREAS, DEFAULT_GLOBAL_AREAS);
+        let low = WorldPositionTransformer::from_parts(anchors, paths_to_global);
+        
+        let map_id = 0x0A010000u32;
+        let (x, z) = (0.123f32, 0.456f32);
+        
+        // Exact arithmetic on the same (f32-stored) inputs
+        let anchors_chain = &low.paths_to_global[&(10, 1, 0)].steps;
+        let exact = |start: f32, pick: fn(&(f32, f32, f32)) -> f32, grid: f64| {
+            anchors_chain.iter().fold(start as f64, |acc, step| {
+                acc - pick(&step.anchor.src_pos) as f64 + pick(&step.anchor.dst_pos) as f64
+            }) + grid * 256.0
+        };
+        let exact_x = exact(x, |p| p.0, 0.0);
+        let exact_z = exact(z, |p| p.2, 0.0);
+        
+        let (lx, _, lz) = low.local_to_world_first(map_id, x, 0.0, z).unwrap();
+        let high = low.with_high_precision(true);
+        let (hx, _, hz) = high.local_to_world_first(map_id, x, 0.0, z).unwrap();
+        
+        let low_error = (lx as f64 - exact_x).abs() + (lz as f64 - exact_z).abs();
+        let high_error = (hx as f64 - exact_x).abs() + (hz as f64 - exact_z).abs();
+        
+        assert!(high_error < low_error, "f64 error {} should be below f32 error {}", high_error, low_error);
+        // Only the final rounding to f32 remains
+        assert!(high_error < 1e-4);
+    }
+    
+    #[test]
+    fn test_to_dot() {
+        let mut anchors: HashMap<(u8, u8, u8), Vec<Anchor>> = HashMap::new();
+        anchors.insert((10, 0, 0), vec![Anchor {
+            src_pos: (0.0, 0.0, 0.0),
+            dst_area_no: 60,
+            dst_grid_x: 40,
+            dst_grid_z: 35,
+            dst_pos: (100.0, 50.0, 100.0),
+            origin: AnchorOrigin::Csv,
+        }]);
+        WorldPositionTransformer::add_inverse_anchors(&mut anchors);
+        let transformer = WorldPositionTransformer::from_parts(anchors, HashMap::new());
+        
+        let dot = transformer.to_dot();
+        assert!(dot.starts_with("digraph anchors {"));
+        assert!(dot.contains("\"m10_00_00\" -> \"m60_40_35\" [color=blue];"));
+        assert!(dot.contains("\"m60_40_35\" -> \"m10_00_00\" [style=dashed];"));
+    }
+    
+    #[test]
+    fn test_csv_short_line_skipped() {
+        // Second line stops at dstPosY (18 fields), third one is complete
+        let csv_path = std::env::temp_dir().join(format!("short_line_test_{}.csv", std::process::id()));
+        std::fs::write(
+            &csv_path,
+            "header\n\
+             0,0,0,0,0,11,0,0,0,1.0,2.0,3.0,60,40,35,0,100.0,50.0\n\
+             0,0,0,0,0,10,0,0,0,1.0,2.0,3.0,60,40,35,0,100.0,50.0,100.0\n",
+        ).unwrap();
+        let transformer = WorldPositionTransformer::from_csv(&csv_path).unwrap();
+        std::fs::remove_file(&csv_path).unwrap();
+        
+        // Only the complete line is loaded (plus its inverse)
+        assert_eq!(transformer.anchor_count(), 2);
+        assert!(transformer.local_to_world_first(0x0A000000, 1.0, 2.0, 3.0).is_ok());
+        assert!(transformer.local_to_world_first(0x0B000000, 1.0, 2.0, 3.0).is_err());
+    }
+    
+    #[test]
+    fn test_anchor_origin_tagging() {
+        let csv_path = std::env::temp_dir().join(format!("anchor_origin_test_{}.csv", std::process::id()));
+        std::fs::write(
+            &csv_path,
+            "header\n\
+             0,0,0,0,0,10,0,0,0,1.0,2.0,3.0,60,40,35,0,100.0,50.0,100.0\n",
+        ).unwrap();
+        let transformer = WorldPositionTransformer::from_csv(&csv_path).unwrap();
+        std::fs::remove_file(&csv_path).unwrap();
+        
+        let csv: Vec<u32> = transformer.iter_anchors(Some(AnchorOrigin::Csv)).map(|(map_id, _)| map_id).collect();
+        let inverse: Vec<u32> = transformer.iter_anchors(Some(AnchorOrigin::Inverse)).map(|(map_id, _)| map_id).collect();
+        assert_eq!(csv, vec![0x0A000000]);
+        assert_eq!(inverse, vec![0x3C282300]);
+        assert_eq!(transformer.iter_anchors(None).count(), 2);
+        
+        // Generated inverses can be left out of the graph view
+        assert!(!transformer.to_dot_filtered(Some(AnchorOrigin::Csv)).contains("dashed"));
+        assert!(transformer.to_dot().contains("dashed"));
+        assert_eq!(transformer.tile_report_filtered(Some(AnchorOrigin::Inverse)).len(), 0);
+    }
+    
+    #[test]
+    fn test_neighbors() {
+        let mut anchors: HashMap<(u8, u8, u8), Vec<Anchor>> = HashMap::new();
+        let to_overworld = Anchor {
+            src_pos: (0.0, 0.0, 0.0),
+            dst_area_no: 60,
+            dst_grid_x: 40,
+            dst_grid_z: 35,
+            dst_pos: (10.0, 0.0, 10.0),
+            origin: AnchorOrigin::Csv,
+        };
+        let to_interior = Anchor {
+            dst_area_no: 11,
+            dst_grid_x: 0,
+            dst_grid_z: 0,
+            ..to_overworld.clone()
+        };
+        let to_overworld_again = Anchor {
+            src_pos: (50.0, 0.0, 50.0),
+            ..to_overworld.clone()
+        };
+        anchors.insert((10, 0, 0), vec![to_overworld, to_interior, to_overworld_again]);
+        WorldPositionTransformer::add_inverse_anchors(&mut anchors);
+        
+        let paths_to_global = WorldPositionTransformer::precompute_paths_to_global(&anchors, DEFAULT_GLOBAL_AREAS, DEFAULT_GLOBAL_AREAS);
+        let transformer = WorldPositionTransformer::from_parts(anchors, paths_to_global);
+        
+        assert_eq!(transformer.neighbors(0x0A000000), vec![0x0B000000, 0x3C282300]);
+        assert_eq!(transformer.neighbors(0x0B000000), vec![0x0A000000]);
+        assert!(transformer.neighbors(0x0C000000).is_empty());
+    }
+    
+    #[test]
+    fn test_incoming_anchors() {
+        let mut anchors: HashMap<(u8, u8, u8), Vec<Anchor>> = HashMap::new();
+        let to_overworld = Anchor {
+            src_pos: (0.0, 0.0, 0.0),
+            dst_area_no: 60,
+            dst_grid_x: 40,
+            dst_grid_z: 35,
+            dst_pos: (10.0, 0.0, 10.0),
+            origin: AnchorOrigin::Csv,
+        };
+        let to_interior = Anchor {
+            dst_area_no: 11,
+            dst_grid_x: 0,
+            dst_grid_z: 0,
+            ..to_overworld.clone()
+        };
+        // m10 and m12 both lead to m60_40_35, m10 also to m11
+        anchors.insert((10, 0, 0), vec![to_overworld.clone(), to_interior, to_overworld.clone()]);
+        anchors.insert((12, 1, 0), vec![to_overworld]);
+        
+        let paths_to_global = WorldPositionTransformer::precompute_paths_to_global(&anchors, DEFAULT_GLOBAL_AREAS, DEFAULT_GLOBAL_AREAS);
+        let transformer = WorldPositionTransformer::from_parts(anchors, paths_to_global);
+        
+        assert_eq!(transformer.incoming_anchors(0x3C282300), vec![0x0A000000, 0x0C010000]);
+        assert_eq!(transformer.incoming_anchors(0x0B000000), vec![0x0A000000]);
+        assert!(transformer.incoming_anchors(0x0A000000).is_empty());
+    }
+    
+    #[test]
+    fn test_local_to_world_relative() {
+        let transformer = WorldPositionTransformer::empty();
+        let map_id = 0x3C282300; // m60_40_35_00
+        let origin = (10000.0, 20.0, 9000.0);
+        
+        let (gx, gy, gz, area) = transformer.local_to_world_with_global_map(map_id, 10.0, 5.0, 20.0).unwrap();
+        let relative = transformer.local_to_world_relative(map_id, 10.0, 5.0, 20.0, origin).unwrap();
+        assert_eq!(relative, (gx - origin.0, gy - origin.1, gz - origin.2, area));
+        
+        assert!(transformer.local_to_world_relative(0x63000000, 0.0, 0.0, 0.0, origin).is_err());
+    }
+    
+    #[test]
+    fn test_validate_global_separation() {
+        let anchor_to = |dst_area_no: u8| Anchor {
+            src_pos: (0.0, 0.0, 0.0),
+            dst_area_no,
+            dst_grid_x: 40,
+            dst_grid_z: 35,
+            dst_pos: (0.0, 0.0, 0.0),
+            origin: AnchorOrigin::Csv,
+        };
+        
+        let mut anchors: HashMap<(u8, u8, u8), Vec<Anchor>> = HashMap::new();
+        anchors.insert((10, 0, 0), vec![anchor_to(60)]);
+        anchors.insert((20, 0, 0), vec![anchor_to(60), anchor_to(61)]);
+        WorldPositionTransformer::add_inverse_anchors(&mut anchors);
+        let transformer = WorldPositionTransformer::from_parts(anchors, HashMap::new());
+        
+        let warnings = transformer.validate_global_separation();
+        assert_eq!(warnings, vec![AnchorWarning::MultipleGlobalMaps {
+            map_id: 0x14000000,
+            global_areas: vec![60, 61],
+        }]);
+        assert_eq!(
+            warnings[0].to_string(),
+            "m20_00_00_00 has direct anchors to several global maps: m60, m61"
+        );
+    }
+    
+    #[test]
+    fn test_neighbor_anchor_fallback() {
+        // m10_01_00 has an anchor, m10_02_00 (one tile east) has none
+        let mut anchors: HashMap<(u8, u8, u8), Vec<Anchor>> = HashMap::new();
+        anchors.insert((10, 1, 0), vec![Anchor {
+            src_pos: (0.0, 0.0, 0.0),
+            dst_area_no: 60,
+            dst_grid_x: 40,
+            dst_grid_z: 35,
+            dst_pos: (0.0, 0.0, 0.0),
+            origin: AnchorOrigin::Csv,
+        }]);
+        let transformer = WorldPositionTransformer::from_parts(anchors, HashMap::new());
+        let map_id = 0x0A020000; // m10_02_00_00
+        
+        // Disabled by default
+        assert!(transformer.local_to_world_first(map_id, 10.0, 5.0, 20.0).is_err());
+        
+        // The missing tile continues its neighbor 256 units further east
+        let transformer = transformer.with_neighbor_fallback(true);
+        let (gx, gy, gz, area) = transformer.local_to_world_with_global_map(map_id, 10.0, 5.0, 20.0).unwrap();
+        assert_eq!((gx, gy, gz, area), (10.0 + 256.0 + 40.0 * 256.0, 5.0, 20.0 + 35.0 * 256.0, 60));
+        
+        // Other areas don't borrow
+        assert!(transformer.local_to_world_first(0x0B000000, 0.0, 0.0, 0.0).is_err());
+    }
+    
+    #[test]
+    fn test_update_anchor_incremental() {
+        let anchor_to = |dst: (u8, u8, u8), dst_pos: (f32, f32, f32)| Anchor {
+            src_pos: (0.0, 0.0, 0.0),
+            dst_area_no: dst.0,
+            dst_grid_x: dst.1,
+            dst_grid_z: dst.2,
+            dst_pos,
+            origin: AnchorOrigin::Csv,
+        };
+        
+        // m10_01_00 -> m10_00_00 (dead end), m12_00_00 -> m60_40_35 (unrelated)
+        let mut anchors: HashMap<(u8, u8, u8), Vec<Anchor>> = HashMap::new();
+        anchors.insert((10, 1, 0), vec![anchor_to((10, 0, 0), (10.0, 0.0, 10.0))]);
+        anchors.insert((12, 0, 0), vec![anchor_to((60, 40, 35), (5.0, 0.0, 5.0))]);
+        WorldPositionTransformer::add_inverse_anchors(&mut anchors);
+        let paths_to_global = WorldPositionTransformer::precompute_paths_to_global(&anchors, DEFAULT_GLOBAL_AREAS, DEFAULT_GLOBAL_AREAS);
+        let mut transformer = WorldPositionTransformer::from_parts(anchors, paths_to_global);
+        
+        let dependent = 0x0A010000; // m10_01_00_00
+        let unrelated = 0x0C000000; // m12_00_00_00
+        assert!(transformer.local_to_world_first(dependent, 0.0, 0.0, 0.0).is_err());
+        let unrelated_before = transformer.local_to_world_first(unrelated, 1.0, 2.0, 3.0).unwrap();
+        
+        // Link the dead end to m60: m10_01_00 now converts through it
+        transformer.update_anchor(0x0A000000, anchor_to((60, 41, 35), (100.0, 0.0, 100.0)));
+        assert_eq!(transformer.conversion_hops(dependent), Some(2));
+        assert_eq!(
+            transformer.local_to_world_first(dependent, 0.0, 0.0, 0.0).unwrap(),
+            (110.0 + 41.0 * 256.0, 0.0, 110.0 + 35.0 * 256.0)
+        );
+        assert_eq!(transformer.local_to_world_first(unrelated, 1.0, 2.0, 3.0).unwrap(), unrelated_before);
+        
+        // Same result as a full rebuild
+        let rebuilt = WorldPositionTransformer::precompute_paths_to_global(&transformer.anchors, DEFAULT_GLOBAL_AREAS, DEFAULT_GLOBAL_AREAS);
+        let mut rebuilt_keys: Vec<_> = rebuilt.keys().collect();
+        let mut incremental_keys: Vec<_> = transformer.paths_to_global.keys().collect();
+        rebuilt_keys.sort();
+        incremental_keys.sort();
+        assert_eq!(rebuilt_keys, incremental_keys);
+    }
+    
+    #[test]
+    fn test_update_anchor_inverse_origin() {
+        let anchor_to = |dst_grid_x: u8, origin: AnchorOrigin| Anchor {
+            src_pos: (0.0, 0.0, 0.0),
+            dst_area_no: 60,
+            dst_grid_x,
+            dst_grid_z: 35,
+            dst_pos: (10.0, 0.0, 10.0),
+            origin,
+        };
+        let mut transformer = WorldPositionTransformer::empty();
+        
+        // The generated counterpart is an inverse whatever the added anchor's origin
+        transformer.update_anchor(0x0A000000, anchor_to(40, AnchorOrigin::Csv));
+        transformer.update_anchor(0x0B000000, anchor_to(41, AnchorOrigin::Inverse));
+        assert_eq!(transformer.anchors[&(60, 40, 35)].len(), 1);
+        assert_eq!(transformer.anchors[&(60, 40, 35)][0].origin, AnchorOrigin::Inverse);
+        assert_eq!(transformer.anchors[&(60, 41, 35)].len(), 1);
+        assert_eq!(transformer.anchors[&(60, 41, 35)][0].origin, AnchorOrigin::Inverse);
+    }
+    
+    #[test]
+    fn test_update_anchor_drops_conflicting_duplicates() {
+        // The same m10_00_00 destination written against m60_40_35 and, off by one grid, m60_41_35
+        let good = Anchor {
+            src_pos: (0.0, 0.0, 0.0),
+            dst_area_no: 60,
+            dst_grid_x: 40,
+            dst_grid_z: 35,
+            dst_pos: (10.0, 0.0, 10.0),
+            origin: AnchorOrigin::Csv,
+        };
+        let off_by_one = Anchor {
+            dst_grid_x: 41,
+            dst_pos: (10.0 - DEFAULT_TILE_SIZE, 0.0, 10.0),
+            ..good.clone()
+        };
+        let map_id = 0x0A000000; // m10_00_00_00
+        let expected = (11.0 + 40.0 * DEFAULT_TILE_SIZE, 0.0, 11.0 + 35.0 * DEFAULT_TILE_SIZE);
+        
+        // A worse duplicate of an existing anchor is discarded, without an inverse
+        let mut transformer = WorldPositionTransformer::empty();
+        transformer.update_anchor(map_id, good.clone());
+        transformer.update_anchor(map_id, off_by_one.clone());
+        assert_eq!(transformer.anchors[&(10, 0, 0)].len(), 1);
+        assert!(!transformer.anchors.contains_key(&(60, 41, 35)));
+        assert_eq!(transformer.local_to_world_first(map_id, 1.0, 0.0, 1.0).unwrap(), expected);
+        
+        // A better one replaces it, and the replaced anchor's inverse goes with it
+        let mut transformer = WorldPositionTransformer::empty();
+        transformer.update_anchor(map_id, off_by_one);
+        transformer.update_anchor(map_id, good);
+        assert_eq!(transformer.anchors[&(10, 0, 0)].len(), 1);
+        assert_eq!(transformer.anchors[&(10, 0, 0)][0].dst_grid_x, 40);
+        assert!(transformer.anchors[&(60, 41, 35)].is_empty());
+        assert_eq!(transformer.anchors[&(60, 40, 35)].len(), 1);
+        assert_eq!(transformer.local_to_world_first(map_id, 1.0, 0.0, 1.0).unwrap(), expected);
+    }
+    
+    #[test]
+    fn test_normalized_position() {
+        let transformer = WorldPositionTransformer::empty();
+        let m60_46_46 = 0x3C2E2E00;
+        
+        // Middle of m60: (11864.5, 11946.5) = tile 46,46 + (88.5, 170.5)
+        let (u, v) = transformer.normalized_position(m60_46_46, 88.5, 0.0, 170.5).unwrap();
+        assert!((u - 0.5).abs() < 0.01 && (v - 0.5).abs() < 0.01, "got ({}, {})", u, v);
+        
+        // Far outside clamps to the corner
+        assert_eq!(transformer.normalized_position(0x3C000000, 0.0, 0.0, 0.0), Some((0.0, 1.0)));
+        
+        // Custom bounds
+        let bounds = MapBounds { min_x: 11776.0, max_x: 12032.0, min_z: 11776.0, max_z: 12032.0 };
+        let transformer = transformer.with_map_bounds(60, bounds);
+        assert_eq!(transformer.normalized_position(m60_46_46, 64.0, 0.0, 192.0), Some((0.25, 0.25)));
+        
+        // Unknown tile
+        assert_eq!(transformer.normalized_position(0x0A000000, 0.0, 0.0, 0.0), None);
+    }
+    
+    #[test]
+    fn test_gzip_csv() {
+        use flate2::write::GzEncoder;
+        use std::io::Write;
+        
+        let csv = "header\n\
+                   0,0,0,0,0,10,0,0,0,1.0,2.0,3.0,60,40,35,0,100.0,50.0,100.0\n\
+                   0,0,0,0,0,11,0,0,0,1.0,2.0,3.0,10,0,0,0,4.0,5.0,6.0\n";
+        let dir = std::env::temp_dir();
+        let plain_path = dir.join(format!("gzip_test_{}.csv", std::process::id()));
+        let gz_path = dir.join(format!("gzip_test_{}.csv.gz", std::process::id()));
+        std::fs::write(&plain_path, csv).unwrap();
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(csv.as_bytes()).unwrap();
+        std::fs::write(&gz_path, encoder.finish().unwrap()).unwrap();
+        
+        let plain = WorldPositionTransformer::from_csv(&plain_path).unwrap();
+        let compressed = WorldPositionTransformer::from_csv(&gz_path).unwrap();
+        std::fs::remove_file(&plain_path).unwrap();
+        std::fs::remove_file(&gz_path).unwrap();
+        
+        assert_eq!(compressed.anchor_count(), plain.anchor_count());
+        assert_eq!(compressed.map_count(), plain.map_count());
+        assert_eq!(
+            compressed.local_to_world_first(0x0B000000, 1.0, 2.0, 3.0).unwrap(),
+            plain.local_to_world_first(0x0B000000, 1.0, 2.0, 3.0).unwrap()
+        );
+    }
+    
+    #[test]
+    fn test_tile_report() {
+        let anchor_to = |dst: (u8, u8, u8)| Anchor {
+            src_pos: (0.0, 0.0, 0.0),
+            dst_area_no: dst.0,
+            dst_grid_x: dst.1,
+            dst_grid_z: dst.2,
+            dst_pos: (0.0, 0.0, 0.0),
+            origin: AnchorOrigin::Csv,
+        };
+        
+        // m10_00_00 -> m60, m10_01_00 -> m10_00_00, m99_00_00 <-> m99_01_00 (isolated)
+        let mut anchors: HashMap<(u8, u8, u8), Vec<Anchor>> = HashMap::new();
+        anchors.insert((10, 0, 0), vec![anchor_to((60, 40, 35))]);
+        anchors.insert((10, 1, 0), vec![anchor_to((10, 0, 0))]);
+        anchors.insert((99, 0, 0), vec![anchor_to((99, 1, 0))]);
+        WorldPositionTransformer::add_inverse_anchors(&mut anchors);
+        let paths_to_global = WorldPositionTransformer::precompute_paths_to_global(&anchors, DEFAULT_GLOBAL_AREAS, DEFAULT_GLOBAL_AREAS);
+        let transformer = WorldPositionTransformer::from_parts(anchors, paths_to_global);
+        
+        let report: Vec<(u32, usize, Resolution)> = transformer
+            .tile_report()
+            .into_iter()
+            .map(|tile| (tile.map_id, tile.anchor_count, tile.resolution))
+            .collect();
+        assert_eq!(report, vec![
+            (0x0A000000, 2, Resolution::Direct),   // to m60 + inverse from m10_01
+            (0x0A010000, 1, Resolution::Path(2)),
+            (0x63000000, 1, Resolution::None),
+            (0x63010000, 1, Resolution::None),     // inverse only
+        ]);
+    }
+    
+    #[test]
+    fn test_local_to_world_or_local() {
+        let transformer = WorldPositionTransformer::empty();
+        
+        // m60_40_35_00 converts
+        let (gx, _, gz, area, converted) = transformer.local_to_world_or_local(0x3C282300, 1.0, 2.0, 3.0);
+        assert!(converted);
+        assert_eq!((gx, gz, area), (1.0 + 40.0 * 256.0, 3.0 + 35.0 * 256.0, 60));
+        
+        // m12_01_00_00 has no anchor: local coordinates, underground guess
+        assert_eq!(transformer.local_to_world_or_local(0x0C010000, 1.0, 2.0, 3.0), (1.0, 2.0, 3.0, 62, false));
+    }
 
+    #[test]
+    fn test_parallel_precompute_matches_serial() {
+        // Chains of interior tiles of various lengths, plus some dead ends
+        let mut csv = String::from("header\n");
+        for area in 10..30u8 {
+            csv.push_str(&format!("0,0,0,0,0,{},0,0,0,1.0,2.0,3.0,60,40,35,0,100.0,50.0,100.0\n", area));
+            for grid_x in 1..=area % 7 {
+                csv.push_str(&format!(
+                    "0,0,0,0,0,{},{},0,0,{}.0,0.0,0.0,{},{},0,0,10.0,0.0,-5.0\n",
+                    area, grid_x, grid_x, area, grid_x - 1
+                ));
+            }
+            csv.push_str(&format!("0,0,0,0,0,{},0,9,0,0.0,0.0,0.0,{},1,9,0,0.0,0.0,0.0\n", area + 100, area + 100));
+        }
+        let csv_path = std::env::temp_dir().join(format!("parallel_precompute_test_{}.csv", std::process::id()));
+        std::fs::write(&csv_path, csv).unwrap();
+        let serial = WorldPositionTransformer::from_csv(&csv_path).unwrap();
+        let parallel = WorldPositionTransformer::from_csv_with_options(&csv_path, DEFAULT_GLOBAL_AREAS, true).unwrap();
+        std::fs::remove_file(&csv_path).unwrap();
+        
+        let sorted = |t: &WorldPositionTransformer| {
+            let mut paths: Vec<_> = t.paths_to_global.iter().map(|(key, path)| format!("{:?} {:?}", key, path)).collect();
+            paths.sort();
+            paths
+        };
+        assert!(serial.paths_to_global.len() > 50);
+        assert_eq!(sorted(&serial), sorted(&parallel));
+        assert!(parallel.parallel_precompute);
+    }
+    
+    #[test]
+    fn test_allowed_global_maps() {
+        let csv = "header\n\
+            0,0,0,0,0,10,0,0,0,0.0,0.0,0.0,60,40,35,0,0.0,0.0,0.0\n\
+            0,0,0,0,0,21,0,0,0,0.0,0.0,0.0,61,45,40,0,0.0,0.0,0.0\n\
+            0,0,0,0,0,22,0,0,0,0.0,0.0,0.0,21,0,0,0,5.0,0.0,5.0\n\
+            0,0,0,0,0,11,0,0,0,0.0,0.0,0.0,60,40,35,0,0.0,0.0,0.0\n\
+            0,0,0,0,0,11,0,0,0,0.0,0.0,0.0,61,45,40,0,0.0,0.0,0.0\n";
+        let csv_path = std::env::temp_dir().join(format!("allowed_global_maps_test_{}.csv", std::process::id()));
+        std::fs::write(&csv_path, csv).unwrap();
+        let transformer = WorldPositionTransformer::from_csv(&csv_path).unwrap();
+        let base_game = WorldPositionTransformer::from_csv(&csv_path).unwrap().with_allowed_global_maps(&[60]);
+        let dlc = WorldPositionTransformer::from_csv(&csv_path).unwrap().with_allowed_global_maps(&[61]);
+        std::fs::remove_file(&csv_path).unwrap();
+        
+        let global_map = |t: &WorldPositionTransformer, map_id: u32| {
+            t.local_to_world_with_global_map(map_id, 1.0, 0.0, 1.0).map(|(_, _, _, global_map_id)| global_map_id).ok()
+        };
+        
+        // Unrestricted: m21/m22 (direct anchor / path) and the m61 tile convert to m61
+        assert_eq!(global_map(&transformer, 0x15000000), Some(61));
+        assert_eq!(global_map(&transformer, 0x16000000), Some(61));
+        assert_eq!(global_map(&transformer, 0x3D2D2800), Some(61));
+        
+        // Base game only: nothing may end on m61
+        assert_eq!(global_map(&base_game, 0x0A000000), Some(60));
+        assert_eq!(global_map(&base_game, 0x3C282300), Some(60));
+        for map_id in [0x15000000, 0x16000000, 0x3D2D2800] {
+            let result = base_game.local_to_world_with_global_map(map_id, 1.0, 0.0, 1.0);
+            assert!(matches!(result, Err(TransformError::UnknownMap(_))), "{:08X}", map_id);
+        }
+        
+        // m11 anchors to both: m60 is preferred unless disallowed
+        assert_eq!(global_map(&base_game, 0x0B000000), Some(60));
+        assert_eq!(global_map(&dlc, 0x0B000000), Some(61));
+    }
+    
+    #[test]
+    fn test_allowed_global_maps_rebuild_paths() {
+        let anchor_to = |dst: (u8, u8, u8)| Anchor {
+            src_pos: (0.0, 0.0, 0.0),
+            dst_area_no: dst.0,
+            dst_grid_x: dst.1,
+            dst_grid_z: dst.2,
+            dst_pos: (0.0, 0.0, 0.0),
+            origin: AnchorOrigin::Csv,
+        };
+        
+        // m20_00_00 -> m61 directly, and m20_00_00 -> m21_00_00 -> m60
+        let mut anchors: HashMap<(u8, u8, u8), Vec<Anchor>> = HashMap::new();
+        anchors.insert((20, 0, 0), vec![anchor_to((61, 45, 40)), anchor_to((21, 0, 0))]);
+        anchors.insert((21, 0, 0), vec![anchor_to((60, 40, 35))]);
+        WorldPositionTransformer::add_inverse_anchors(&mut anchors);
+        let paths_to_global = WorldPositionTransformer::precompute_paths_to_global(&anchors, DEFAULT_GLOBAL_AREAS, DEFAULT_GLOBAL_AREAS);
+        let unrestricted = WorldPositionTransformer::from_parts(anchors.clone(), paths_to_global);
+        let base_game = WorldPositionTransformer::from_parts(anchors, HashMap::new()).with_allowed_global_maps(&[60]);
+        
+        let map_id = 0x14000000; // m20_00_00_00
+        assert_eq!(unrestricted.local_to_world_with_global_map(map_id, 1.0, 0.0, 1.0).unwrap().3, 61);
+        assert_eq!(unrestricted.conversion_hops(map_id), Some(1));
+        
+        // The direct m61 anchor is disallowed, so the two-hop path to m60 is used
+        let (x, _, z, global_map_id) = base_game.local_to_world_with_global_map(map_id, 1.0, 0.0, 1.0).unwrap();
+        assert_eq!(global_map_id, 60);
+        assert_eq!((x, z), (1.0 + 40.0 * DEFAULT_TILE_SIZE, 1.0 + 35.0 * DEFAULT_TILE_SIZE));
+        assert_eq!(base_game.conversion_hops(map_id), Some(2));
+    }
+}
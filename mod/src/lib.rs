@@ -24,9 +24,13 @@
 
 mod config;
 pub mod coordinate_transformer;
+pub mod map_icons;
+pub mod position_pump;
 mod realtime_client;
 mod route;
-mod tracker;
+pub mod route_analysis;
+pub mod route_export;
+pub mod tracker;
 mod ui;
 
 // =============================================================================
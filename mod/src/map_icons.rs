@@ -0,0 +1,117 @@
+// Map icon data (bonfires and map points) as produced by convert-map-icons
+//
+// Shared between the converter binary (which writes `map_data_processed.json`)
+// and the tracker (which reads it back to snap points to Sites of Grace).
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+// =============================================================================
+// DATA STRUCTURES (matching map_data_processed.json)
+// =============================================================================
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct MapIconText {
+    pub text_id: u64,
+    /// 0 = location name, 1 = character name
+    pub text_type: u32,
+    pub text: Option<String>,
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputMapIcon {
+    pub id: u64,
+    pub icon_id: u32,
+    pub event_flag_id: u64,
+    // Original local coordinates
+    pub area_no: u8,
+    pub grid_x_no: u8,
+    pub grid_z_no: u8,
+    pub pos_x: f32,
+    pub pos_y: f32,
+    pub pos_z: f32,
+    // Converted global coordinates
+    pub global_x: f32,
+    pub global_y: f32,
+    pub global_z: f32,
+    // Map string (e.g., "m60" or "m61")
+    pub map_id: String,
+    // Text data
+    pub texts: Vec<MapIconText>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputMapData {
+    pub bonfires: Vec<OutputMapIcon>,
+    pub map_points: Vec<OutputMapIcon>,
+    // Statistics
+    pub total_count: usize,
+    pub converted_count: usize,
+    pub failed_count: usize,
+    pub failed_maps: Vec<String>,
+    // Coordinate dataset the icons were converted with (None in older files)
+    #[serde(default)]
+    pub dataset_version: Option<String>,
+}
+
+impl OutputMapIcon {
+    /// Display name: location text first, then character text, then any text
+    pub fn primary_text(&self) -> Option<&str> {
+        let text_of_type = |text_type: u32| {
+            self.texts
+                .iter()
+                .find(|t| t.text_type == text_type)
+                .and_then(|t| t.text.as_deref())
+        };
+
+        text_of_type(0)
+            .or_else(|| text_of_type(1))
+            .or_else(|| self.texts.first().and_then(|t| t.text.as_deref()))
+    }
+
+    /// Whether this icon is drawn on the given global map
+    ///
+    /// Underground (62) points share the m60 coordinate space.
+    pub fn is_on_global_map(&self, global_map_id: u8) -> bool {
+        let coordinate_space = if global_map_id == 62 { 60 } else { global_map_id };
+        self.map_id == format!("m{}", coordinate_space)
+    }
+}
+
+// =============================================================================
+// HELPERS
+// =============================================================================
+
+/// Load a `map_data_processed.json` file
+pub fn load_map_data<P: AsRef<Path>>(path: P) -> Result<OutputMapData, String> {
+    let json = fs::read_to_string(path.as_ref())
+        .map_err(|e| format!("Failed to read map data: {}", e))?;
+    serde_json::from_str(&json)
+        .map_err(|e| format!("Failed to parse map data: {}", e))
+}
+
+/// Find the icon closest to a global position on the same global map, within `radius`
+pub fn nearest_icon(
+    icons: &[OutputMapIcon],
+    global_map_id: u8,
+    (x, y, z): (f32, f32, f32),
+    radius: f32,
+) -> Option<&OutputMapIcon> {
+    icons
+        .iter()
+        .filter(|icon| icon.is_on_global_map(global_map_id))
+        .map(|icon| {
+            let dx = icon.global_x - x;
+            let dy = icon.global_y - y;
+            let dz = icon.global_z - z;
+            (icon, (dx * dx + dy * dy + dz * dz).sqrt())
+        })
+        .filter(|&(_, distance)| distance <= radius)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(icon, _)| icon)
+}
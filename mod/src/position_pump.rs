@@ -0,0 +1,217 @@
+// Background conversion of raw position samples
+//
+// The hook thread only reads the raw position and hands it over; conversion to
+// global coordinates and point building happen on a worker thread, the same way
+// the realtime client sends points from its own thread.
+
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use hudhook::tracing::{info, warn};
+
+use crate::coordinate_transformer::{TransformError, WorldPositionTransformer};
+use crate::route::RoutePoint;
+use crate::tracker::RouteTracker;
+
+/// Everything read from the game for one sample, before conversion
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawSample {
+    /// Local position within the tile
+    pub position: [f32; 3],
+    pub map_id: u32,
+    /// Unix epoch milliseconds when the sample was read
+    pub timestamp_ms: u64,
+    /// Orientation quaternion `[x, y, z, w]`, if the game exposes it
+    pub orientation: Option<[f32; 4]>,
+    /// Game frame counter, if the game exposes it
+    pub frame: Option<u64>,
+    /// Whole position array (`recording.capture_raw_array`)
+    pub raw: Option<[f32; 5]>,
+}
+
+impl RawSample {
+    /// Convert to a route point
+    ///
+    /// On a failed conversion the point keeps its local coordinates and the
+    /// error is returned alongside it, for the caller to log.
+    pub fn convert(&self, transformer: &WorldPositionTransformer) -> (RoutePoint, Option<TransformError>) {
+        let [x, y, z] = self.position;
+        let converted = transformer.local_to_world_with_global_map(self.map_id, x, y, z);
+        let error = converted.as_ref().err().cloned();
+        let hops = transformer.conversion_hops(self.map_id);
+
+        let mut point = RouteTracker::build_point(self.position, self.map_id, self.timestamp_ms, converted, hops);
+        point.set_orientation(self.orientation);
+        point.frame = self.frame;
+        point.raw = self.raw;
+        (point, error)
+    }
+}
+
+/// What a sample was taken for (both when recording and streaming are due together)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SampleTarget {
+    pub record: bool,
+    pub stream: bool,
+}
+
+/// A converted sample coming back from the worker
+#[derive(Debug)]
+pub struct ConvertedSample {
+    pub point: RoutePoint,
+    /// Why the conversion failed (the point then has local coordinates)
+    pub error: Option<TransformError>,
+    pub target: SampleTarget,
+}
+
+/// Message types for the pump worker thread
+enum PumpMessage {
+    /// Raw sample to convert
+    Sample(RawSample, SampleTarget),
+    /// Shutdown the worker thread
+    Shutdown,
+}
+
+/// Converts raw position samples to route points on a worker thread
+///
+/// Samples are converted in the order they were pushed; converted samples are
+/// collected with `drain` (or `wait_all` when they are needed right away).
+pub struct PositionPump {
+    /// Channel to the worker thread
+    sender: Sender<PumpMessage>,
+    /// Converted samples coming back from the worker (in a mutex only so the
+    /// tracker stays `Sync` for the render hook; always accessed through `&mut`)
+    converted: Mutex<Receiver<ConvertedSample>>,
+    /// Samples pushed but not collected yet
+    in_flight: usize,
+    /// Worker thread handle
+    _thread_handle: JoinHandle<()>,
+}
+
+impl PositionPump {
+    /// Start a pump converting with `transformer` (shared with the caller)
+    pub fn new(transformer: Arc<WorldPositionTransformer>) -> Self {
+        let (sender, receiver) = mpsc::channel::<PumpMessage>();
+        let (converted_sender, converted) = mpsc::channel::<ConvertedSample>();
+
+        let thread_handle = thread::spawn(move || {
+            Self::worker_thread(transformer, receiver, converted_sender);
+        });
+
+        Self {
+            sender,
+            converted: Mutex::new(converted),
+            in_flight: 0,
+            _thread_handle: thread_handle,
+        }
+    }
+
+    /// Queue a raw sample for conversion (non-blocking)
+    pub fn push(&mut self, sample: RawSample, target: SampleTarget) {
+        match self.sender.send(PumpMessage::Sample(sample, target)) {
+            Ok(()) => self.in_flight += 1,
+            Err(e) => warn!("Failed to queue position sample: {}", e),
+        }
+    }
+
+    /// Number of samples pushed and not collected yet
+    pub fn in_flight(&self) -> usize {
+        self.in_flight
+    }
+
+    /// All converted samples ready so far, in order
+    pub fn drain(&mut self) -> Vec<ConvertedSample> {
+        let converted = self.converted.get_mut().unwrap_or_else(PoisonError::into_inner);
+        let ready: Vec<ConvertedSample> = converted.try_iter().collect();
+        self.in_flight -= ready.len();
+        ready
+    }
+
+    /// Wait up to `timeout` for every pushed sample to be converted, in order
+    ///
+    /// Returns what arrived before the timeout (or before the worker died).
+    pub fn wait_all(&mut self, timeout: Duration) -> Vec<ConvertedSample> {
+        let deadline = Instant::now() + timeout;
+        let converted = self.converted.get_mut().unwrap_or_else(PoisonError::into_inner);
+        let mut ready = Vec::with_capacity(self.in_flight);
+        while self.in_flight > 0 {
+            match converted.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(sample) => {
+                    self.in_flight -= 1;
+                    ready.push(sample);
+                }
+                Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => {
+                    warn!("Position pump: {} sample(s) not converted in time", self.in_flight);
+                    break;
+                }
+            }
+        }
+        ready
+    }
+
+    /// Worker thread: convert samples until shutdown
+    fn worker_thread(
+        transformer: Arc<WorldPositionTransformer>,
+        receiver: Receiver<PumpMessage>,
+        converted: Sender<ConvertedSample>,
+    ) {
+        while let Ok(PumpMessage::Sample(sample, target)) = receiver.recv() {
+            let (point, error) = sample.convert(&transformer);
+
+            if converted.send(ConvertedSample { point, error, target }).is_err() {
+                break; // Pump dropped
+            }
+        }
+
+        info!("Position pump shutting down");
+    }
+}
+
+impl Drop for PositionPump {
+    fn drop(&mut self) {
+        // Signal shutdown to the worker thread
+        let _ = self.sender.send(PumpMessage::Shutdown);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pump_converts_in_order() {
+        let mut pump = PositionPump::new(Arc::new(WorldPositionTransformer::empty()));
+        let target = SampleTarget { record: true, stream: false };
+        for i in 0..100u64 {
+            let sample = RawSample {
+                position: [i as f32, 0.0, 0.0],
+                map_id: 0x3C282300, // m60_40_35_00
+                timestamp_ms: i,
+                orientation: None,
+                frame: Some(i),
+                raw: None,
+            };
+            pump.push(sample, target);
+        }
+
+        let mut converted = Vec::new();
+        let start = Instant::now();
+        while converted.len() < 100 && start.elapsed() < Duration::from_secs(5) {
+            converted.extend(pump.drain());
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        assert_eq!(converted.len(), 100);
+        assert_eq!(pump.in_flight(), 0);
+        for (i, sample) in converted.iter().enumerate() {
+            assert_eq!(sample.point.timestamp_ms, i as u64);
+            assert_eq!(sample.point.frame, Some(i as u64));
+            assert_eq!(sample.point.global_x, i as f32 + 40.0 * 256.0);
+            assert_eq!(sample.point.global_map_id, 60);
+            assert_eq!(sample.target, target);
+            assert!(sample.error.is_none());
+        }
+    }
+}
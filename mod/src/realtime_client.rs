@@ -2,11 +2,18 @@
 
 use hudhook::tracing::{debug, error, info, warn};
 use serde::Serialize;
-use std::sync::mpsc::{self, Sender, TryRecvError};
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::Arc;
 use std::thread::{self, JoinHandle};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use crate::route::RoutePoint;
+use crate::config::{AuthHeader, PayloadFormat};
+use crate::route::{DeathEvent, MapTransition, RoutePoint};
 
 // =============================================================================
 // DATA STRUCTURES
@@ -33,8 +40,22 @@ struct RoutePointRequest {
     map_id_str: String,
     #[serde(rename = "globalMapId")]
     global_map_id: u8,
+    #[serde(rename = "globalMapLabel")]
+    global_map_label: &'static str,
     #[serde(rename = "timestampMs")]
     timestamp_ms: u64,
+    #[serde(rename = "epochMs")]
+    epoch_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frame: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    qx: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    qy: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    qz: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    qw: Option<f32>,
 }
 
 impl From<&RoutePoint> for RoutePointRequest {
@@ -49,19 +70,458 @@ impl From<&RoutePoint> for RoutePointRequest {
             map_id: point.map_id,
             map_id_str: point.map_id_str.clone(),
             global_map_id: point.global_map_id,
+            global_map_label: point.global_map_label,
             timestamp_ms: point.timestamp_ms,
+            epoch_ms: point.epoch_ms,
+            frame: point.frame,
+            qx: point.qx,
+            qy: point.qy,
+            qz: point.qz,
+            qw: point.qw,
         }
     }
 }
 
+/// Request body for sending a map transition event to the backend
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MapTransitionRequest {
+    old_map_id: u32,
+    old_map_id_str: String,
+    old_global_map_id: u8,
+    new_map_id: u32,
+    new_map_id_str: String,
+    new_global_map_id: u8,
+    timestamp_ms: u64,
+}
+
+impl From<&MapTransition> for MapTransitionRequest {
+    fn from(transition: &MapTransition) -> Self {
+        Self {
+            old_map_id: transition.old_map_id,
+            old_map_id_str: transition.old_map_id_str.clone(),
+            old_global_map_id: transition.old_global_map_id,
+            new_map_id: transition.new_map_id,
+            new_map_id_str: transition.new_map_id_str.clone(),
+            new_global_map_id: transition.new_global_map_id,
+            timestamp_ms: transition.timestamp_ms,
+        }
+    }
+}
+
+/// Request body for sending a player death event to the backend
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeathRequest {
+    global_x: f32,
+    global_y: f32,
+    global_z: f32,
+    map_id: u32,
+    map_id_str: String,
+    global_map_id: u8,
+    timestamp_ms: u64,
+    death_count: u32,
+}
+
+impl From<&DeathEvent> for DeathRequest {
+    fn from(death: &DeathEvent) -> Self {
+        Self {
+            global_x: death.global_x,
+            global_y: death.global_y,
+            global_z: death.global_z,
+            map_id: death.map_id,
+            map_id_str: death.map_id_str.clone(),
+            global_map_id: death.global_map_id,
+            timestamp_ms: death.timestamp_ms,
+            death_count: death.death_count,
+        }
+    }
+}
+
+/// Quantization step of delta-encoded coordinates (in-game units)
+const DELTA_QUANTUM: f32 = 0.01;
+
+/// A full keyframe is sent at least every this many delta-encoded points
+const DELTA_KEYFRAME_INTERVAL: usize = 50;
+
+/// Point encoded relative to the previous point of the stream
+/// 
+/// Coordinate deltas are in units of `DELTA_QUANTUM`. Map fields are those of
+/// the last keyframe (a map change always produces a keyframe).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RoutePointDelta {
+    dx: i32,
+    dy: i32,
+    dz: i32,
+    d_global_x: i32,
+    d_global_y: i32,
+    d_global_z: i32,
+    dt_ms: u64,
+}
+
+/// Entry of a delta-encoded batch
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+enum EncodedPoint {
+    /// Absolute point, resets the reference position
+    Keyframe(RoutePointRequest),
+    /// Offset from the previous point
+    Delta(RoutePointDelta),
+}
+
+/// Request body for sending delta-encoded route points to the backend
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeltaBatchRequest {
+    quantum: f32,
+    points: Vec<EncodedPoint>,
+}
+
+/// Quantized position of the last encoded point (what the backend reconstructs)
+#[derive(Debug, Clone, Copy)]
+struct QuantizedPoint {
+    local: [i64; 3],
+    global: [i64; 3],
+    map_id: u32,
+    global_map_id: u8,
+    timestamp_ms: u64,
+}
+
+impl QuantizedPoint {
+    fn from_point(point: &RoutePoint) -> Self {
+        let quantize = |value: f32| (value / DELTA_QUANTUM).round() as i64;
+        Self {
+            local: [quantize(point.x), quantize(point.y), quantize(point.z)],
+            global: [quantize(point.global_x), quantize(point.global_y), quantize(point.global_z)],
+            map_id: point.map_id,
+            global_map_id: point.global_map_id,
+            timestamp_ms: point.timestamp_ms,
+        }
+    }
+}
+
+/// Stateful delta encoder for the realtime stream
+/// 
+/// The backend rebuilds each point by adding `delta * quantum` to the previous
+/// one, starting from the last keyframe. Deltas are taken between quantized
+/// positions so rounding errors don't accumulate.
+struct DeltaEncoder {
+    keyframe_interval: usize,
+    /// Points encoded since the last keyframe
+    since_keyframe: usize,
+    previous: Option<QuantizedPoint>,
+}
+
+impl DeltaEncoder {
+    fn new(keyframe_interval: usize) -> Self {
+        Self {
+            keyframe_interval,
+            since_keyframe: 0,
+            previous: None,
+        }
+    }
+
+    /// Forget the reference position (the next point will be a keyframe)
+    fn reset(&mut self) {
+        self.previous = None;
+    }
+
+    fn encode(&mut self, points: &[RoutePoint]) -> Vec<EncodedPoint> {
+        points.iter().map(|point| self.encode_point(point)).collect()
+    }
+
+    fn encode_point(&mut self, point: &RoutePoint) -> EncodedPoint {
+        let current = QuantizedPoint::from_point(point);
+        let delta = self.previous
+            .filter(|previous| {
+                self.since_keyframe < self.keyframe_interval
+                    && previous.map_id == current.map_id
+                    && previous.global_map_id == current.global_map_id
+            })
+            .and_then(|previous| Self::delta(&previous, &current));
+
+        self.previous = Some(current);
+        match delta {
+            Some(delta) => {
+                self.since_keyframe += 1;
+                EncodedPoint::Delta(delta)
+            }
+            None => {
+                self.since_keyframe = 0;
+                // The backend starts again from the quantized keyframe position
+                let mut request = RoutePointRequest::from(point);
+                [request.x, request.y, request.z] = current.local.map(|v| v as f32 * DELTA_QUANTUM);
+                [request.global_x, request.global_y, request.global_z] =
+                    current.global.map(|v| v as f32 * DELTA_QUANTUM);
+                EncodedPoint::Keyframe(request)
+            }
+        }
+    }
+
+    /// Delta between two quantized points (None if it doesn't fit or time went backwards)
+    fn delta(previous: &QuantizedPoint, current: &QuantizedPoint) -> Option<RoutePointDelta> {
+        let d = |from: i64, to: i64| i32::try_from(to - from).ok();
+        Some(RoutePointDelta {
+            dx: d(previous.local[0], current.local[0])?,
+            dy: d(previous.local[1], current.local[1])?,
+            dz: d(previous.local[2], current.local[2])?,
+            d_global_x: d(previous.global[0], current.global[0])?,
+            d_global_y: d(previous.global[1], current.global[1])?,
+            d_global_z: d(previous.global[2], current.global[2])?,
+            dt_ms: current.timestamp_ms.checked_sub(previous.timestamp_ms)?,
+        })
+    }
+}
+
+/// Events posted to their own endpoint, in order with the points
+enum RealtimeEvent {
+    /// `POST /api/MapTransition`
+    MapTransition(MapTransition),
+    /// `POST /api/Death`
+    Death(DeathEvent),
+}
+
 /// Message types for the background sender thread
 enum SenderMessage {
     /// Send a batch of route points
     SendPoints(Vec<RoutePoint>),
+    /// Send an event (after any pending points)
+    SendEvent(RealtimeEvent),
+    /// Send pending points now instead of waiting for a full batch
+    /// (the sender, if any, is notified once they are sent)
+    Flush(Option<Sender<()>>),
     /// Shutdown the sender thread
     Shutdown,
 }
 
+/// Coalesces repeated network errors so an outage doesn't flood the log
+/// 
+/// The first error is logged, further errors within `window` are only counted
+/// and reported together once the window is over.
+struct ErrorThrottle {
+    window: Duration,
+    /// Start of the current reporting window (None while the backend is reachable)
+    window_start: Option<Instant>,
+    /// Errors not reported yet
+    suppressed: u32,
+}
+
+impl ErrorThrottle {
+    fn new(window: Duration) -> Self {
+        Self {
+            window,
+            window_start: None,
+            suppressed: 0,
+        }
+    }
+
+    /// Register an error; returns the number of errors to report if it should be logged
+    fn record_error(&mut self, now: Instant) -> Option<u32> {
+        match self.window_start {
+            Some(start) if now.duration_since(start) < self.window => {
+                self.suppressed += 1;
+                None
+            }
+            _ => {
+                self.window_start = Some(now);
+                Some(std::mem::take(&mut self.suppressed) + 1)
+            }
+        }
+    }
+
+    /// Register a success; returns the number of errors that were not reported
+    fn record_success(&mut self) -> u32 {
+        self.window_start = None;
+        std::mem::take(&mut self.suppressed)
+    }
+
+    /// Whether errors are currently being coalesced
+    fn is_suppressing(&self) -> bool {
+        self.window_start.is_some()
+    }
+}
+
+// =============================================================================
+// PROTOBUF ENCODING
+// =============================================================================
+
+// Hand-written encoder for this schema (`payload_format = "protobuf"`):
+//
+//     message RoutePoint {
+//         float x = 1;  float y = 2;  float z = 3;
+//         float global_x = 4;  float global_y = 5;  float global_z = 6;
+//         uint32 map_id = 7;  string map_id_str = 8;
+//         uint32 global_map_id = 9;  string global_map_label = 10;
+//         uint64 timestamp_ms = 11;  uint64 epoch_ms = 12;
+//         optional float qx = 13;  optional float qy = 14;
+//         optional float qz = 15;  optional float qw = 16;
+//         optional uint64 frame = 17;
+//     }
+//     message RoutePointBatch { repeated RoutePoint points = 1; }
+
+/// Protobuf wire types used by the schema
+const WIRE_VARINT: u32 = 0;
+const WIRE_LENGTH_DELIMITED: u32 = 2;
+const WIRE_FIXED32: u32 = 5;
+
+fn put_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn put_key(buf: &mut Vec<u8>, field: u32, wire_type: u32) {
+    put_varint(buf, ((field << 3) | wire_type) as u64);
+}
+
+fn put_float(buf: &mut Vec<u8>, field: u32, value: f32) {
+    put_key(buf, field, WIRE_FIXED32);
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_uint(buf: &mut Vec<u8>, field: u32, value: u64) {
+    put_key(buf, field, WIRE_VARINT);
+    put_varint(buf, value);
+}
+
+fn put_bytes(buf: &mut Vec<u8>, field: u32, bytes: &[u8]) {
+    put_key(buf, field, WIRE_LENGTH_DELIMITED);
+    put_varint(buf, bytes.len() as u64);
+    buf.extend_from_slice(bytes);
+}
+
+impl RoutePointRequest {
+    /// Encode as a protobuf `RoutePoint` message
+    fn encode_protobuf(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(96);
+        put_float(&mut buf, 1, self.x);
+        put_float(&mut buf, 2, self.y);
+        put_float(&mut buf, 3, self.z);
+        put_float(&mut buf, 4, self.global_x);
+        put_float(&mut buf, 5, self.global_y);
+        put_float(&mut buf, 6, self.global_z);
+        put_uint(&mut buf, 7, self.map_id as u64);
+        put_bytes(&mut buf, 8, self.map_id_str.as_bytes());
+        put_uint(&mut buf, 9, self.global_map_id as u64);
+        put_bytes(&mut buf, 10, self.global_map_label.as_bytes());
+        put_uint(&mut buf, 11, self.timestamp_ms);
+        put_uint(&mut buf, 12, self.epoch_ms);
+        for (field, value) in [(13, self.qx), (14, self.qy), (15, self.qz), (16, self.qw)] {
+            if let Some(value) = value {
+                put_float(&mut buf, field, value);
+            }
+        }
+        if let Some(frame) = self.frame {
+            put_uint(&mut buf, 17, frame);
+        }
+        buf
+    }
+}
+
+/// Encode points as a protobuf `RoutePointBatch`
+fn encode_protobuf_batch(points: &[RoutePoint]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for point in points {
+        put_bytes(&mut buf, 1, &RoutePointRequest::from(point).encode_protobuf());
+    }
+    buf
+}
+
+// =============================================================================
+// HTTP
+// =============================================================================
+
+/// Response to a POST, whatever its status code
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpResponse {
+    pub status: u16,
+    /// Value of the `Retry-After` header, if any
+    pub retry_after: Option<String>,
+    pub body: String,
+}
+
+/// Sends HTTP requests for the realtime client (replaceable in tests)
+pub trait HttpPoster: Send {
+    /// POST a JSON `body` to `url`; `Err` is a transport error (no response received)
+    fn post_json(&self, url: &str, headers: &[(&str, &str)], body: &str) -> Result<HttpResponse, String>;
+    
+    /// POST a binary `body` to `url` (protobuf payloads), same error semantics as `post_json`
+    fn post_bytes(&self, url: &str, headers: &[(&str, &str)], body: &[u8]) -> Result<HttpResponse, String>;
+}
+
+/// Default poster, backed by `ureq`
+pub struct UreqPoster;
+
+impl HttpPoster for UreqPoster {
+    fn post_json(&self, url: &str, headers: &[(&str, &str)], body: &str) -> Result<HttpResponse, String> {
+        self.post_bytes(url, headers, body.as_bytes())
+    }
+    
+    fn post_bytes(&self, url: &str, headers: &[(&str, &str)], body: &[u8]) -> Result<HttpResponse, String> {
+        let mut request = ureq::post(url).timeout(Duration::from_secs(5));
+        for &(name, value) in headers {
+            request = request.set(name, value);
+        }
+        
+        let response = match request.send_bytes(body) {
+            Ok(response) => response,
+            Err(ureq::Error::Status(_, response)) => response,
+            Err(ureq::Error::Transport(e)) => return Err(e.to_string()),
+        };
+        Ok(HttpResponse {
+            status: response.status(),
+            retry_after: response.header("Retry-After").map(str::to_string),
+            body: response.into_string().unwrap_or_default(),
+        })
+    }
+}
+
+/// Maximum number of rate-limit waits per request before 429s count as failures
+const MAX_RATE_LIMIT_WAITS: u32 = 10;
+
+/// How failed requests are retried
+struct RetryPolicy {
+    /// Attempts per request (rate-limited attempts not included)
+    max_retries: u32,
+    /// Upper bound for a `Retry-After` wait
+    max_retry_after: Duration,
+}
+
+impl RetryPolicy {
+    /// Wait requested by a 429 response (`Retry-After` in seconds, 1s if missing)
+    fn retry_after(&self, retry_after_header: Option<&str>) -> Duration {
+        retry_after_header
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(Duration::from_secs(1))
+            .min(self.max_retry_after)
+    }
+}
+
+/// Pending points above which batches are merged into larger requests
+const COALESCE_THRESHOLD: usize = 50;
+
+/// Most points in one coalesced request (roughly 100 KB of JSON)
+const MAX_COALESCED_POINTS: usize = 500;
+
+/// Backend connection used by the sender thread
+struct Connection {
+    poster: Box<dyn HttpPoster>,
+    /// Authentication header name and value (see `AuthHeader::header`), None without auth
+    auth: Option<(&'static str, String)>,
+    retry: RetryPolicy,
+    throttle: ErrorThrottle,
+    /// Encoding of plain (non-delta) point batches
+    payload_format: PayloadFormat,
+    /// Points queued but not yet sent, shared with `RealtimeClient::queue_depth`
+    queue_depth: Arc<AtomicUsize>,
+    /// Sequence number of the next point batch (`X-Batch-Seq`), so the backend can spot lost batches
+    batch_seq: u64,
+}
+
 // =============================================================================
 // REALTIME CLIENT
 // =============================================================================
@@ -70,25 +530,46 @@ enum SenderMessage {
 pub struct RealtimeClient {
     /// Backend API URL
     backend_url: String,
-    /// Push key for authentication
-    push_key: String,
+    /// Push key for authentication (None if the backend doesn't require one)
+    push_key: Option<String>,
     /// Channel sender for sending points to background thread
     sender: Sender<SenderMessage>,
+    /// Points queued but not yet sent (decremented by the sender thread)
+    queue_depth: Arc<AtomicUsize>,
+    /// Cleared if the sender thread panicked (nothing is sent anymore)
+    healthy: Arc<AtomicBool>,
     /// Background sender thread handle
     _thread_handle: JoinHandle<()>,
 }
 
 impl RealtimeClient {
     /// Create a new realtime client
-    pub fn new(backend_url: String, push_key: String) -> Self {
+    /// 
+    /// With `delta_encoding`, point batches are sent delta-encoded to `/api/RoutePoints/delta`;
+    /// otherwise they are encoded as `payload_format`. Rate-limited requests wait for the backend's `Retry-After`, up to `max_retry_after`.
+    /// Requests go through `poster` if given, `ureq` otherwise. Without `push_key`
+    /// no authentication header is sent.
+    pub fn new(
+        backend_url: String,
+        push_key: Option<String>,
+        auth_header: AuthHeader,
+        delta_encoding: bool,
+        payload_format: PayloadFormat,
+        max_retry_after: Duration,
+        poster: Option<Box<dyn HttpPoster>>,
+    ) -> Self {
         let (sender, receiver) = mpsc::channel::<SenderMessage>();
         
         let url = backend_url.clone();
-        let key = push_key.clone();
+        let queue_depth = Arc::new(AtomicUsize::new(0));
+        let mut connection = Connection::new(push_key.as_deref(), auth_header, max_retry_after, poster, queue_depth.clone());
+        connection.payload_format = payload_format;
         
         // Spawn background thread for sending points
+        let healthy = Arc::new(AtomicBool::new(true));
+        let thread_healthy = healthy.clone();
         let thread_handle = thread::spawn(move || {
-            Self::sender_thread(url, key, receiver);
+            Self::run_guarded(&thread_healthy, || Self::sender_thread(url, connection, delta_encoding, receiver));
         });
 
         info!("Realtime client initialized: backend={}", backend_url);
@@ -97,6 +578,79 @@ impl RealtimeClient {
             backend_url,
             push_key,
             sender,
+            queue_depth,
+            healthy,
+            _thread_handle: thread_handle,
+        }
+    }
+
+    /// Create a client streaming points over one long-lived chunked POST
+    ///
+    /// Each point is written as a JSON line to `/api/RoutePoints/stream`, and the
+    /// request is reopened with backoff when it fails. Map transitions and deaths
+    /// are still sent as separate requests.
+    pub fn new_http_stream(
+        backend_url: String,
+        push_key: Option<String>,
+        auth_header: AuthHeader,
+        max_retry_after: Duration,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel::<SenderMessage>();
+
+        let url = backend_url.clone();
+        let queue_depth = Arc::new(AtomicUsize::new(0));
+        let connection = Connection::new(push_key.as_deref(), auth_header, max_retry_after, None, queue_depth.clone());
+
+        let healthy = Arc::new(AtomicBool::new(true));
+        let thread_healthy = healthy.clone();
+        let thread_handle = thread::spawn(move || {
+            Self::run_guarded(&thread_healthy, || Self::stream_sender_thread(url, connection, receiver, StreamTarget::Http));
+        });
+
+        info!("Realtime client initialized (HTTP stream): backend={}", backend_url);
+
+        Self {
+            backend_url,
+            push_key,
+            sender,
+            queue_depth,
+            healthy,
+            _thread_handle: thread_handle,
+        }
+    }
+
+    /// Create a client writing points to a local IPC endpoint
+    ///
+    /// Each point is written as a JSON line to the named pipe (Windows, e.g.
+    /// `\\.\pipe\er-route-tracker`) or Unix socket at `path`, reconnecting when the
+    /// peer restarts. Map transitions and deaths are still posted to `backend_url`.
+    pub fn new_ipc(
+        path: PathBuf,
+        backend_url: String,
+        push_key: Option<String>,
+        auth_header: AuthHeader,
+        max_retry_after: Duration,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel::<SenderMessage>();
+
+        let url = backend_url.clone();
+        let queue_depth = Arc::new(AtomicUsize::new(0));
+        let connection = Connection::new(push_key.as_deref(), auth_header, max_retry_after, None, queue_depth.clone());
+
+        info!("Realtime client initialized (IPC): path={}", path.display());
+
+        let healthy = Arc::new(AtomicBool::new(true));
+        let thread_healthy = healthy.clone();
+        let thread_handle = thread::spawn(move || {
+            Self::run_guarded(&thread_healthy, || Self::stream_sender_thread(url, connection, receiver, StreamTarget::Ipc(path)));
+        });
+
+        Self {
+            backend_url,
+            push_key,
+            sender,
+            queue_depth,
+            healthy,
             _thread_handle: thread_handle,
         }
     }
@@ -112,34 +666,137 @@ impl RealtimeClient {
             return;
         }
 
+        self.queue_depth.fetch_add(points.len(), Ordering::Relaxed);
         if let Err(e) = self.sender.send(SenderMessage::SendPoints(points.to_vec())) {
+            self.queue_depth.fetch_sub(points.len(), Ordering::Relaxed);
             warn!("Failed to queue route points for sending: {}", e);
         }
     }
 
+    /// Number of points queued but not yet sent (or given up on) by the sender thread
+    /// 
+    /// Grows when the backend can't keep up; the tracker uses it to slow down streaming.
+    pub fn queue_depth(&self) -> usize {
+        self.queue_depth.load(Ordering::Relaxed)
+    }
+
+    /// Send a map transition event (non-blocking)
+    /// 
+    /// Points queued before the transition are sent before it.
+    pub fn send_map_transition(&self, transition: MapTransition) {
+        if let Err(e) = self.sender.send(SenderMessage::SendEvent(RealtimeEvent::MapTransition(transition))) {
+            warn!("Failed to queue map transition for sending: {}", e);
+        }
+    }
+    
+    /// Send a player death event (non-blocking)
+    /// 
+    /// Points queued before the death are sent before it.
+    pub fn send_death(&self, death: DeathEvent) {
+        if let Err(e) = self.sender.send(SenderMessage::SendEvent(RealtimeEvent::Death(death))) {
+            warn!("Failed to queue death event for sending: {}", e);
+        }
+    }
+
+    /// Send queued points without waiting for the batching delay (non-blocking)
+    pub fn flush(&self) {
+        if let Err(e) = self.sender.send(SenderMessage::Flush(None)) {
+            warn!("Failed to queue realtime flush: {}", e);
+        }
+    }
+
+    /// Send queued points and wait until they are sent, up to `timeout`
+    /// 
+    /// Returns false if the sender thread didn't finish in time.
+    pub fn flush_and_wait(&self, timeout: Duration) -> bool {
+        let (done, flushed) = mpsc::channel();
+        if let Err(e) = self.sender.send(SenderMessage::Flush(Some(done))) {
+            warn!("Failed to queue realtime flush: {}", e);
+            return false;
+        }
+        flushed.recv_timeout(timeout).is_ok()
+    }
+
+    /// Tell `flush_and_wait` that the flush is done
+    fn notify_flushed(done: Option<Sender<()>>) {
+        if let Some(done) = done {
+            let _ = done.send(());
+        }
+    }
+
+    /// Whether the sender thread is still running (false once it panicked)
+    pub fn is_healthy(&self) -> bool {
+        self.healthy.load(Ordering::Relaxed)
+    }
+
+    /// Run a sender thread body, clearing `healthy` if it panics
+    /// 
+    /// The panic is logged instead of silently ending the thread; the queued
+    /// points are dropped with it.
+    fn run_guarded(healthy: &AtomicBool, body: impl FnOnce()) {
+        if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(body)) {
+            let message = payload
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+                .unwrap_or("unknown panic");
+            error!("Realtime sender thread panicked, streaming stopped: {}", message);
+            healthy.store(false, Ordering::Relaxed);
+        }
+    }
+
     /// Check if the client is configured and ready
     pub fn is_configured(&self) -> bool {
-        !self.push_key.is_empty() && !self.backend_url.is_empty()
+        self.push_key.as_ref().is_none_or(|push_key| !push_key.is_empty()) && !self.backend_url.is_empty()
     }
 
     /// Background thread that handles actual HTTP sending
-    fn sender_thread(backend_url: String, push_key: String, receiver: mpsc::Receiver<SenderMessage>) {
+    fn sender_thread(
+        backend_url: String,
+        mut connection: Connection,
+        delta_encoding: bool,
+        receiver: mpsc::Receiver<SenderMessage>,
+    ) {
         let endpoint = format!("{}/api/RoutePoints", backend_url.trim_end_matches('/'));
+        let events_url = backend_url.trim_end_matches('/');
         let mut pending_points: Vec<RoutePoint> = Vec::new();
         let batch_size = 10; // Send in batches of 10 points max
-        let max_retries = 3;
+        let mut encoder = delta_encoding.then(|| DeltaEncoder::new(DELTA_KEYFRAME_INTERVAL));
+        // Message received while collecting queued points, handled on the next iteration
+        let mut deferred: Option<SenderMessage> = None;
 
         loop {
             // Try to receive messages (non-blocking to allow batching)
-            match receiver.try_recv() {
+            let message = match deferred.take() {
+                Some(message) => Ok(message),
+                None => receiver.try_recv(),
+            };
+            match message {
                 Ok(SenderMessage::SendPoints(mut points)) => {
                     pending_points.append(&mut points);
+                    // Take the whole backlog at once so it can be coalesced
+                    deferred = Self::take_queued_points(&receiver, &mut pending_points);
+                }
+                Ok(SenderMessage::SendEvent(event)) => {
+                    // Flush earlier points first to keep ordering
+                    if !pending_points.is_empty() {
+                        let batch = std::mem::take(&mut pending_points);
+                        connection.send_batch(&endpoint, &batch, &mut encoder);
+                    }
+                    connection.send_event(events_url, &event);
+                }
+                Ok(SenderMessage::Flush(done)) => {
+                    if !pending_points.is_empty() {
+                        let batch = std::mem::take(&mut pending_points);
+                        connection.send_batch(&endpoint, &batch, &mut encoder);
+                    }
+                    Self::notify_flushed(done);
                 }
                 Ok(SenderMessage::Shutdown) => {
                     info!("Realtime sender thread shutting down");
                     // Flush remaining points before shutdown
                     if !pending_points.is_empty() {
-                        Self::send_batch(&endpoint, &push_key, &pending_points, max_retries);
+                        connection.send_batch(&endpoint, &pending_points, &mut encoder);
                     }
                     break;
                 }
@@ -152,10 +809,16 @@ impl RealtimeClient {
                 }
             }
 
-            // Send pending points in batches
+            // Send pending points in batches (larger ones when behind)
             while pending_points.len() >= batch_size {
-                let batch: Vec<_> = pending_points.drain(..batch_size).collect();
-                Self::send_batch(&endpoint, &push_key, &batch, max_retries);
+                let size = Self::batch_len(pending_points.len(), batch_size);
+                let batch: Vec<_> = pending_points.drain(..size).collect();
+                connection.send_batch(&endpoint, &batch, &mut encoder);
+            }
+
+            // A deferred transition / flush / shutdown sends the remaining points itself
+            if deferred.is_some() {
+                continue;
             }
 
             // If we have pending points but less than batch size, wait a bit then send
@@ -169,17 +832,28 @@ impl RealtimeClient {
                         pending_points.append(&mut points);
                         continue; // Go back to check if we have enough for a batch
                     }
+                    Ok(SenderMessage::SendEvent(event)) => {
+                        // Flush earlier points first to keep ordering
+                        let batch = std::mem::take(&mut pending_points);
+                        connection.send_batch(&endpoint, &batch, &mut encoder);
+                        connection.send_event(events_url, &event);
+                    }
+                    Ok(SenderMessage::Flush(done)) => {
+                        let batch = std::mem::take(&mut pending_points);
+                        connection.send_batch(&endpoint, &batch, &mut encoder);
+                        Self::notify_flushed(done);
+                    }
                     Ok(SenderMessage::Shutdown) => {
                         // Flush and exit
                         if !pending_points.is_empty() {
-                            Self::send_batch(&endpoint, &push_key, &pending_points, max_retries);
+                            connection.send_batch(&endpoint, &pending_points, &mut encoder);
                         }
                         break;
                     }
                     Err(TryRecvError::Empty) => {
                         // Timeout reached, send what we have
                         let batch: Vec<_> = pending_points.drain(..).collect();
-                        Self::send_batch(&endpoint, &push_key, &batch, max_retries);
+                        connection.send_batch(&endpoint, &batch, &mut encoder);
                     }
                     Err(TryRecvError::Disconnected) => {
                         break;
@@ -191,6 +865,13 @@ impl RealtimeClient {
                     Ok(SenderMessage::SendPoints(points)) => {
                         pending_points = points;
                     }
+                    Ok(SenderMessage::SendEvent(event)) => {
+                        connection.send_event(events_url, &event);
+                    }
+                    Ok(SenderMessage::Flush(done)) => {
+                        // Nothing pending
+                        Self::notify_flushed(done);
+                    }
                     Ok(SenderMessage::Shutdown) => {
                         break;
                     }
@@ -204,59 +885,460 @@ impl RealtimeClient {
             }
         }
     }
+}
+
+impl RealtimeClient {
+    /// Append every point batch already queued, stopping at the first other message
+    /// 
+    /// Returns that message so it can be handled after the points (keeps ordering).
+    fn take_queued_points(
+        receiver: &mpsc::Receiver<SenderMessage>,
+        pending_points: &mut Vec<RoutePoint>,
+    ) -> Option<SenderMessage> {
+        loop {
+            match receiver.try_recv() {
+                Ok(SenderMessage::SendPoints(mut points)) => pending_points.append(&mut points),
+                Ok(message) => return Some(message),
+                Err(_) => return None,
+            }
+        }
+    }
+
+    /// Number of pending points to send in the next request
+    /// 
+    /// Normally `batch_size`; once more than `COALESCE_THRESHOLD` points are pending
+    /// they are merged into requests of up to `MAX_COALESCED_POINTS`.
+    fn batch_len(pending: usize, batch_size: usize) -> usize {
+        if pending > COALESCE_THRESHOLD {
+            pending.min(MAX_COALESCED_POINTS)
+        } else {
+            batch_size.min(pending)
+        }
+    }
+}
 
-    /// Send a batch of points with retry logic
-    fn send_batch(endpoint: &str, push_key: &str, points: &[RoutePoint], max_retries: u32) {
-        let requests: Vec<RoutePointRequest> = points.iter().map(|p| p.into()).collect();
+impl Connection {
+    fn new(
+        push_key: Option<&str>,
+        auth_header: AuthHeader,
+        max_retry_after: Duration,
+        poster: Option<Box<dyn HttpPoster>>,
+        queue_depth: Arc<AtomicUsize>,
+    ) -> Self {
+        Self {
+            poster: poster.unwrap_or_else(|| Box::new(UreqPoster)),
+            auth: push_key.map(|push_key| auth_header.header(push_key)),
+            retry: RetryPolicy { max_retries: 3, max_retry_after },
+            throttle: ErrorThrottle::new(Duration::from_secs(10)), // Report network errors every 10s at most
+            payload_format: PayloadFormat::Json,
+            queue_depth,
+            batch_seq: 0,
+        }
+    }
+
+    /// Mark `count` queued points as handled (sent or given up on)
+    fn points_done(&self, count: usize) {
+        self.queue_depth.fetch_sub(count, Ordering::Relaxed);
+    }
+
+    /// Send a batch of points with retry logic (delta-encoded if `encoder` is set)
+    fn send_batch(
+        &mut self,
+        endpoint: &str,
+        points: &[RoutePoint],
+        encoder: &mut Option<DeltaEncoder>,
+    ) {
+        let description = format!("{} route points", points.len());
+        // Numbered once per batch, so retries keep the same number
+        let batch_seq = Some(self.batch_seq);
+        self.batch_seq += 1;
         
-        for attempt in 0..max_retries {
-            match ureq::post(endpoint)
-                .set("X-Push-Key", push_key)
-                .set("Content-Type", "application/json")
-                .timeout(Duration::from_secs(5))
-                .send_json(&requests)
-            {
-                Ok(response) => {
-                    if response.status() == 200 {
-                        debug!("Sent {} route points successfully", points.len());
-                        return;
-                    } else {
-                        warn!(
-                            "Backend returned status {}: {}",
-                            response.status(),
-                            response.status_text()
-                        );
+        let Some(encoder) = encoder else {
+            match self.payload_format {
+                PayloadFormat::Json => {
+                    let requests: Vec<RoutePointRequest> = points.iter().map(|p| p.into()).collect();
+                    self.post_json(endpoint, &requests, &description, batch_seq);
+                }
+                PayloadFormat::Protobuf => {
+                    let body = encode_protobuf_batch(points);
+                    self.post_protobuf(endpoint, &body, &description, batch_seq);
+                }
+            }
+            self.points_done(points.len());
+            return;
+        };
+        
+        let request = DeltaBatchRequest {
+            quantum: DELTA_QUANTUM,
+            points: encoder.encode(points),
+        };
+        let delta_endpoint = format!("{}/delta", endpoint);
+        if !self.post_json(&delta_endpoint, &request, &description, batch_seq) {
+            // The backend may have missed the reference point: restart from a keyframe
+            encoder.reset();
+        }
+        self.points_done(points.len());
+    }
+
+    /// Send an event to its endpoint under `base_url`, with retry logic
+    fn send_event(&mut self, base_url: &str, event: &RealtimeEvent) {
+        match event {
+            RealtimeEvent::MapTransition(transition) => {
+                let request = MapTransitionRequest::from(transition);
+                let description = format!(
+                    "map transition {} -> {}",
+                    transition.old_map_id_str, transition.new_map_id_str
+                );
+                self.post_json(&format!("{}/api/MapTransition", base_url), &request, &description, None);
+            }
+            RealtimeEvent::Death(death) => {
+                let request = DeathRequest::from(death);
+                let description = format!("death #{} on {}", death.death_count, death.map_id_str);
+                self.post_json(&format!("{}/api/Death", base_url), &request, &description, None);
+            }
+        }
+    }
+
+    /// POST a JSON body with retry logic, returns whether it was accepted
+    fn post_json<T: Serialize>(&mut self, endpoint: &str, body: &T, description: &str, batch_seq: Option<u64>) -> bool {
+        let body = match serde_json::to_string(body) {
+            Ok(body) => body,
+            Err(e) => {
+                error!("Failed to serialize {}: {}", description, e);
+                return false;
+            }
+        };
+        self.post_with_retry("application/json", description, batch_seq, |poster, headers| {
+            poster.post_json(endpoint, headers, &body)
+        })
+    }
+
+    /// POST a protobuf body with retry logic, returns whether it was accepted
+    fn post_protobuf(&mut self, endpoint: &str, body: &[u8], description: &str, batch_seq: Option<u64>) -> bool {
+        self.post_with_retry("application/x-protobuf", description, batch_seq, |poster, headers| {
+            poster.post_bytes(endpoint, headers, body)
+        })
+    }
+
+    /// Run `send` (one POST) with retry logic, returns whether it was accepted
+    /// 
+    /// `batch_seq` is sent as `X-Batch-Seq` on every attempt (point batches only).
+    fn post_with_retry(
+        &mut self,
+        content_type: &str,
+        description: &str,
+        batch_seq: Option<u64>,
+        send: impl Fn(&dyn HttpPoster, &[(&str, &str)]) -> Result<HttpResponse, String>,
+    ) -> bool {
+        let batch_seq = batch_seq.map(|seq| seq.to_string());
+        let headers: Vec<(&str, &str)> = self.auth
+            .iter()
+            .map(|(name, value)| (*name, value.as_str()))
+            .chain([("Content-Type", content_type)])
+            .chain(batch_seq.as_deref().map(|seq| ("X-Batch-Seq", seq)))
+            .collect();
+        let max_retries = self.retry.max_retries;
+        let mut transport_errors_only = true;
+        let mut rate_limit_waits = 0;
+        let mut attempt = 0;
+        
+        while attempt < max_retries {
+            match send(self.poster.as_ref(), &headers) {
+                Ok(response) if response.status == 200 => {
+                    debug!("Sent {} successfully", description);
+                    let unreported = self.throttle.record_success();
+                    if unreported > 0 {
+                        info!("Backend reachable again ({} network errors not logged)", unreported);
                     }
+                    return true;
                 }
-                Err(ureq::Error::Status(code, response)) => {
-                    let body = response.into_string().unwrap_or_default();
-                    warn!("Backend error ({}): {}", code, body);
-                    if code == 401 {
+                Ok(response) if response.status == 429 && rate_limit_waits < MAX_RATE_LIMIT_WAITS => {
+                    // Rate limited: wait as asked, without using up an attempt
+                    transport_errors_only = false;
+                    rate_limit_waits += 1;
+                    let wait = self.retry.retry_after(response.retry_after.as_deref());
+                    warn!("Backend rate limited, retrying {} in {}s", description, wait.as_secs_f32());
+                    thread::sleep(wait);
+                    continue;
+                }
+                Ok(response) => {
+                    transport_errors_only = false;
+                    warn!("Backend error ({}): {}", response.status, response.body);
+                    if response.status == 401 {
                         error!("Push key is invalid or expired. Please generate a new key.");
-                        return; // Don't retry auth errors
+                        return false; // Don't retry auth errors
                     }
                 }
-                Err(ureq::Error::Transport(e)) => {
-                    warn!(
-                        "Network error sending route points (attempt {}/{}): {}",
+                Err(e) => match self.throttle.record_error(Instant::now()) {
+                    Some(1) => warn!(
+                        "Network error sending {} (attempt {}/{}): {}",
+                        description,
                         attempt + 1,
                         max_retries,
                         e
-                    );
-                }
+                    ),
+                    Some(count) => warn!(
+                        "{} network errors in the last {}s (latest sending {}: {})",
+                        count,
+                        self.throttle.window.as_secs(),
+                        description,
+                        e
+                    ),
+                    None => {}
+                },
             }
 
             // Wait before retry
             if attempt < max_retries - 1 {
                 thread::sleep(Duration::from_millis(100 * (attempt as u64 + 1)));
             }
+            attempt += 1;
         }
 
-        error!(
-            "Failed to send {} route points after {} attempts",
-            points.len(),
-            max_retries
-        );
+        // During an outage the throttled network errors already cover this
+        if transport_errors_only && self.throttle.is_suppressing() {
+            debug!("Failed to send {} after {} attempts", description, max_retries);
+        } else {
+            error!(
+                "Failed to send {} after {} attempts",
+                description,
+                max_retries
+            );
+        }
+        false
+    }
+}
+
+// =============================================================================
+// HTTP STREAM TRANSPORT
+// =============================================================================
+
+/// First wait before reopening a failed stream (doubled on each failure)
+const STREAM_MIN_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Longest wait before reopening a failed stream
+const STREAM_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Request body of a stream: NDJSON lines taken from a channel as they come
+///
+/// ureq fills each chunk of a chunked body with `read_to_end`, so returning 0 once
+/// the queued lines are written ends the chunk (sent right away) rather than the
+/// body. The body only ends when the channel is closed.
+///
+/// `current` keeps the last line taken from the channel once it is written, so a
+/// reopened stream can start with it (see `stream_writer_thread`).
+struct LineReader<'a> {
+    lines: &'a Receiver<String>,
+    current: Vec<u8>,
+    position: usize,
+    /// Data was returned since the last chunk boundary
+    in_chunk: bool,
+    /// The channel was closed (client shut down)
+    closed: bool,
+}
+
+impl<'a> LineReader<'a> {
+    /// Body writing `resend` (if not empty) before the lines from the channel
+    fn new(lines: &'a Receiver<String>, resend: Vec<u8>) -> Self {
+        Self {
+            lines,
+            current: resend,
+            position: 0,
+            in_chunk: false,
+            closed: false,
+        }
+    }
+}
+
+impl Read for LineReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position == self.current.len() {
+            let next = if self.in_chunk {
+                match self.lines.try_recv() {
+                    Ok(line) => line,
+                    Err(_) => {
+                        // Nothing else queued: end the chunk
+                        self.in_chunk = false;
+                        return Ok(0);
+                    }
+                }
+            } else {
+                match self.lines.recv() {
+                    Ok(line) => line,
+                    Err(_) => {
+                        self.closed = true;
+                        return Ok(0);
+                    }
+                }
+            };
+            self.current = next.into_bytes();
+            self.position = 0;
+        }
+
+        let count = (self.current.len() - self.position).min(buf.len());
+        buf[..count].copy_from_slice(&self.current[self.position..self.position + count]);
+        self.position += count;
+        self.in_chunk = true;
+        Ok(count)
+    }
+}
+
+/// Where the line-per-point transports write to
+enum StreamTarget {
+    /// Chunked POST to `/api/RoutePoints/stream`
+    Http,
+    /// Named pipe or Unix socket at this path
+    Ipc(PathBuf),
+}
+
+/// Connect to the named pipe at `path` as a client
+#[cfg(windows)]
+fn connect_ipc(path: &Path) -> io::Result<Box<dyn Write>> {
+    Ok(Box::new(std::fs::OpenOptions::new().write(true).open(path)?))
+}
+
+/// Connect to the Unix socket at `path`
+#[cfg(unix)]
+fn connect_ipc(path: &Path) -> io::Result<Box<dyn Write>> {
+    Ok(Box::new(std::os::unix::net::UnixStream::connect(path)?))
+}
+
+impl RealtimeClient {
+    /// Background thread for the line-per-point transports (HTTP stream and IPC)
+    ///
+    /// Points are serialized to lines for the writer thread of `target`; events
+    /// are posted through `connection`.
+    fn stream_sender_thread(
+        backend_url: String,
+        mut connection: Connection,
+        receiver: Receiver<SenderMessage>,
+        target: StreamTarget,
+    ) {
+        let events_url = backend_url.trim_end_matches('/');
+        let (line_sender, lines) = mpsc::channel::<String>();
+
+        let writer = match target {
+            StreamTarget::Http => {
+                let stream_endpoint = format!("{}/api/RoutePoints/stream", events_url);
+                let auth = connection.auth.clone();
+                thread::spawn(move || Self::stream_writer_thread(&stream_endpoint, auth, lines))
+            }
+            StreamTarget::Ipc(path) => thread::spawn(move || Self::ipc_writer_thread(&path, lines)),
+        };
+
+        while let Ok(message) = receiver.recv() {
+            match message {
+                SenderMessage::SendPoints(points) => {
+                    for point in &points {
+                        match serde_json::to_string(&RoutePointRequest::from(point)) {
+                            Ok(line) => {
+                                let _ = line_sender.send(line + "\n");
+                            }
+                            Err(e) => error!("Failed to serialize route point: {}", e),
+                        }
+                    }
+                    // Handed over to the stream writer
+                    connection.points_done(points.len());
+                }
+                SenderMessage::SendEvent(event) => {
+                    connection.send_event(events_url, &event);
+                }
+                SenderMessage::Flush(done) => {
+                    // Lines are written as soon as they are queued
+                    Self::notify_flushed(done);
+                }
+                SenderMessage::Shutdown => break,
+            }
+        }
+
+        // Closing the line channel ends the request body
+        info!("Realtime stream shutting down");
+        drop(line_sender);
+        let _ = writer.join();
+    }
+
+    /// Keep a chunked POST open on `endpoint`, reopening it with backoff on failure
+    ///
+    /// Lines written to a stream that then fails are not resent, except the last
+    /// one: a reopened stream starts with it, so the consumer gets the current
+    /// position right away instead of at the next point.
+    fn stream_writer_thread(endpoint: &str, auth: Option<(&'static str, String)>, lines: Receiver<String>) {
+        let mut backoff = STREAM_MIN_BACKOFF;
+        let mut last_line = Vec::new();
+
+        loop {
+            let mut request = ureq::post(endpoint).set("Content-Type", "application/x-ndjson");
+            if let Some((name, value)) = &auth {
+                request = request.set(name, value);
+            }
+
+            if !last_line.is_empty() {
+                debug!("Realtime stream reopened, resending the last position");
+            }
+            let mut body = LineReader::new(&lines, std::mem::take(&mut last_line));
+            let result = request.send(&mut body);
+            last_line = std::mem::take(&mut body.current);
+            match result {
+                Ok(_) => backoff = STREAM_MIN_BACKOFF,
+                Err(e) if !body.closed => {
+                    warn!("Realtime stream failed, reopening in {}s: {}", backoff.as_secs(), e);
+                    thread::sleep(backoff);
+                    backoff = (backoff * 2).min(STREAM_MAX_BACKOFF);
+                }
+                Err(e) => warn!("Realtime stream closed with an error: {}", e),
+            }
+
+            if body.closed {
+                break;
+            }
+        }
+    }
+
+    /// Write lines to the IPC endpoint at `path`, reconnecting with backoff when it goes away
+    ///
+    /// Lines queued while disconnected are kept and written once reconnected, starting
+    /// with the one whose write failed, so a restarted peer gets the current position.
+    fn ipc_writer_thread(path: &Path, lines: Receiver<String>) {
+        let mut backoff = STREAM_MIN_BACKOFF;
+        let mut backlog: VecDeque<String> = VecDeque::new();
+
+        loop {
+            let mut pipe = match connect_ipc(path) {
+                Ok(pipe) => {
+                    backoff = STREAM_MIN_BACKOFF;
+                    pipe
+                }
+                Err(e) => {
+                    // Keep the lines queued meanwhile, and stop if the client is gone
+                    loop {
+                        match lines.try_recv() {
+                            Ok(line) => backlog.push_back(line),
+                            Err(TryRecvError::Empty) => break,
+                            Err(TryRecvError::Disconnected) => return,
+                        }
+                    }
+                    warn!("IPC endpoint {} unavailable, retrying in {}s: {}", path.display(), backoff.as_secs(), e);
+                    thread::sleep(backoff);
+                    backoff = (backoff * 2).min(STREAM_MAX_BACKOFF);
+                    continue;
+                }
+            };
+
+            loop {
+                let line = match backlog.pop_front() {
+                    Some(line) => line,
+                    None => match lines.recv() {
+                        Ok(line) => line,
+                        Err(_) => return,
+                    },
+                };
+                if let Err(e) = pipe.write_all(line.as_bytes()) {
+                    warn!("IPC endpoint {} disconnected, reconnecting: {}", path.display(), e);
+                    backlog.push_front(line);
+                    break;
+                }
+            }
+        }
     }
 }
 
@@ -267,3 +1349,711 @@ impl Drop for RealtimeClient {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::route::test_point;
+    use std::collections::HashMap;
+
+    fn point(x: f32, z: f32, map_id: u32, timestamp_ms: u64) -> RoutePoint {
+        RoutePoint {
+            x,
+            y: 12.345,
+            z,
+            global_x: x + 10240.0,
+            global_y: 12.345,
+            global_z: z + 8960.0,
+            map_id,
+            map_id_str: String::new(),
+            ..test_point(timestamp_ms)
+        }
+    }
+
+    /// Rebuild absolute (x, z, global_x, timestamp) values like the backend does
+    fn decode(entries: &[EncodedPoint], quantum: f32) -> Vec<(f32, f32, f32, u64)> {
+        let mut decoded: Vec<(f32, f32, f32, u64)> = Vec::new();
+        for entry in entries {
+            let next = match (entry, decoded.last()) {
+                (EncodedPoint::Keyframe(k), _) => (k.x, k.z, k.global_x, k.timestamp_ms),
+                (EncodedPoint::Delta(d), Some(&(x, z, global_x, timestamp_ms))) => (
+                    x + d.dx as f32 * quantum,
+                    z + d.dz as f32 * quantum,
+                    global_x + d.d_global_x as f32 * quantum,
+                    timestamp_ms + d.dt_ms,
+                ),
+                (EncodedPoint::Delta(_), None) => panic!("delta before the first keyframe"),
+            };
+            decoded.push(next);
+        }
+        decoded
+    }
+
+    #[test]
+    fn test_delta_encoding_roundtrip() {
+        // Small random-ish steps, then a map change
+        let mut points: Vec<RoutePoint> = (0..120u64)
+            .map(|i| point(i as f32 * 1.337, (i as f32 * 0.7).sin() * 40.0, 0x3C282300, i * 100))
+            .collect();
+        points.push(point(3.21, -4.56, 0x3C292300, 12_000));
+
+        let mut encoder = DeltaEncoder::new(DELTA_KEYFRAME_INTERVAL);
+        let mut entries = encoder.encode(&points[..60]);
+        entries.extend(encoder.encode(&points[60..]));
+
+        // Keyframes: first point, every 50 deltas, and on the map change
+        let keyframes: Vec<usize> = entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| matches!(entry, EncodedPoint::Keyframe(_)))
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(keyframes, vec![0, 51, 102, 120]);
+
+        let decoded = decode(&entries, DELTA_QUANTUM);
+        for (original, &(x, z, global_x, timestamp_ms)) in points.iter().zip(&decoded) {
+            assert!((original.x - x).abs() <= DELTA_QUANTUM);
+            assert!((original.z - z).abs() <= DELTA_QUANTUM);
+            assert!((original.global_x - global_x).abs() <= DELTA_QUANTUM);
+            assert_eq!(original.timestamp_ms, timestamp_ms);
+        }
+    }
+
+    #[test]
+    fn test_error_throttle_coalesces_failures() {
+        let mut throttle = ErrorThrottle::new(Duration::from_secs(10));
+        let start = Instant::now();
+        
+        // 1000 failures over 15 seconds
+        let logged: Vec<u32> = (0..1000u64)
+            .filter_map(|i| throttle.record_error(start + Duration::from_millis(i * 15)))
+            .collect();
+        
+        // First error, then one summary when the window ends
+        assert_eq!(logged, vec![1, 667]);
+        assert_eq!(throttle.record_success(), 332);
+        
+        // Next failure after a success is logged immediately
+        assert_eq!(throttle.record_error(start + Duration::from_secs(20)), Some(1));
+    }
+
+    #[test]
+    fn test_rate_limit_honors_retry_after() {
+        use std::io::{Read, Write};
+        use std::net::TcpListener;
+        
+        // Mock backend: 429 with Retry-After: 1, then 200
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}/api/RoutePoints", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let responses = [
+                "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 1\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                "HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            ];
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = [0u8; 4096];
+                let _ = stream.read(&mut buf);
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        
+        // A single attempt: the 429 must not use it up. The wait is capped so the
+        // test doesn't sleep the full second.
+        let mut connection = Connection {
+            poster: Box::new(UreqPoster),
+            auth: Some(("X-Push-Key", "key".to_string())),
+            retry: RetryPolicy { max_retries: 1, max_retry_after: Duration::from_millis(100) },
+            throttle: ErrorThrottle::new(Duration::from_secs(10)),
+            payload_format: PayloadFormat::Json,
+            queue_depth: Arc::new(AtomicUsize::new(0)),
+            batch_seq: 0,
+        };
+        let start = Instant::now();
+        let sent = connection.post_json(&endpoint, &[1, 2, 3], "test points", None);
+        server.join().unwrap();
+        
+        assert!(sent);
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+    
+    #[test]
+    fn test_retry_after_parsing() {
+        let retry = RetryPolicy { max_retries: 3, max_retry_after: Duration::from_secs(30) };
+        assert_eq!(retry.retry_after(Some("5")), Duration::from_secs(5));
+        assert_eq!(retry.retry_after(Some("3600")), Duration::from_secs(30));
+        assert_eq!(retry.retry_after(None), Duration::from_secs(1));
+    }
+
+    /// URL, headers and body of a posted request
+    type RecordedRequest = (String, Vec<(String, String)>, String);
+
+    /// Records every request and answers 200 (after `delay`)
+    struct MockPoster {
+        requests: std::sync::Arc<std::sync::Mutex<Vec<RecordedRequest>>>,
+        delay: Duration,
+    }
+
+    impl HttpPoster for MockPoster {
+        fn post_json(&self, url: &str, headers: &[(&str, &str)], body: &str) -> Result<HttpResponse, String> {
+            thread::sleep(self.delay);
+            let headers = headers.iter().map(|&(n, v)| (n.to_string(), v.to_string())).collect();
+            self.requests.lock().unwrap().push((url.to_string(), headers, body.to_string()));
+            Ok(HttpResponse { status: 200, retry_after: None, body: String::new() })
+        }
+        
+        fn post_bytes(&self, url: &str, headers: &[(&str, &str)], body: &[u8]) -> Result<HttpResponse, String> {
+            self.post_json(url, headers, &String::from_utf8_lossy(body))
+        }
+    }
+
+    /// Like `MockPoster`, but each request waits until `release` is dropped
+    /// (after telling `started`)
+    struct GatedPoster {
+        requests: std::sync::Arc<std::sync::Mutex<Vec<RecordedRequest>>>,
+        started: Sender<()>,
+        release: Receiver<()>,
+    }
+
+    impl HttpPoster for GatedPoster {
+        fn post_json(&self, url: &str, headers: &[(&str, &str)], body: &str) -> Result<HttpResponse, String> {
+            let _ = self.started.send(());
+            let _ = self.release.recv();
+            let headers = headers.iter().map(|&(n, v)| (n.to_string(), v.to_string())).collect();
+            self.requests.lock().unwrap().push((url.to_string(), headers, body.to_string()));
+            Ok(HttpResponse { status: 200, retry_after: None, body: String::new() })
+        }
+        
+        fn post_bytes(&self, url: &str, headers: &[(&str, &str)], body: &[u8]) -> Result<HttpResponse, String> {
+            self.post_json(url, headers, &String::from_utf8_lossy(body))
+        }
+    }
+
+    #[test]
+    fn test_injected_poster() {
+        let requests = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let client = RealtimeClient::new(
+            "http://backend/".to_string(),
+            Some("secret".to_string()),
+            AuthHeader::Bearer,
+            false,
+            PayloadFormat::Json,
+            Duration::from_secs(1),
+            Some(Box::new(MockPoster { requests: requests.clone(), delay: Duration::ZERO })),
+        );
+        client.send_point(&point(1.0, 2.0, 0x3C282300, 42));
+        client.flush();
+        
+        let start = Instant::now();
+        while requests.lock().unwrap().is_empty() && start.elapsed() < Duration::from_secs(5) {
+            thread::sleep(Duration::from_millis(10));
+        }
+        
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        let (url, headers, body) = &requests[0];
+        assert_eq!(url, "http://backend/api/RoutePoints");
+        assert!(headers.contains(&("Authorization".to_string(), "Bearer secret".to_string())));
+        assert!(headers.contains(&("Content-Type".to_string(), "application/json".to_string())));
+        
+        let body: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(body[0]["x"], 1.0);
+        assert_eq!(body[0]["mapId"], 0x3C282300);
+        assert_eq!(body[0]["timestampMs"], 42);
+    }
+
+    #[test]
+    fn test_backlog_is_coalesced() {
+        let requests = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let (started_sender, started) = mpsc::channel();
+        let (release, release_receiver) = mpsc::channel();
+        let client = RealtimeClient::new(
+            "http://backend".to_string(),
+            Some("secret".to_string()),
+            AuthHeader::Bearer,
+            false,
+            PayloadFormat::Json,
+            Duration::from_secs(1),
+            Some(Box::new(GatedPoster { requests: requests.clone(), started: started_sender, release: release_receiver })),
+        );
+        
+        // Queue 299 points one by one while the first request is in flight
+        client.send_point(&point(0.0, 0.0, 0x3C282300, 0));
+        client.flush();
+        started.recv_timeout(Duration::from_secs(5)).unwrap();
+        for i in 1..300 {
+            client.send_point(&point(i as f32, 0.0, 0x3C282300, i));
+        }
+        drop(release);
+        assert!(client.flush_and_wait(Duration::from_secs(5)));
+        
+        // The backlog goes out as one request, nothing lost or reordered
+        let requests = requests.lock().unwrap();
+        let sent_points: Vec<Vec<u64>> = requests
+            .iter()
+            .map(|(_, _, body)| {
+                let points: Vec<serde_json::Value> = serde_json::from_str(body).unwrap();
+                points.iter().map(|point| point["timestampMs"].as_u64().unwrap()).collect()
+            })
+            .collect();
+        assert_eq!(sent_points, vec![vec![0], (1..300).collect::<Vec<u64>>()]);
+    }
+
+    #[test]
+    fn test_auth_disabled() {
+        let requests = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let client = RealtimeClient::new(
+            "http://localhost:5000".to_string(),
+            None,
+            AuthHeader::XPushKey,
+            false,
+            PayloadFormat::Json,
+            Duration::from_secs(1),
+            Some(Box::new(MockPoster { requests: requests.clone(), delay: Duration::ZERO })),
+        );
+        assert!(client.is_configured());
+
+        client.send_point(&point(1.0, 2.0, 0x3C282300, 42));
+        assert!(client.flush_and_wait(Duration::from_secs(5)));
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        let (_, headers, _) = &requests[0];
+        assert_eq!(
+            headers,
+            &vec![
+                ("Content-Type".to_string(), "application/json".to_string()),
+                ("X-Batch-Seq".to_string(), "0".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_http_stream_single_connection() {
+        use std::io::Write;
+        use std::net::TcpListener;
+
+        // Mock backend: one connection, read the chunked body until the last chunk
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let backend_url = format!("http://{}", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut received = Vec::new();
+            let mut buf = [0u8; 4096];
+            while !received.ends_with(b"\r\n0\r\n\r\n") {
+                let n = stream.read(&mut buf).unwrap();
+                assert!(n > 0, "connection closed before the last chunk");
+                received.extend_from_slice(&buf[..n]);
+            }
+            stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").unwrap();
+
+            // No second connection
+            listener.set_nonblocking(true).unwrap();
+            assert!(listener.accept().is_err());
+
+            let received = String::from_utf8(received).unwrap();
+            let (head, mut chunked) = received.split_once("\r\n\r\n").unwrap();
+            assert!(head.starts_with("POST /api/RoutePoints/stream "));
+            assert!(head.to_ascii_lowercase().contains("transfer-encoding: chunked"));
+
+            let mut body = String::new();
+            while let Some((size, rest)) = chunked.split_once("\r\n") {
+                let size = usize::from_str_radix(size, 16).unwrap();
+                body.push_str(&rest[..size]);
+                chunked = &rest[size + 2..];
+            }
+            body
+        });
+
+        let client = RealtimeClient::new_http_stream(backend_url, Some("key".to_string()), AuthHeader::XPushKey, Duration::from_secs(1));
+        for i in 0..3 {
+            client.send_point(&point(i as f32, 0.0, 0x3C282300, i));
+            thread::sleep(Duration::from_millis(20));
+        }
+        drop(client);
+
+        let body = server.join().unwrap();
+        let lines: Vec<serde_json::Value> = body.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines.len(), 3);
+        for (i, line) in lines.iter().enumerate() {
+            assert_eq!(line["timestampMs"], i as u64);
+        }
+    }
+
+    #[test]
+    fn test_queue_depth_tracks_backlog() {
+        let requests = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let client = RealtimeClient::new(
+            "http://backend".to_string(),
+            Some("secret".to_string()),
+            AuthHeader::Bearer,
+            false,
+            PayloadFormat::Json,
+            Duration::from_secs(1),
+            Some(Box::new(MockPoster { requests: requests.clone(), delay: Duration::from_millis(500) })),
+        );
+        assert_eq!(client.queue_depth(), 0);
+        
+        // The backend is far slower than the tracker: the backlog builds up
+        for i in 0..10 {
+            client.send_point(&point(i as f32, 0.0, 0x3C282300, i));
+        }
+        let early_depth = client.queue_depth();
+        for i in 10..100 {
+            client.send_point(&point(i as f32, 0.0, 0x3C282300, i));
+        }
+        assert!(early_depth > 0);
+        assert!(client.queue_depth() > early_depth);
+        
+        // Once drained, nothing is left queued
+        assert!(client.flush_and_wait(Duration::from_secs(10)));
+        assert_eq!(client.queue_depth(), 0);
+    }
+
+    /// Field of a decoded protobuf message
+    #[derive(Debug, Clone, PartialEq)]
+    enum ProtoValue {
+        Varint(u64),
+        Fixed32(u32),
+        Bytes(Vec<u8>),
+    }
+
+    /// Minimal protobuf decoder: (field number, value) in wire order
+    fn decode_protobuf(mut buf: &[u8]) -> Vec<(u32, ProtoValue)> {
+        fn varint(buf: &mut &[u8]) -> u64 {
+            let mut value = 0u64;
+            for shift in (0..64).step_by(7) {
+                let byte = buf[0];
+                *buf = &buf[1..];
+                value |= ((byte & 0x7F) as u64) << shift;
+                if byte & 0x80 == 0 {
+                    break;
+                }
+            }
+            value
+        }
+        
+        let mut fields = Vec::new();
+        while !buf.is_empty() {
+            let key = varint(&mut buf);
+            let value = match (key & 7) as u32 {
+                WIRE_VARINT => ProtoValue::Varint(varint(&mut buf)),
+                WIRE_FIXED32 => {
+                    let value = u32::from_le_bytes(buf[..4].try_into().unwrap());
+                    buf = &buf[4..];
+                    ProtoValue::Fixed32(value)
+                }
+                WIRE_LENGTH_DELIMITED => {
+                    let len = varint(&mut buf) as usize;
+                    let value = buf[..len].to_vec();
+                    buf = &buf[len..];
+                    ProtoValue::Bytes(value)
+                }
+                wire_type => panic!("unexpected wire type {}", wire_type),
+            };
+            fields.push(((key >> 3) as u32, value));
+        }
+        fields
+    }
+
+    #[test]
+    fn test_protobuf_batch_roundtrip() {
+        let mut oriented = point(-1.5, 2.25, 0x3C282300, 1_700_000_000_123);
+        oriented.map_id_str = "m60_40_35_00".to_string();
+        oriented.epoch_ms = 1_700_000_000_456;
+        oriented.set_orientation(Some([0.0, 0.5, 0.0, 0.75]));
+        oriented.frame = Some(123_456_789_012);
+        let points = vec![oriented, point(300.0, -40.0, 0x0A010000, 7)];
+        
+        let batch = decode_protobuf(&encode_protobuf_batch(&points));
+        assert_eq!(batch.len(), 2);
+        
+        for ((field, message), original) in batch.iter().zip(&points) {
+            assert_eq!(*field, 1);
+            let ProtoValue::Bytes(message) = message else { panic!("points must be messages") };
+            let fields: HashMap<u32, ProtoValue> = decode_protobuf(message).into_iter().collect();
+            let float = |field: u32| match fields.get(&field) {
+                Some(ProtoValue::Fixed32(bits)) => Some(f32::from_bits(*bits)),
+                _ => None,
+            };
+            let uint = |field: u32| match fields.get(&field) {
+                Some(ProtoValue::Varint(value)) => *value,
+                other => panic!("field {} is {:?}", field, other),
+            };
+            let string = |field: u32| match fields.get(&field) {
+                Some(ProtoValue::Bytes(bytes)) => String::from_utf8(bytes.clone()).unwrap(),
+                other => panic!("field {} is {:?}", field, other),
+            };
+            
+            assert_eq!(float(1), Some(original.x));
+            assert_eq!(float(2), Some(original.y));
+            assert_eq!(float(3), Some(original.z));
+            assert_eq!(float(4), Some(original.global_x));
+            assert_eq!(float(5), Some(original.global_y));
+            assert_eq!(float(6), Some(original.global_z));
+            assert_eq!(uint(7), original.map_id as u64);
+            assert_eq!(string(8), original.map_id_str);
+            assert_eq!(uint(9), original.global_map_id as u64);
+            assert_eq!(string(10), original.global_map_label);
+            assert_eq!(uint(11), original.timestamp_ms);
+            assert_eq!(uint(12), original.epoch_ms);
+            assert_eq!([float(13), float(14), float(15), float(16)], [original.qx, original.qy, original.qz, original.qw]);
+            let frame = match fields.get(&17) {
+                Some(ProtoValue::Varint(value)) => Some(*value),
+                _ => None,
+            };
+            assert_eq!(frame, original.frame);
+        }
+    }
+
+    #[test]
+    fn test_protobuf_payload_format() {
+        let requests = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let client = RealtimeClient::new(
+            "http://backend".to_string(),
+            None,
+            AuthHeader::XPushKey,
+            false,
+            PayloadFormat::Protobuf,
+            Duration::from_secs(1),
+            Some(Box::new(MockPoster { requests: requests.clone(), delay: Duration::ZERO })),
+        );
+        client.send_point(&point(1.0, 2.0, 0x3C282300, 42));
+        assert!(client.flush_and_wait(Duration::from_secs(5)));
+        
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        let (url, headers, _) = &requests[0];
+        assert_eq!(url, "http://backend/api/RoutePoints");
+        assert!(headers.contains(&("Content-Type".to_string(), "application/x-protobuf".to_string())));
+    }
+
+    /// Panics on every request
+    struct PanickingPoster;
+
+    impl HttpPoster for PanickingPoster {
+        fn post_json(&self, _url: &str, _headers: &[(&str, &str)], _body: &str) -> Result<HttpResponse, String> {
+            panic!("poster failure");
+        }
+        
+        fn post_bytes(&self, _url: &str, _headers: &[(&str, &str)], _body: &[u8]) -> Result<HttpResponse, String> {
+            panic!("poster failure");
+        }
+    }
+
+    #[test]
+    fn test_sender_panic_marks_unhealthy() {
+        let client = RealtimeClient::new(
+            "http://backend".to_string(),
+            None,
+            AuthHeader::XPushKey,
+            false,
+            PayloadFormat::Json,
+            Duration::from_secs(1),
+            Some(Box::new(PanickingPoster)),
+        );
+        assert!(client.is_healthy());
+        
+        client.send_point(&point(1.0, 2.0, 0x3C282300, 42));
+        client.flush();
+        
+        let start = Instant::now();
+        while client.is_healthy() && start.elapsed() < Duration::from_secs(5) {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(!client.is_healthy());
+        
+        // Later points are refused without blocking
+        client.send_point(&point(2.0, 2.0, 0x3C282300, 43));
+    }
+
+    #[test]
+    fn test_death_event_posted_after_points() {
+        let requests = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let client = RealtimeClient::new(
+            "http://backend".to_string(),
+            None,
+            AuthHeader::XPushKey,
+            false,
+            PayloadFormat::Json,
+            Duration::from_secs(1),
+            Some(Box::new(MockPoster { requests: requests.clone(), delay: Duration::ZERO })),
+        );
+        let dead_at = point(5.0, 6.0, 0x3C282300, 42);
+        client.send_point(&dead_at);
+        client.send_death(DeathEvent::at(&dead_at, 7));
+        
+        let start = Instant::now();
+        while requests.lock().unwrap().len() < 2 && start.elapsed() < Duration::from_secs(5) {
+            thread::sleep(Duration::from_millis(10));
+        }
+        
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].0, "http://backend/api/RoutePoints");
+        assert_eq!(requests[1].0, "http://backend/api/Death");
+        
+        let body: serde_json::Value = serde_json::from_str(&requests[1].2).unwrap();
+        assert_eq!(body["globalX"], dead_at.global_x);
+        assert_eq!(body["mapId"], 0x3C282300);
+        assert_eq!(body["deathCount"], 7);
+        assert_eq!(body["timestampMs"], 42);
+    }
+
+    #[test]
+    fn test_http_stream_resends_last_point_on_reconnect() {
+        use std::io::Write;
+        use std::net::TcpListener;
+
+        /// Read a chunked request until `done` returns true for the decoded body so far
+        fn read_body(stream: &mut std::net::TcpStream, done: impl Fn(&str) -> bool) -> String {
+            let mut received = Vec::new();
+            let mut buf = [0u8; 4096];
+            loop {
+                // Complete chunks received so far
+                let text = String::from_utf8_lossy(&received).into_owned();
+                let mut body = String::new();
+                if let Some((_, mut chunked)) = text.split_once("\r\n\r\n") {
+                    while let Some((size, rest)) = chunked.split_once("\r\n") {
+                        let Ok(size) = usize::from_str_radix(size, 16) else { break };
+                        if rest.len() < size + 2 {
+                            break;
+                        }
+                        body.push_str(&rest[..size]);
+                        chunked = &rest[size + 2..];
+                    }
+                }
+                if done(&body) || received.ends_with(b"\r\n0\r\n\r\n") {
+                    return body;
+                }
+                let n = stream.read(&mut buf).unwrap();
+                if n == 0 {
+                    return body;
+                }
+                received.extend_from_slice(&buf[..n]);
+            }
+        }
+        let timestamps = |body: &str| -> Vec<u64> {
+            body.lines()
+                .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["timestampMs"].as_u64().unwrap())
+                .collect()
+        };
+
+        // Mock backend: drop the first connection after two points, keep the second one
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let backend_url = format!("http://{}", listener.local_addr().unwrap());
+        let (events, events_rx) = mpsc::channel();
+        let server = thread::spawn(move || {
+            let (mut first, _) = listener.accept().unwrap();
+            let first_body = read_body(&mut first, |body| body.lines().count() >= 2);
+            drop(first);
+            events.send("dropped").unwrap();
+
+            let (mut second, _) = listener.accept().unwrap();
+            events.send("reconnected").unwrap();
+            let second_body = read_body(&mut second, |_| false);
+            second.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").unwrap();
+            (timestamps(&first_body), timestamps(&second_body))
+        });
+
+        let client = RealtimeClient::new_http_stream(backend_url, None, AuthHeader::XPushKey, Duration::from_secs(1));
+        client.send_point(&point(0.0, 0.0, 0x3C282300, 0));
+        thread::sleep(Duration::from_millis(50));
+        client.send_point(&point(1.0, 0.0, 0x3C282300, 1));
+        assert_eq!(events_rx.recv_timeout(Duration::from_secs(5)), Ok("dropped"));
+
+        // Point 2 goes into the closed connection (answered with a reset), point 3 fails
+        client.send_point(&point(2.0, 0.0, 0x3C282300, 2));
+        thread::sleep(Duration::from_millis(200));
+        client.send_point(&point(3.0, 0.0, 0x3C282300, 3));
+        assert_eq!(events_rx.recv_timeout(Duration::from_secs(10)), Ok("reconnected"));
+        drop(client);
+
+        // Nothing new was sent after the drop: the new stream only carries the last point
+        let (first, second) = server.join().unwrap();
+        assert_eq!(first, vec![0, 1]);
+        assert_eq!(second, vec![3]);
+    }
+    
+    /// Answers 500 to the first request, then 200, recording every request
+    struct FlakyPoster {
+        requests: std::sync::Arc<std::sync::Mutex<Vec<RecordedRequest>>>,
+    }
+    
+    impl HttpPoster for FlakyPoster {
+        fn post_json(&self, url: &str, headers: &[(&str, &str)], body: &str) -> Result<HttpResponse, String> {
+            let mut requests = self.requests.lock().unwrap();
+            let status = if requests.is_empty() { 500 } else { 200 };
+            let headers = headers.iter().map(|&(n, v)| (n.to_string(), v.to_string())).collect();
+            requests.push((url.to_string(), headers, body.to_string()));
+            Ok(HttpResponse { status, retry_after: None, body: String::new() })
+        }
+        
+        fn post_bytes(&self, url: &str, headers: &[(&str, &str)], body: &[u8]) -> Result<HttpResponse, String> {
+            self.post_json(url, headers, &String::from_utf8_lossy(body))
+        }
+    }
+    
+    #[test]
+    fn test_batch_sequence_numbers() {
+        let requests = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut connection = Connection::new(
+            None,
+            AuthHeader::XPushKey,
+            Duration::from_secs(1),
+            Some(Box::new(FlakyPoster { requests: requests.clone() })),
+            Arc::new(AtomicUsize::new(3)),
+        );
+        for i in 0..3 {
+            connection.send_batch("http://backend/api/RoutePoints", &[point(0.0, 0.0, 0x3C282300, i)], &mut None);
+        }
+        connection.send_event("http://backend", &RealtimeEvent::Death(DeathEvent::at(&point(0.0, 0.0, 0x3C282300, 5), 1)));
+        
+        let batch_seqs: Vec<Option<String>> = requests
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(_, headers, _)| headers.iter().find(|(name, _)| name == "X-Batch-Seq").map(|(_, seq)| seq.clone()))
+            .collect();
+        // The first batch is retried under the same number; events are not numbered
+        let expected = [Some("0"), Some("0"), Some("1"), Some("2"), None];
+        assert_eq!(batch_seqs, expected.map(|seq| seq.map(str::to_string)));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_ipc_stream_delivers_lines_and_reconnects() {
+        use std::io::{BufRead, BufReader};
+        use std::os::unix::net::UnixListener;
+
+        let path = std::env::temp_dir().join(format!("route_tracker_ipc_{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let client = RealtimeClient::new_ipc(path.clone(), "http://127.0.0.1:9".to_string(), None, AuthHeader::XPushKey, Duration::from_secs(1));
+        client.send_points(&[point(1.0, 0.0, 0x3C282300, 1), point(2.0, 0.0, 0x3C282300, 2)]);
+
+        let accept = || {
+            let (stream, _) = listener.accept().unwrap();
+            stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+            BufReader::new(stream)
+        };
+        let next_timestamp = |reader: &mut BufReader<std::os::unix::net::UnixStream>| {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            serde_json::from_str::<serde_json::Value>(&line).unwrap()["timestampMs"].as_u64().unwrap()
+        };
+
+        let mut reader = accept();
+        assert_eq!(next_timestamp(&mut reader), 1);
+        assert_eq!(next_timestamp(&mut reader), 2);
+
+        // The consumer restarts: the next point arrives on the new connection
+        drop(reader);
+        client.send_point(&point(3.0, 0.0, 0x3C282300, 3));
+        let mut reader = accept();
+        assert_eq!(next_timestamp(&mut reader), 3);
+
+        drop(client);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,15 +1,44 @@
 // Route data structures and serialization
 
+use hudhook::tracing::warn;
 use serde::Serialize;
 use std::fs::{self, File};
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
+use crate::coordinate_transformer::WorldPositionTransformer;
+
 // =============================================================================
 // DATA STRUCTURES
 // =============================================================================
 
+/// How trustworthy the global coordinates of a point are
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PointAccuracy {
+    /// Recorded on a global map tile (grid formula only)
+    ExactGlobal,
+    /// Converted through a single anchor
+    DirectAnchor,
+    /// Converted through a chain of n anchors
+    MultiHop(usize),
+    /// Conversion failed, global coordinates are the local ones
+    Fallback,
+}
+
+impl PointAccuracy {
+    /// Accuracy from the number of anchors applied (None if the conversion failed)
+    pub fn from_hops(hops: Option<usize>) -> Self {
+        match hops {
+            Some(0) => PointAccuracy::ExactGlobal,
+            Some(1) => PointAccuracy::DirectAnchor,
+            Some(n) => PointAccuracy::MultiHop(n),
+            None => PointAccuracy::Fallback,
+        }
+    }
+}
+
 /// Route point with timestamp (serializable)
 #[derive(Clone, Debug, Serialize)]
 pub struct RoutePoint {
@@ -32,10 +61,163 @@ pub struct RoutePoint {
     /// Global map area number (60 for Lands Between, 61 for Shadow Realm)
     /// This indicates which global map the coordinates belong to after conversion
     pub global_map_id: u8,
-    /// Timestamp in milliseconds from start of recording
+    /// Human-readable name of the global map (see `global_map_label`)
+    pub global_map_label: &'static str,
+    /// Capture time in Unix epoch milliseconds (rebased by tools such as `slice_by_time`)
+    pub timestamp_ms: u64,
+    /// Wall-clock time the point was captured (Unix epoch milliseconds), for syncing
+    /// with videos; equal to `timestamp_ms` when recorded, but never rebased or replayed
+    pub epoch_ms: u64,
+    /// Game frame counter when the point was captured (omitted when the game doesn't expose it)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame: Option<u64>,
+    /// How the global coordinates were obtained
+    pub accuracy: PointAccuracy,
+    /// Player orientation quaternion (omitted when the game doesn't expose it)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub qx: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub qy: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub qz: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub qw: Option<f32>,
+    /// Full position array `[x, y, z, ?, ?]` as read from memory (`recording.capture_raw_array`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw: Option<[f32; 5]>,
+    /// Name of the last visited Site of Grace and the global `(x, y, z)` offset from it
+    /// (omitted until a grace has been passed, see `snap_to_bonfires_radius`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relative_to_grace: Option<(String, f32, f32, f32)>,
+}
+
+impl RoutePoint {
+    /// Whether all local and global coordinates are finite (not NaN or infinite)
+    pub fn has_finite_coordinates(&self) -> bool {
+        [self.x, self.y, self.z, self.global_x, self.global_y, self.global_z]
+            .iter()
+            .all(|v| v.is_finite())
+    }
+    
+    /// Set the orientation quaternion `[x, y, z, w]` (cleared with None)
+    pub fn set_orientation(&mut self, orientation: Option<[f32; 4]>) {
+        let [qx, qy, qz, qw] = orientation.map_or([None; 4], |q| q.map(Some));
+        self.qx = qx;
+        self.qy = qy;
+        self.qz = qz;
+        self.qw = qw;
+    }
+    
+    /// Straight-line distance between the global positions of two points
+    pub fn distance_to(&self, other: &RoutePoint) -> f32 {
+        let dx = other.global_x - self.global_x;
+        let dy = other.global_y - self.global_y;
+        let dz = other.global_z - self.global_z;
+        (dx * dx + dy * dy + dz * dz).sqrt()
+    }
+}
+
+/// Change of map tile between two consecutive points
+#[derive(Clone, Debug, Serialize)]
+pub struct MapTransition {
+    /// Map tile ID before the transition
+    pub old_map_id: u32,
+    /// Map tile ID before the transition, as human-readable string
+    pub old_map_id_str: String,
+    /// Global map area number before the transition
+    pub old_global_map_id: u8,
+    /// Map tile ID after the transition
+    pub new_map_id: u32,
+    /// Map tile ID after the transition, as human-readable string
+    pub new_map_id_str: String,
+    /// Global map area number after the transition
+    pub new_global_map_id: u8,
+    /// Timestamp of the first point on the new map
     pub timestamp_ms: u64,
 }
 
+impl MapTransition {
+    /// Detect a map change between two consecutive points
+    pub fn between(previous: &RoutePoint, next: &RoutePoint) -> Option<Self> {
+        if previous.map_id == next.map_id {
+            return None;
+        }
+        
+        Some(Self {
+            old_map_id: previous.map_id,
+            old_map_id_str: previous.map_id_str.clone(),
+            old_global_map_id: previous.global_map_id,
+            new_map_id: next.map_id,
+            new_map_id_str: next.map_id_str.clone(),
+            new_global_map_id: next.global_map_id,
+            timestamp_ms: next.timestamp_ms,
+        })
+    }
+}
+
+/// Site of Grace the route passed by (see `recording.snap_to_bonfires_radius`)
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct GraceVisit {
+    /// Bonfire icon id in map_data_processed.json
+    pub id: u64,
+    /// Grace name (empty if the icon has no text)
+    pub name: String,
+    /// Timestamp of the first point snapped to this grace
+    pub timestamp_ms: u64,
+}
+
+/// Player death detected while recording (see `PositionProvider::read_death_count`)
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DeathEvent {
+    /// Global position where the player died
+    pub global_x: f32,
+    pub global_y: f32,
+    pub global_z: f32,
+    /// Map tile ID of the death
+    pub map_id: u32,
+    /// Map tile ID as human-readable string
+    pub map_id_str: String,
+    /// Global map area number
+    pub global_map_id: u8,
+    /// Timestamp of the death (same clock as the route points)
+    pub timestamp_ms: u64,
+    /// Death counter value after this death
+    pub death_count: u32,
+}
+
+impl DeathEvent {
+    /// Death at the position of a point
+    pub fn at(point: &RoutePoint, death_count: u32) -> Self {
+        Self {
+            global_x: point.global_x,
+            global_y: point.global_y,
+            global_z: point.global_z,
+            map_id: point.map_id,
+            map_id_str: point.map_id_str.clone(),
+            global_map_id: point.global_map_id,
+            timestamp_ms: point.timestamp_ms,
+            death_count,
+        }
+    }
+}
+
+/// Context about where a route was recorded (unavailable fields are omitted)
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct RouteMetadata {
+    /// Game version the route was recorded on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub game_version: Option<String>,
+    /// Name of the character that ran the route
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub character_name: Option<String>,
+    /// Recording date (ISO 8601, UTC)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recorded_at: Option<String>,
+    /// Coordinate conversion dataset used for the global coordinates
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dataset_version: Option<String>,
+}
+
 /// Saved route file structure
 #[derive(Debug, Serialize)]
 pub struct SavedRoute {
@@ -49,6 +231,14 @@ pub struct SavedRoute {
     pub interval_ms: u64,
     /// Number of points
     pub point_count: usize,
+    /// Recording context (game version, character, dataset)
+    pub metadata: RouteMetadata,
+    /// Sites of Grace passed by, in order (omitted if grace snapping is disabled)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub graces_visited: Vec<GraceVisit>,
+    /// Player deaths, in order (omitted if there were none)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub deaths: Vec<DeathEvent>,
     /// The route points
     pub points: Vec<RoutePoint>,
 }
@@ -57,6 +247,37 @@ pub struct SavedRoute {
 // HELPERS
 // =============================================================================
 
+/// Implied speed (global units per second) between two consecutive points above
+/// which the movement is considered a teleport (grace warp, fast travel, ...)
+pub const TELEPORT_SPEED: f32 = 200.0;
+
+/// Human-readable label for a global map area number ("unknown" if not a global map)
+pub fn global_map_label(global_map_id: u8) -> &'static str {
+    match global_map_id {
+        60 => "lands_between",
+        61 => "shadow_realm",
+        62 => "underground",
+        _ => "unknown",
+    }
+}
+
+/// Whether the move between two consecutive points is a teleport
+///
+/// True when the global map changes or the implied speed (distance / time
+/// between them) exceeds `max_speed` global units per second. A long move over
+/// a long sampling gap is not a teleport; moving without time passing always is.
+pub fn is_teleport(a: &RoutePoint, b: &RoutePoint, max_speed: f32) -> bool {
+    let elapsed_secs = b.timestamp_ms.saturating_sub(a.timestamp_ms) as f32 / 1000.0;
+    a.global_map_id != b.global_map_id || a.distance_to(b) > max_speed * elapsed_secs
+}
+
+/// Split a route into continuous segments, breaking at teleports (see `is_teleport`)
+pub fn split_at_teleports(route: &[RoutePoint], max_speed: f32) -> Vec<&[RoutePoint]> {
+    route
+        .chunk_by(|a, b| !is_teleport(a, b, max_speed))
+        .collect()
+}
+
 /// Simple timestamp generator (without chrono dependency)
 pub fn generate_timestamp() -> String {
     let duration = SystemTime::now()
@@ -78,17 +299,186 @@ pub fn generate_timestamp() -> String {
             years, months, day, hours, minutes, seconds)
 }
 
+/// Format a Unix timestamp (seconds) as an ISO 8601 UTC date-time
+pub fn iso8601_from_unix(secs: u64) -> String {
+    // Civil date from days since epoch (Howard Hinnant's algorithm)
+    let days = (secs / 86400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    
+    let hours = (secs % 86400) / 3600;
+    let minutes = (secs % 3600) / 60;
+    let seconds = secs % 60;
+    
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            year, month, day, hours, minutes, seconds)
+}
+
+/// Current wall-clock time in milliseconds since the Unix epoch
+pub fn epoch_ms_now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Current date-time as ISO 8601 UTC
+pub fn iso8601_now() -> String {
+    let secs = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    iso8601_from_unix(secs)
+}
+
+// =============================================================================
+// RECONVERSION
+// =============================================================================
+
+impl WorldPositionTransformer {
+    /// Recompute the global coordinates of each point from its `map_id` and local position
+    ///
+    /// Used when an old route is loaded together with a newer dataset. Local
+    /// coordinates, timestamps and orientation are left intact. Points whose tile
+    /// can no longer be converted keep their old globals and are flagged with
+    /// `PointAccuracy::Fallback`. Returns the number of flagged points.
+    pub fn reconvert(&self, route: &mut [RoutePoint]) -> usize {
+        let mut flagged = 0;
+        for point in route.iter_mut() {
+            match self.local_to_world_with_global_map(point.map_id, point.x, point.y, point.z) {
+                Ok((global_x, global_y, global_z, global_map_id)) => {
+                    point.global_x = global_x;
+                    point.global_y = global_y;
+                    point.global_z = global_z;
+                    point.global_map_id = global_map_id;
+                    point.global_map_label = global_map_label(global_map_id);
+                    point.map_id_str = WorldPositionTransformer::format_map_id(point.map_id);
+                    point.accuracy = PointAccuracy::from_hops(self.conversion_hops(point.map_id));
+                }
+                Err(_) => {
+                    point.accuracy = PointAccuracy::Fallback;
+                    flagged += 1;
+                }
+            }
+        }
+        flagged
+    }
+}
+
 // =============================================================================
 // ROUTE SAVING
 // =============================================================================
 
-/// Save a route to a JSON file
+/// Split a route into consecutive chunks covering at most `window_ms` each
+///
+/// Windows are aligned on the first point's timestamp. Windows without any
+/// points (e.g. during a pause) do not produce an empty chunk.
+/// A `window_ms` of 0 returns the whole route as a single chunk.
+pub fn split_route_by_window(route: &[RoutePoint], window_ms: u64) -> Vec<&[RoutePoint]> {
+    let Some(first) = route.first() else {
+        return Vec::new();
+    };
+    
+    if window_ms == 0 {
+        return vec![route];
+    }
+    
+    let start_ms = first.timestamp_ms;
+    let window_of = |p: &RoutePoint| p.timestamp_ms.saturating_sub(start_ms) / window_ms;
+    
+    route
+        .chunk_by(|a, b| window_of(a) == window_of(b))
+        .collect()
+}
+
+/// Round the local and global coordinates of points to `decimals` decimals
+/// 
+/// Orientation is left as is. Rounding is done in f64, so the f32 result is the
+/// closest value to the rounded number and serializes with at most `decimals` digits.
+pub fn round_coordinates(points: &mut [RoutePoint], decimals: u32) {
+    let scale = 10f64.powi(decimals as i32);
+    let round = |value: &mut f32| *value = ((*value as f64 * scale).round() / scale) as f32;
+    for point in points {
+        for value in [
+            &mut point.x,
+            &mut point.y,
+            &mut point.z,
+            &mut point.global_x,
+            &mut point.global_y,
+            &mut point.global_z,
+        ] {
+            round(value);
+        }
+    }
+}
+
+/// How `save_route_to_file` partitions a route into files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteFileSplit {
+    /// Windows of this many minutes (0 = single file)
+    Minutes(u64),
+    /// One file per contiguous run of points on the same map tile
+    ByMap,
+}
+
+/// Waypoints saved next to the points of a route
+/// 
+/// When a route is split, each file gets the waypoints from its first point until
+/// the next file's first point (see `for_chunk`), so none are dropped.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RouteWaypoints<'a> {
+    /// Sites of Grace passed by, in order
+    pub graces_visited: &'a [GraceVisit],
+    /// Player deaths, in order
+    pub deaths: &'a [DeathEvent],
+}
+
+impl<'a> RouteWaypoints<'a> {
+    /// The waypoints saved with `chunks[index]` of a split route
+    /// 
+    /// Waypoints between two chunks go to the earlier one, those before the first
+    /// point to the first chunk and those after the last point (e.g. a death read
+    /// after the last recorded sample) to the last chunk.
+    fn for_chunk(&self, chunks: &[&[RoutePoint]], index: usize) -> Self {
+        let start = (index > 0).then(|| chunks[index][0].timestamp_ms);
+        let end = chunks.get(index + 1).map(|chunk| chunk[0].timestamp_ms);
+        Self {
+            graces_visited: time_slice(self.graces_visited, |visit| visit.timestamp_ms, start, end),
+            deaths: time_slice(self.deaths, |death| death.timestamp_ms, start, end),
+        }
+    }
+}
+
+/// The items (in time order) from `start` up to but excluding `end`, unbounded when None
+fn time_slice<T>(items: &[T], timestamp_ms: impl Fn(&T) -> u64, start: Option<u64>, end: Option<u64>) -> &[T] {
+    let from = start.map_or(0, |start| items.partition_point(|item| timestamp_ms(item) < start));
+    let to = end.map_or(items.len(), |end| items.partition_point(|item| timestamp_ms(item) < end));
+    &items[from..to.max(from)]
+}
+
+/// Save a route to one or more JSON files
+///
+/// With `RouteFileSplit::Minutes(n)` and a non-zero `n`, the route is partitioned
+/// into windows of that many minutes and each window is written to its own
+/// self-contained `route_<timestamp>_partNN.json` file. With `ByMap`, each map
+/// tile segment goes to `route_<timestamp>_<map_id_str>_NN.json` (NN counts all
+/// segments, so revisits get their own file). Returns the paths of all written
+/// files. Every file carries the same `metadata`.
 pub fn save_route_to_file(
     route: &[RoutePoint],
     base_dir: &PathBuf,
     routes_directory: &str,
     interval_ms: u64,
-) -> Result<PathBuf, String> {
+    split: RouteFileSplit,
+    metadata: &RouteMetadata,
+    waypoints: RouteWaypoints,
+) -> Result<Vec<PathBuf>, String> {
     if route.is_empty() {
         return Err("No route data to save".to_string());
     }
@@ -102,36 +492,425 @@ pub fn save_route_to_file(
     
     // Generate filename with timestamp
     let now = generate_timestamp();
-    let filename = format!("route_{}.json", now.replace(":", "-").replace(" ", "_"));
-    let filepath = routes_dir.join(&filename);
+    let file_stem = format!("route_{}", now.replace(":", "-").replace(" ", "_"));
     
-    // Calculate total duration
-    let duration_secs = route.last()
-        .map(|p| p.timestamp_ms as f64 / 1000.0)
-        .unwrap_or(0.0);
-    
-    // Create saved route structure
-    let saved_route = SavedRoute {
-        name: format!("Route {}", now),
-        recorded_at: now,
-        duration_secs,
-        interval_ms,
-        point_count: route.len(),
-        points: route.to_vec(),
+    let split_minutes = match split {
+        RouteFileSplit::Minutes(minutes) => minutes,
+        RouteFileSplit::ByMap => {
+            let segments: Vec<&[RoutePoint]> = route.chunk_by(|a, b| a.map_id == b.map_id).collect();
+            let mut paths = Vec::with_capacity(segments.len());
+            for (index, segment) in segments.iter().enumerate() {
+                let part = index + 1;
+                let map_id_str = &segment[0].map_id_str;
+                let filepath = routes_dir.join(format!("{}_{}_{:02}.json", file_stem, map_id_str, part));
+                let name = format!("Route {} ({}, part {}/{})", now, map_id_str, part, segments.len());
+                let waypoints = waypoints.for_chunk(&segments, index);
+                write_route_file(&filepath, segment, name, &now, interval_ms, metadata, waypoints)?;
+                paths.push(filepath);
+            }
+            return Ok(paths);
+        }
     };
+    let chunks = split_route_by_window(route, split_minutes * 60 * 1000);
     
-    // Serialize to JSON
-    let json = serde_json::to_string_pretty(&saved_route)
-        .map_err(|e| format!("Failed to serialize route: {}", e))?;
+    // Single file: keep the historical naming
+    if chunks.len() == 1 {
+        let filepath = routes_dir.join(format!("{}.json", file_stem));
+        write_route_file(&filepath, chunks[0], format!("Route {}", now), &now, interval_ms, metadata, waypoints)?;
+        return Ok(vec![filepath]);
+    }
+    
+    let mut paths = Vec::with_capacity(chunks.len());
+    for (index, chunk) in chunks.iter().enumerate() {
+        let part = index + 1;
+        let filepath = routes_dir.join(format!("{}_part{:02}.json", file_stem, part));
+        let name = format!("Route {} (part {}/{})", now, part, chunks.len());
+        let waypoints = waypoints.for_chunk(&chunks, index);
+        write_route_file(&filepath, chunk, name, &now, interval_ms, metadata, waypoints)?;
+        paths.push(filepath);
+    }
+    
+    Ok(paths)
+}
+
+/// Write a self-contained route file for the given points and waypoints
+fn write_route_file(
+    filepath: &Path,
+    points: &[RoutePoint],
+    name: String,
+    recorded_at: &str,
+    interval_ms: u64,
+    metadata: &RouteMetadata,
+    waypoints: RouteWaypoints,
+) -> Result<(), String> {
+    let json = route_to_json_string(points, name, recorded_at, interval_ms, metadata, waypoints)?;
     
     // Write to file
-    let mut file = File::create(&filepath)
+    let mut file = File::create(filepath)
         .map_err(|e| format!("Failed to create file: {}", e))?;
     file.write_all(json.as_bytes())
         .map_err(|e| format!("Failed to write file: {}", e))?;
     
-    Ok(filepath)
+    Ok(())
 }
 
+/// Serialize a route to the JSON of a saved route file, without touching the filesystem
+/// 
+/// Every saved file is written from this; all `waypoints` are included (see
+/// `RouteWaypoints::for_chunk` for split routes). Fails on an empty route.
+pub fn route_to_json_string(
+    points: &[RoutePoint],
+    name: String,
+    recorded_at: &str,
+    interval_ms: u64,
+    metadata: &RouteMetadata,
+    waypoints: RouteWaypoints,
+) -> Result<String, String> {
+    if points.is_empty() {
+        return Err("No route data to save".to_string());
+    }
+    
+    // Calculate duration covered by these points
+    let duration_secs = match (points.first(), points.last()) {
+        (Some(first), Some(last)) => {
+            last.timestamp_ms.saturating_sub(first.timestamp_ms) as f64 / 1000.0
+        }
+        _ => 0.0,
+    };
+    
+    // Create saved route structure
+    let saved_route = SavedRoute {
+        name,
+        recorded_at: recorded_at.to_string(),
+        duration_secs,
+        interval_ms,
+        point_count: points.len(),
+        metadata: metadata.clone(),
+        graces_visited: waypoints.graces_visited.to_vec(),
+        deaths: waypoints.deaths.to_vec(),
+        points: points.to_vec(),
+    };
+    
+    // serde_json writes NaN/inf as null, so a glitched read doesn't lose the whole
+    // route; report where they are since null coordinates break most consumers
+    let non_finite: Vec<usize> = points
+        .iter()
+        .enumerate()
+        .filter(|(_, p)| !p.has_finite_coordinates())
+        .map(|(index, _)| index)
+        .collect();
+    if let Some(first) = non_finite.first() {
+        warn!(
+            "{}: {} point(s) with NaN/infinite coordinates saved as null (first at index {})",
+            saved_route.name,
+            non_finite.len(),
+            first
+        );
+    }
+    
+    // Serialize to JSON
+    serde_json::to_string_pretty(&saved_route)
+        .map_err(|e| format!("Failed to serialize route: {}", e))
+}
+
+/// A point at the origin of m60_40_35_00 (global 0, 0, 0) at `timestamp_ms`, for tests
+/// 
+/// Tests override the fields they care about with struct update syntax, so a new
+/// field only needs a value here.
+#[cfg(test)]
+pub(crate) fn test_point(timestamp_ms: u64) -> RoutePoint {
+    RoutePoint {
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+        global_x: 0.0,
+        global_y: 0.0,
+        global_z: 0.0,
+        map_id: 0x3C282300,
+        map_id_str: "m60_40_35_00".to_string(),
+        global_map_id: 60,
+        global_map_label: "lands_between",
+        timestamp_ms,
+        epoch_ms: 0,
+        frame: None,
+        accuracy: PointAccuracy::ExactGlobal,
+        qx: None,
+        qy: None,
+        qz: None,
+        qw: None,
+        raw: None,
+        relative_to_grace: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_iso8601_from_unix() {
+        assert_eq!(iso8601_from_unix(0), "1970-01-01T00:00:00Z");
+        assert_eq!(iso8601_from_unix(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(iso8601_from_unix(1_767_225_599), "2025-12-31T23:59:59Z");
+    }
+
+    #[test]
+    fn test_save_route_includes_metadata() {
+        let base_dir = std::env::temp_dir().join(format!("route_metadata_test_{}", std::process::id()));
+        let route = vec![test_point(0), test_point(100)];
+        let metadata = RouteMetadata {
+            game_version: Some("1.16.0".to_string()),
+            character_name: None,
+            recorded_at: Some("2026-01-01T12:00:00Z".to_string()),
+            dataset_version: Some("WorldMapLegacyConvParam.csv".to_string()),
+        };
+
+        let paths = save_route_to_file(&route, &base_dir, "routes", 100, RouteFileSplit::Minutes(0), &metadata, RouteWaypoints::default()).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&paths[0]).unwrap()).unwrap();
+
+        assert_eq!(json["metadata"]["game_version"], "1.16.0");
+        assert_eq!(json["metadata"]["recorded_at"], "2026-01-01T12:00:00Z");
+        assert_eq!(json["metadata"]["dataset_version"], "WorldMapLegacyConvParam.csv");
+        assert!(json["metadata"].get("character_name").is_none());
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+
+    #[test]
+    fn test_route_to_json_string() {
+        let mut route = vec![test_point(0), test_point(100), test_point(250)];
+        route[1].global_x = 1234.5;
+        route[2].set_orientation(Some([0.0, 0.6, 0.0, 0.8]));
+
+        let deaths = [DeathEvent::at(&route[2], 1)];
+        let waypoints = RouteWaypoints { graces_visited: &[], deaths: &deaths };
+        let metadata = RouteMetadata { game_version: Some("1.16.0".to_string()), ..RouteMetadata::default() };
+        let json = route_to_json_string(&route, "Route".to_string(), "now", 100, &metadata, waypoints).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        let expected: serde_json::Value = serde_json::from_str(&serde_json::to_string(&route).unwrap()).unwrap();
+        assert_eq!(json["points"], expected);
+        assert_eq!(json["point_count"], 3);
+        assert_eq!(json["interval_ms"], 100);
+        assert_eq!(json["duration_secs"], 0.25);
+        assert_eq!(json["metadata"]["game_version"], "1.16.0");
+        assert_eq!(json["deaths"][0]["death_count"], 1);
+        assert!(route_to_json_string(&[], "Route".to_string(), "now", 100, &metadata, waypoints).is_err());
+    }
+
+    #[test]
+    fn test_save_route_with_nan_coordinates() {
+        let base_dir = std::env::temp_dir().join(format!("route_nan_test_{}", std::process::id()));
+        let mut glitched = test_point(100);
+        glitched.global_x = f32::NAN;
+        glitched.z = f32::INFINITY;
+        assert!(!glitched.has_finite_coordinates());
+        let route = vec![test_point(0), glitched, test_point(200)];
 
+        let paths = save_route_to_file(&route, &base_dir, "routes", 100, RouteFileSplit::Minutes(0), &RouteMetadata::default(), RouteWaypoints::default()).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&paths[0]).unwrap()).unwrap();
 
+        // The bad values become null, the rest of the route is kept
+        assert_eq!(json["point_count"], 3);
+        assert!(json["points"][1]["global_x"].is_null());
+        assert!(json["points"][1]["z"].is_null());
+        assert_eq!(json["points"][1]["timestamp_ms"], 100);
+        assert_eq!(json["points"][2]["timestamp_ms"], 200);
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+
+    #[test]
+    fn test_map_transition_fires_once() {
+        let mut route: Vec<RoutePoint> = (0..3).map(|i| test_point(i * 100)).collect();
+        for i in 3..6 {
+            let mut p = test_point(i * 100);
+            p.map_id = 0x0A000000;
+            p.map_id_str = "m10_00_00_00".to_string();
+            route.push(p);
+        }
+
+        let transitions: Vec<MapTransition> = route
+            .windows(2)
+            .filter_map(|pair| MapTransition::between(&pair[0], &pair[1]))
+            .collect();
+        assert_eq!(transitions.len(), 1);
+        assert_eq!(transitions[0].old_map_id_str, "m60_40_35_00");
+        assert_eq!(transitions[0].new_map_id_str, "m10_00_00_00");
+        assert_eq!(transitions[0].timestamp_ms, 300);
+    }
+
+    #[test]
+    fn test_split_route_by_window() {
+        // 1-minute windows starting at 1_000_000 ms, with an empty third window
+        let route: Vec<RoutePoint> = [0, 30_000, 59_999, 60_000, 90_000, 185_000]
+            .iter()
+            .map(|&offset| test_point(1_000_000 + offset))
+            .collect();
+
+        let chunks = split_route_by_window(&route, 60_000);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].len(), 3);
+        assert_eq!(chunks[1].len(), 2);
+        assert_eq!(chunks[2].len(), 1);
+
+        assert_eq!(split_route_by_window(&route, 0).len(), 1);
+    }
+
+    #[test]
+    fn test_save_route_split_into_parts() {
+        let base_dir = std::env::temp_dir().join(format!("route_split_test_{}", std::process::id()));
+        let route: Vec<RoutePoint> = (0..6).map(|i| test_point(i * 45_000)).collect();
+
+        // Points at 0s, 45s, 90s, 135s, 180s, 225s -> minute windows 0, 0, 1, 2, 3, 3
+        let paths =
+            save_route_to_file(&route, &base_dir, "routes", 100, RouteFileSplit::Minutes(1), &RouteMetadata::default(), RouteWaypoints::default())
+                .unwrap();
+        assert_eq!(paths.len(), 4);
+        assert!(paths[0].to_string_lossy().ends_with("_part01.json"));
+
+        for (window, path) in paths.iter().enumerate() {
+            let json: serde_json::Value =
+                serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+            let points = json["points"].as_array().unwrap();
+            assert_eq!(json["point_count"].as_u64().unwrap() as usize, points.len());
+            for p in points {
+                assert_eq!(p["timestamp_ms"].as_u64().unwrap() / 60_000, window as u64);
+            }
+        }
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+
+    #[test]
+    fn test_save_route_split_by_map() {
+        let base_dir = std::env::temp_dir().join(format!("route_split_by_map_test_{}", std::process::id()));
+        let mut route: Vec<RoutePoint> = (0..5).map(|i| test_point(i * 100)).collect();
+        for point in &mut route[3..] {
+            point.map_id = 0x0A000000;
+            point.map_id_str = "m10_00_00_00".to_string();
+        }
+
+        let paths = save_route_to_file(&route, &base_dir, "routes", 100, RouteFileSplit::ByMap, &RouteMetadata::default(), RouteWaypoints::default())
+            .unwrap();
+        assert_eq!(paths.len(), 2);
+        assert!(paths[0].to_string_lossy().ends_with("_m60_40_35_00_01.json"));
+        assert!(paths[1].to_string_lossy().ends_with("_m10_00_00_00_02.json"));
+
+        let point_counts: Vec<u64> = paths
+            .iter()
+            .map(|path| {
+                let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+                json["point_count"].as_u64().unwrap()
+            })
+            .collect();
+        assert_eq!(point_counts, vec![3, 2]);
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+
+    #[test]
+    fn test_split_route_keeps_every_death() {
+        let base_dir = std::env::temp_dir().join(format!("route_split_deaths_test_{}", std::process::id()));
+        // Minute windows 0 and 1, deaths between the chunks and after the last point
+        let route = vec![test_point(0), test_point(30_000), test_point(60_000), test_point(90_000)];
+        let deaths: Vec<DeathEvent> = [10_000, 45_000, 95_000]
+            .iter()
+            .zip(1..)
+            .map(|(&timestamp_ms, count)| DeathEvent::at(&test_point(timestamp_ms), count))
+            .collect();
+        let waypoints = RouteWaypoints { graces_visited: &[], deaths: &deaths };
+
+        let death_counts = |paths: &[PathBuf]| -> Vec<Vec<u64>> {
+            paths
+                .iter()
+                .map(|path| {
+                    let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+                    json["deaths"].as_array().map_or(Vec::new(), |deaths| {
+                        deaths.iter().map(|death| death["death_count"].as_u64().unwrap()).collect()
+                    })
+                })
+                .collect()
+        };
+
+        let paths = save_route_to_file(&route, &base_dir, "routes", 100, RouteFileSplit::Minutes(1), &RouteMetadata::default(), waypoints)
+            .unwrap();
+        assert_eq!(death_counts(&paths), vec![vec![1, 2], vec![3]]);
+
+        let paths = save_route_to_file(&route, &base_dir, "single", 100, RouteFileSplit::Minutes(0), &RouteMetadata::default(), waypoints)
+            .unwrap();
+        assert_eq!(death_counts(&paths), vec![vec![1, 2, 3]]);
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+
+    #[test]
+    fn test_global_map_label() {
+        assert_eq!(global_map_label(60), "lands_between");
+        assert_eq!(global_map_label(61), "shadow_realm");
+        assert_eq!(global_map_label(62), "underground");
+        assert_eq!(global_map_label(99), "unknown");
+    }
+
+    #[test]
+    fn test_orientation_serialization() {
+        let mut point = test_point(0);
+        let json = serde_json::to_value(&point).unwrap();
+        assert!(json.get("qx").is_none() && json.get("qw").is_none());
+
+        point.set_orientation(Some([0.0, 0.6, 0.0, 0.8]));
+        let json = serde_json::to_value(&point).unwrap();
+        assert_eq!(json["qx"], 0.0);
+        assert_eq!(json["qy"].as_f64().unwrap() as f32, 0.6);
+        assert_eq!(json["qz"], 0.0);
+        assert_eq!(json["qw"].as_f64().unwrap() as f32, 0.8);
+    }
+
+    #[test]
+    fn test_frame_serialization() {
+        let mut point = test_point(0);
+        assert!(serde_json::to_value(&point).unwrap().get("frame").is_none());
+
+        point.frame = Some(123_456_789_012);
+        let json = serde_json::to_string(&point).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["frame"].as_u64(), Some(123_456_789_012));
+    }
+
+    #[test]
+    fn test_reconvert_with_new_dataset() {
+        // Globals stored by an older dataset
+        let mut overworld = test_point(100);
+        overworld.x = 10.0;
+        overworld.z = 20.0;
+        overworld.map_id_str = "stale".to_string();
+        let mut interior = test_point(200);
+        interior.map_id = 0x0A000000; // m10_00_00_00, no anchor in the new dataset
+        interior.global_x = 1234.0;
+        let mut route = vec![overworld, interior];
+
+        let flagged = WorldPositionTransformer::empty().reconvert(&mut route);
+
+        assert_eq!(flagged, 1);
+        assert_eq!((route[0].global_x, route[0].global_z), (10.0 + 40.0 * 256.0, 20.0 + 35.0 * 256.0));
+        assert_eq!(route[0].map_id_str, "m60_40_35_00");
+        assert_eq!((route[0].x, route[0].timestamp_ms), (10.0, 100));
+        assert_eq!(route[1].global_x, 1234.0);
+        assert_eq!(route[1].accuracy, PointAccuracy::Fallback);
+    }
+
+    #[test]
+    fn test_split_at_teleports_uses_speed() {
+        let at = |global_x: f32, timestamp_ms: u64| RoutePoint { global_x, ..test_point(timestamp_ms) };
+
+        // 500 units over 10s (dropped samples on an elevator): 50 u/s, same segment
+        let slow = vec![at(0.0, 0), at(500.0, 10_000)];
+        assert_eq!(split_at_teleports(&slow, TELEPORT_SPEED).len(), 1);
+
+        // The same 500 units in 100ms: 5000 u/s, teleport
+        let fast = vec![at(0.0, 0), at(500.0, 100)];
+        assert_eq!(split_at_teleports(&fast, TELEPORT_SPEED).len(), 2);
+    }
+}
@@ -0,0 +1,962 @@
+// Route analysis helpers (comparison, resampling, statistics)
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::coordinate_transformer::WorldPositionTransformer;
+use crate::route::{is_teleport, split_at_teleports, DeathEvent, GraceVisit, RoutePoint, RouteWaypoints};
+
+// =============================================================================
+// ROUTE COMPARISON
+// =============================================================================
+
+/// A point of route B matched with its closest point on route A
+#[derive(Debug, Clone)]
+pub struct AlignedPair {
+    /// Index of the matched point in route A
+    pub index_a: usize,
+    /// Index of the point in route B
+    pub index_b: usize,
+    /// Distance between the two points (global units)
+    pub deviation: f32,
+    /// Elapsed time on B minus elapsed time on A at this spot, in milliseconds
+    /// (positive = B is behind A)
+    pub time_delta_ms: i64,
+}
+
+/// Result of comparing two runs of the same route
+#[derive(Debug, Clone, Default)]
+pub struct RouteDiff {
+    /// One entry per point of B that could be matched on A
+    pub pairs: Vec<AlignedPair>,
+    /// Points of B with no point of A on the same global map
+    pub unmatched_count: usize,
+    /// Average deviation over all pairs
+    pub mean_deviation: f32,
+    /// Largest deviation over all pairs
+    pub max_deviation: f32,
+    /// Time delta of the last pair (overall time lost or gained by B)
+    pub final_time_delta_ms: i64,
+}
+
+/// Compare run B against reference run A
+///
+/// Each point of B is aligned with the nearest point of A (by global position,
+/// on the same global map). Elapsed times are measured from each route's first point.
+pub fn diff_routes(a: &[RoutePoint], b: &[RoutePoint]) -> RouteDiff {
+    let (Some(a_start), Some(b_start)) = (a.first(), b.first()) else {
+        return RouteDiff {
+            unmatched_count: b.len(),
+            ..RouteDiff::default()
+        };
+    };
+    
+    let mut diff = RouteDiff::default();
+    
+    for (index_b, point_b) in b.iter().enumerate() {
+        let nearest = a
+            .iter()
+            .enumerate()
+            .filter(|(_, point_a)| point_a.global_map_id == point_b.global_map_id)
+            .map(|(index_a, point_a)| (index_a, point_a, point_a.distance_to(point_b)))
+            .min_by(|x, y| x.2.total_cmp(&y.2));
+        
+        let Some((index_a, point_a, deviation)) = nearest else {
+            diff.unmatched_count += 1;
+            continue;
+        };
+        
+        let elapsed_a = point_a.timestamp_ms as i64 - a_start.timestamp_ms as i64;
+        let elapsed_b = point_b.timestamp_ms as i64 - b_start.timestamp_ms as i64;
+        
+        diff.pairs.push(AlignedPair {
+            index_a,
+            index_b,
+            deviation,
+            time_delta_ms: elapsed_b - elapsed_a,
+        });
+    }
+    
+    if !diff.pairs.is_empty() {
+        diff.mean_deviation =
+            diff.pairs.iter().map(|p| p.deviation).sum::<f32>() / diff.pairs.len() as f32;
+        diff.max_deviation = diff.pairs.iter().map(|p| p.deviation).fold(0.0, f32::max);
+        diff.final_time_delta_ms = diff.pairs.last().map(|p| p.time_delta_ms).unwrap_or(0);
+    }
+    
+    diff
+}
+
+/// Index of the point of `route` closest to a global position, and its 3D distance
+///
+/// Linear scan over every point, regardless of global map. None for an empty route.
+pub fn nearest_route_point(route: &[RoutePoint], gx: f32, gy: f32, gz: f32) -> Option<(usize, f32)> {
+    route
+        .iter()
+        .enumerate()
+        .map(|(index, point)| {
+            let dx = point.global_x - gx;
+            let dy = point.global_y - gy;
+            let dz = point.global_z - gz;
+            (index, (dx * dx + dy * dy + dz * dz).sqrt())
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+/// Pairs of points `(i, j)`, `i < j`, where the route came back near an earlier location
+///
+/// Reported when both points are on the same global map, within `radius` (3D,
+/// global units) and at least `min_gap` points apart, so consecutive samples
+/// don't count. Pairs are sorted by `i`, then `j`. Points are bucketed in a grid
+/// of `radius`-sized cells, so long routes don't need a pairwise scan.
+pub fn detect_revisits(route: &[RoutePoint], radius: f32, min_gap: usize) -> Vec<(usize, usize)> {
+    if radius <= 0.0 {
+        return Vec::new();
+    }
+    
+    let cell_of = |point: &RoutePoint| {
+        (
+            point.global_map_id,
+            (point.global_x / radius).floor() as i32,
+            (point.global_z / radius).floor() as i32,
+        )
+    };
+    
+    let mut cells: HashMap<(u8, i32, i32), Vec<usize>> = HashMap::new();
+    let mut revisits = Vec::new();
+    for (j, point) in route.iter().enumerate() {
+        // Earlier points become candidates once they are `min_gap` behind
+        if let Some(i) = j.checked_sub(min_gap.max(1)) {
+            cells.entry(cell_of(&route[i])).or_default().push(i);
+        }
+        
+        let (map, cx, cz) = cell_of(point);
+        for dx in -1..=1 {
+            for dz in -1..=1 {
+                let Some(candidates) = cells.get(&(map, cx + dx, cz + dz)) else {
+                    continue;
+                };
+                revisits.extend(
+                    candidates
+                        .iter()
+                        .filter(|&&i| route[i].distance_to(point) <= radius)
+                        .map(|&i| (i, j)),
+                );
+            }
+        }
+    }
+    
+    revisits.sort_unstable();
+    revisits
+}
+
+// =============================================================================
+// RESAMPLING
+// =============================================================================
+
+/// Linearly interpolate between two points of the same map
+///
+/// Coordinates are interpolated; map information, orientation, `epoch_ms` and `frame` are taken from the nearest point.
+fn lerp_point(a: &RoutePoint, b: &RoutePoint, t: f32, timestamp_ms: u64) -> RoutePoint {
+    let lerp = |from: f32, to: f32| from + (to - from) * t;
+    let nearest = if t < 0.5 { a } else { b };
+    
+    RoutePoint {
+        x: lerp(a.x, b.x),
+        y: lerp(a.y, b.y),
+        z: lerp(a.z, b.z),
+        global_x: lerp(a.global_x, b.global_x),
+        global_y: lerp(a.global_y, b.global_y),
+        global_z: lerp(a.global_z, b.global_z),
+        map_id: nearest.map_id,
+        map_id_str: nearest.map_id_str.clone(),
+        global_map_id: nearest.global_map_id,
+        global_map_label: nearest.global_map_label,
+        timestamp_ms,
+        epoch_ms: nearest.epoch_ms,
+        frame: nearest.frame,
+        accuracy: nearest.accuracy,
+        qx: nearest.qx,
+        qy: nearest.qy,
+        qz: nearest.qz,
+        qw: nearest.qw,
+        // Not a memory read: the raw array only belongs to recorded points
+        raw: None,
+        // The offset moves with the position, as long as both ends share the grace
+        relative_to_grace: match (&a.relative_to_grace, &b.relative_to_grace) {
+            (Some((name, ax, ay, az)), Some((other, bx, by, bz))) if name == other => {
+                Some((name.clone(), lerp(*ax, *bx), lerp(*ay, *by), lerp(*az, *bz)))
+            }
+            _ => None,
+        },
+    }
+}
+
+/// Resample a route to a fixed time step
+///
+/// Produces one point every `step_ms` from the first to the last timestamp,
+/// linearly interpolating the bracketing points. Across teleports (map change
+/// or move faster than `teleport_speed`) the nearest source point is held
+/// instead of interpolating. A `step_ms` of 0 returns the route unchanged.
+pub fn resample_route(route: &[RoutePoint], step_ms: u64, teleport_speed: f32) -> Vec<RoutePoint> {
+    let (Some(first), Some(last)) = (route.first(), route.last()) else {
+        return Vec::new();
+    };
+    
+    if step_ms == 0 {
+        return route.to_vec();
+    }
+    
+    let mut resampled = Vec::new();
+    let mut index = 0;
+    let mut timestamp_ms = first.timestamp_ms;
+    
+    while timestamp_ms <= last.timestamp_ms {
+        // Advance to the pair bracketing the target timestamp
+        while index + 1 < route.len() && route[index + 1].timestamp_ms < timestamp_ms {
+            index += 1;
+        }
+        
+        let a = &route[index];
+        let point = match route.get(index + 1) {
+            Some(b) if b.timestamp_ms > a.timestamp_ms => {
+                let t = (timestamp_ms.saturating_sub(a.timestamp_ms)) as f32
+                    / (b.timestamp_ms - a.timestamp_ms) as f32;
+                let t = t.clamp(0.0, 1.0);
+                
+                if !is_teleport(a, b, teleport_speed) {
+                    lerp_point(a, b, t, timestamp_ms)
+                } else {
+                    let nearest = if t < 0.5 { a } else { b };
+                    RoutePoint { timestamp_ms, ..nearest.clone() }
+                }
+            }
+            _ => RoutePoint { timestamp_ms, ..a.clone() },
+        };
+        
+        resampled.push(point);
+        timestamp_ms += step_ms;
+    }
+    
+    resampled
+}
+
+/// Global position at `t_ms`, interpolated between the bracketing points
+///
+/// Returns None outside the route's time range. Across teleports (same rule as
+/// `resample_route`) the nearest point's position is returned instead.
+pub fn position_at(route: &[RoutePoint], t_ms: u64, teleport_speed: f32) -> Option<(f32, f32, f32)> {
+    let (first, last) = (route.first()?, route.last()?);
+    if t_ms < first.timestamp_ms || t_ms > last.timestamp_ms {
+        return None;
+    }
+    
+    // First point at or after t_ms (exists since t_ms <= last)
+    let index = route.partition_point(|p| p.timestamp_ms < t_ms);
+    let b = &route[index];
+    if b.timestamp_ms == t_ms || index == 0 {
+        return Some((b.global_x, b.global_y, b.global_z));
+    }
+    
+    let a = &route[index - 1];
+    let t = (t_ms - a.timestamp_ms) as f32 / (b.timestamp_ms - a.timestamp_ms) as f32;
+    let point = if !is_teleport(a, b, teleport_speed) {
+        lerp_point(a, b, t, t_ms)
+    } else {
+        let nearest = if t < 0.5 { a } else { b };
+        nearest.clone()
+    };
+    Some((point.global_x, point.global_y, point.global_z))
+}
+
+/// Points spaced evenly along the traveled path, one every `spacing` units
+///
+/// Starts with the first point, then walks the cumulative global distance and
+/// interpolates a point (timestamp included) each time it reaches a multiple of
+/// `spacing`. Teleport jumps (see `split_at_teleports`) add no distance. Returns
+/// an empty route if `spacing` is not positive.
+pub fn waypoints_by_distance(route: &[RoutePoint], spacing: f32, teleport_speed: f32) -> Vec<RoutePoint> {
+    let Some(first) = route.first() else {
+        return Vec::new();
+    };
+    if spacing <= 0.0 {
+        return Vec::new();
+    }
+    
+    let mut waypoints = vec![first.clone()];
+    let mut traveled = 0.0;
+    let mut next = spacing;
+    for segment in split_at_teleports(route, teleport_speed) {
+        for pair in segment.windows(2) {
+            let (a, b) = (&pair[0], &pair[1]);
+            let step = a.distance_to(b);
+            while step > 0.0 && traveled + step >= next {
+                let t = (next - traveled) / step;
+                let timestamp_ms = a.timestamp_ms + (b.timestamp_ms.saturating_sub(a.timestamp_ms) as f32 * t) as u64;
+                waypoints.push(lerp_point(a, b, t, timestamp_ms));
+                next += spacing;
+            }
+            traveled += step;
+        }
+    }
+    
+    waypoints
+}
+
+/// Part of a route with the waypoints that go with it
+#[derive(Debug, Clone, Default)]
+pub struct RouteClip {
+    pub points: Vec<RoutePoint>,
+    /// Sites of Grace passed by, in order
+    pub graces_visited: Vec<GraceVisit>,
+    /// Player deaths, in order
+    pub deaths: Vec<DeathEvent>,
+}
+
+impl RouteClip {
+    /// Borrow the waypoints, e.g. to save or export the clip
+    pub fn waypoints(&self) -> RouteWaypoints<'_> {
+        RouteWaypoints { graces_visited: &self.graces_visited, deaths: &self.deaths }
+    }
+}
+
+/// Extract the points and waypoints recorded between `start_ms` and `end_ms` (inclusive)
+///
+/// Timestamps of the returned points and waypoints are rebased so that `start_ms` becomes 0.
+pub fn slice_by_time(route: &[RoutePoint], waypoints: &RouteWaypoints, start_ms: u64, end_ms: u64) -> RouteClip {
+    let window = start_ms..=end_ms;
+    RouteClip {
+        points: route
+            .iter()
+            .filter(|p| window.contains(&p.timestamp_ms))
+            .map(|p| RoutePoint { timestamp_ms: p.timestamp_ms - start_ms, ..p.clone() })
+            .collect(),
+        graces_visited: waypoints
+            .graces_visited
+            .iter()
+            .filter(|visit| window.contains(&visit.timestamp_ms))
+            .map(|visit| GraceVisit { timestamp_ms: visit.timestamp_ms - start_ms, ..visit.clone() })
+            .collect(),
+        deaths: waypoints
+            .deaths
+            .iter()
+            .filter(|death| window.contains(&death.timestamp_ms))
+            .map(|death| DeathEvent { timestamp_ms: death.timestamp_ms - start_ms, ..death.clone() })
+            .collect(),
+    }
+}
+
+/// Keep every `n`th point of a route, for a lighter copy to share
+///
+/// The first and last points are always kept, as are both points around each
+/// map tile change so transitions stay where they happened. The clip covers the
+/// same time span as the route, so it keeps all of `waypoints`. An `n` of 0 or 1
+/// returns the route unchanged.
+pub fn decimate_route(route: &[RoutePoint], waypoints: &RouteWaypoints, n: usize) -> RouteClip {
+    let clip = |points: Vec<RoutePoint>| RouteClip {
+        points,
+        graces_visited: waypoints.graces_visited.to_vec(),
+        deaths: waypoints.deaths.to_vec(),
+    };
+    if n <= 1 {
+        return clip(route.to_vec());
+    }
+    
+    let tile_changes_at = |index: usize| {
+        let changed = |a: usize, b: usize| route[a].map_id != route[b].map_id;
+        (index > 0 && changed(index - 1, index)) || (index + 1 < route.len() && changed(index, index + 1))
+    };
+    
+    let points = route
+        .iter()
+        .enumerate()
+        .filter(|&(index, _)| index % n == 0 || index + 1 == route.len() || tile_changes_at(index))
+        .map(|(_, point)| point.clone())
+        .collect();
+    clip(points)
+}
+
+// =============================================================================
+// VALIDATION
+// =============================================================================
+
+/// Find points reached faster than `max_speed` (global units per second)
+///
+/// Returns the index of the second point of each offending segment. Speeds use
+/// the horizontal (x/z) distance only, so falls and elevators are not flagged.
+/// Teleports (map change or move faster than `teleport_speed`) are skipped.
+pub fn flag_impossible_speeds(route: &[RoutePoint], max_speed: f32, teleport_speed: f32) -> Vec<usize> {
+    route
+        .windows(2)
+        .enumerate()
+        .filter(|(_, pair)| {
+            let (a, b) = (&pair[0], &pair[1]);
+            if is_teleport(a, b, teleport_speed) {
+                return false;
+            }
+            
+            let dx = b.global_x - a.global_x;
+            let dz = b.global_z - a.global_z;
+            let distance = (dx * dx + dz * dz).sqrt();
+            let elapsed_secs = b.timestamp_ms.saturating_sub(a.timestamp_ms) as f32 / 1000.0;
+            
+            // Moving without time passing counts as infinitely fast
+            distance > max_speed * elapsed_secs
+        })
+        .map(|(i, _)| i + 1)
+        .collect()
+}
+
+/// Inconsistency found in a route by `validate_route`
+#[derive(Debug, Clone, PartialEq)]
+pub enum RouteDefect {
+    /// Timestamp earlier than the previous point's
+    TimestampBackwards { index: usize, previous_ms: u64, timestamp_ms: u64 },
+    /// NaN or infinite local or global coordinate
+    NonFiniteCoordinate { index: usize },
+    /// `map_id_str` doesn't match the packed `map_id`
+    MapIdMismatch { index: usize, map_id: u32, map_id_str: String },
+}
+
+impl std::fmt::Display for RouteDefect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RouteDefect::TimestampBackwards { index, previous_ms, timestamp_ms } => write!(
+                f,
+                "point {}: timestamp {} is before the previous one ({})",
+                index, timestamp_ms, previous_ms
+            ),
+            RouteDefect::NonFiniteCoordinate { index } => {
+                write!(f, "point {}: coordinate is NaN or infinite", index)
+            }
+            RouteDefect::MapIdMismatch { index, map_id, map_id_str } => write!(
+                f,
+                "point {}: map_id_str {} doesn't match map_id {}",
+                index,
+                map_id_str,
+                WorldPositionTransformer::format_map_id(*map_id)
+            ),
+        }
+    }
+}
+
+/// Check a loaded route for corruption before using it
+///
+/// Reports backwards timestamps, non-finite coordinates and `map_id_str` values
+/// that don't match `map_id`, in point order (empty if the route is consistent).
+pub fn validate_route(route: &[RoutePoint]) -> Vec<RouteDefect> {
+    let mut defects = Vec::new();
+    
+    for (index, point) in route.iter().enumerate() {
+        if let Some(previous) = index.checked_sub(1).map(|i| &route[i]) {
+            if point.timestamp_ms < previous.timestamp_ms {
+                defects.push(RouteDefect::TimestampBackwards {
+                    index,
+                    previous_ms: previous.timestamp_ms,
+                    timestamp_ms: point.timestamp_ms,
+                });
+            }
+        }
+        
+        let coordinates = [point.x, point.y, point.z, point.global_x, point.global_y, point.global_z];
+        if !coordinates.iter().all(|c| c.is_finite()) {
+            defects.push(RouteDefect::NonFiniteCoordinate { index });
+        }
+        
+        if point.map_id_str != WorldPositionTransformer::format_map_id(point.map_id) {
+            defects.push(RouteDefect::MapIdMismatch {
+                index,
+                map_id: point.map_id,
+                map_id_str: point.map_id_str.clone(),
+            });
+        }
+    }
+    
+    defects
+}
+
+// =============================================================================
+// STATISTICS
+// =============================================================================
+
+/// Overall figures of a route
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RouteStats {
+    /// Number of points
+    pub point_count: usize,
+    /// Time between the first and last point, in milliseconds
+    pub duration_ms: u64,
+    /// Distance travelled (global units), teleports excluded
+    pub distance: f32,
+    /// Number of teleports (map change or move faster than the teleport speed)
+    pub teleport_count: usize,
+}
+
+/// A continuous leg of a route, between two teleports
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RouteSplit {
+    /// Map tile the leg starts on
+    pub map_id_str: String,
+    /// Timestamp of the first point, in milliseconds
+    pub start_ms: u64,
+    /// Time spent on this leg, in milliseconds
+    pub duration_ms: u64,
+    /// Distance travelled on this leg (global units)
+    pub distance: f32,
+}
+
+/// Summary of a route without its points (see `save_route_summary`)
+#[derive(Debug, Clone, Serialize)]
+pub struct RouteSummary {
+    pub stats: RouteStats,
+    /// Legs of the route, split at teleports
+    pub splits: Vec<RouteSplit>,
+    /// Number of distinct map tiles visited
+    pub tiles_visited: usize,
+}
+
+/// Compute the overall figures of a route
+pub fn route_stats(route: &[RoutePoint], teleport_speed: f32) -> RouteStats {
+    let segments = split_at_teleports(route, teleport_speed);
+    RouteStats {
+        point_count: route.len(),
+        duration_ms: duration_ms(route),
+        distance: segments.iter().map(|segment| path_length(segment)).sum(),
+        teleport_count: segments.len().saturating_sub(1),
+    }
+}
+
+/// Compute the stats, splits and visited tile count of a route
+pub fn route_summary(route: &[RoutePoint], teleport_speed: f32) -> RouteSummary {
+    let splits = split_at_teleports(route, teleport_speed)
+        .into_iter()
+        .map(|segment| RouteSplit {
+            map_id_str: segment[0].map_id_str.clone(),
+            start_ms: segment[0].timestamp_ms,
+            duration_ms: duration_ms(segment),
+            distance: path_length(segment),
+        })
+        .collect();
+    
+    RouteSummary {
+        stats: route_stats(route, teleport_speed),
+        splits,
+        tiles_visited: route.iter().map(|p| p.map_id).collect::<HashSet<_>>().len(),
+    }
+}
+
+/// Map tiles in the order the route passed through them
+///
+/// Consecutive points on the same tile collapse into one entry, but revisits
+/// are kept: entering A, then B, then A again gives `[A, B, A]`.
+pub fn tile_sequence(route: &[RoutePoint]) -> Vec<u32> {
+    let mut tiles: Vec<u32> = route.iter().map(|p| p.map_id).collect();
+    tiles.dedup();
+    tiles
+}
+
+/// Stable fingerprint of a route, for detecting duplicate uploads
+///
+/// FNV-1a over the ordered global coordinates and map ids of every point, so
+/// the value is the same across runs and machines. Timestamps are ignored:
+/// the same path recorded twice gives the same fingerprint.
+pub fn route_fingerprint(route: &[RoutePoint]) -> u64 {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+    
+    route
+        .iter()
+        .flat_map(|p| {
+            [p.global_x.to_bits(), p.global_y.to_bits(), p.global_z.to_bits(), p.map_id]
+                .into_iter()
+                .flat_map(u32::to_le_bytes)
+        })
+        .fold(FNV_OFFSET_BASIS, |hash, byte| (hash ^ byte as u64).wrapping_mul(FNV_PRIME))
+}
+
+/// Average speed (units/s) over the last `samples` points
+/// 
+/// Returns None with fewer than two points or no elapsed time. Teleports (map
+/// change or move faster than `teleport_speed`) are skipped, time included, so
+/// a teleport doesn't read as a sprint.
+pub fn recent_speed(route: &[RoutePoint], samples: usize, teleport_speed: f32) -> Option<f32> {
+    let tail = &route[route.len().saturating_sub(samples)..];
+    let (distance, elapsed_ms) = tail
+        .windows(2)
+        .filter(|pair| !is_teleport(&pair[0], &pair[1], teleport_speed))
+        .fold((0.0, 0), |(distance, elapsed_ms), pair| {
+            (distance + pair[0].distance_to(&pair[1]), elapsed_ms + duration_ms(pair))
+        });
+    if elapsed_ms == 0 {
+        return None;
+    }
+    
+    Some(distance / (elapsed_ms as f32 / 1000.0))
+}
+
+/// Average speed (units/s) in each map tile, keyed by `map_id`
+/// 
+/// Distance traveled on a tile divided by the time spent there. Each step counts
+/// toward the tile it starts on; teleport jumps (see `split_at_teleports`) are
+/// left out entirely, time included. Tiles with no elapsed time are omitted.
+pub fn speed_by_map(route: &[RoutePoint], teleport_speed: f32) -> HashMap<u32, f32> {
+    let mut totals: HashMap<u32, (f32, u64)> = HashMap::new();
+    for segment in split_at_teleports(route, teleport_speed) {
+        for pair in segment.windows(2) {
+            let (distance, elapsed_ms) = totals.entry(pair[0].map_id).or_default();
+            *distance += pair[0].distance_to(&pair[1]);
+            *elapsed_ms += duration_ms(pair);
+        }
+    }
+    
+    totals
+        .into_iter()
+        .filter(|&(_, (_, elapsed_ms))| elapsed_ms > 0)
+        .map(|(map_id, (distance, elapsed_ms))| (map_id, distance / (elapsed_ms as f32 / 1000.0)))
+        .collect()
+}
+
+/// Write the summary of a route as a small JSON file (no points)
+pub fn save_route_summary(route: &[RoutePoint], teleport_speed: f32, path: &Path) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(&route_summary(route, teleport_speed))
+        .map_err(|e| format!("Failed to serialize route summary: {}", e))?;
+    fs::write(path, json).map_err(|e| format!("Failed to write route summary: {}", e))
+}
+
+fn duration_ms(points: &[RoutePoint]) -> u64 {
+    match (points.first(), points.last()) {
+        (Some(first), Some(last)) => last.timestamp_ms.saturating_sub(first.timestamp_ms),
+        _ => 0,
+    }
+}
+
+fn path_length(points: &[RoutePoint]) -> f32 {
+    points.windows(2).map(|pair| pair[0].distance_to(&pair[1])).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::route::{test_point, TELEPORT_SPEED};
+
+    fn point(global_x: f32, global_z: f32, timestamp_ms: u64) -> RoutePoint {
+        RoutePoint { global_x, global_z, ..test_point(timestamp_ms) }
+    }
+
+    #[test]
+    fn test_diff_routes_offset_and_slower() {
+        // A walks along X, one point per second
+        let a: Vec<RoutePoint> = (0..10).map(|i| point(i as f32 * 10.0, 0.0, i * 1000)).collect();
+        // B follows 2 units to the side, 200ms slower per point
+        let b: Vec<RoutePoint> = (0..10)
+            .map(|i| point(i as f32 * 10.0, 2.0, 50_000 + i * 1200))
+            .collect();
+
+        let diff = diff_routes(&a, &b);
+
+        assert_eq!(diff.pairs.len(), 10);
+        assert_eq!(diff.unmatched_count, 0);
+        for (i, pair) in diff.pairs.iter().enumerate() {
+            assert_eq!(pair.index_a, i);
+            assert!((pair.deviation - 2.0).abs() < 1e-4);
+            assert_eq!(pair.time_delta_ms, i as i64 * 200);
+        }
+        assert!((diff.mean_deviation - 2.0).abs() < 1e-4);
+        assert!((diff.max_deviation - 2.0).abs() < 1e-4);
+        assert_eq!(diff.final_time_delta_ms, 1800);
+    }
+
+    #[test]
+    fn test_resample_route_interpolates() {
+        let route = vec![
+            point(0.0, 0.0, 1000),
+            point(10.0, 0.0, 2000),
+            point(20.0, 4.0, 3000),
+        ];
+
+        let resampled = resample_route(&route, 250, TELEPORT_SPEED);
+
+        // 1000, 1250, ..., 3000
+        assert_eq!(resampled.len(), 9);
+        assert_eq!(resampled[2].timestamp_ms, 1500);
+        assert!((resampled[2].global_x - 5.0).abs() < 1e-4);
+        assert!((resampled[6].global_x - 15.0).abs() < 1e-4);
+        assert!((resampled[6].global_z - 2.0).abs() < 1e-4);
+        assert!((resampled[8].global_x - 20.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_resample_route_holds_across_teleport() {
+        let route = vec![point(0.0, 0.0, 0), point(5000.0, 0.0, 1000)];
+
+        let resampled = resample_route(&route, 200, TELEPORT_SPEED);
+
+        assert_eq!(resampled.len(), 6);
+        assert_eq!(resampled[1].global_x, 0.0);
+        assert_eq!(resampled[4].global_x, 5000.0);
+    }
+
+    #[test]
+    fn test_flag_impossible_speeds() {
+        let mut drop = point(25.0, 0.0, 2000);
+        drop.global_y = -150.0;
+        let route = vec![
+            point(0.0, 0.0, 0),
+            point(10.0, 0.0, 1000),     // 10 u/s
+            point(25.0, 0.0, 1100),     // 150 u/s: too fast
+            drop,                       // straight fall, not flagged
+            point(5000.0, 0.0, 2100),   // teleport
+            point(5010.0, 0.0, 3100),
+        ];
+
+        assert_eq!(flag_impossible_speeds(&route, 50.0, TELEPORT_SPEED), vec![2]);
+    }
+
+    #[test]
+    fn test_validate_route() {
+        let mut wrong_map = point(20.0, 0.0, 2000);
+        wrong_map.map_id_str = "m61_40_35_00".to_string();
+        let mut nan = point(30.0, 0.0, 3000);
+        nan.global_z = f32::NAN;
+        let route = vec![
+            point(0.0, 0.0, 1000),
+            point(10.0, 0.0, 500), // backwards
+            wrong_map,
+            nan,
+        ];
+
+        assert_eq!(
+            validate_route(&route),
+            vec![
+                RouteDefect::TimestampBackwards { index: 1, previous_ms: 1000, timestamp_ms: 500 },
+                RouteDefect::MapIdMismatch { index: 2, map_id: 0x3C282300, map_id_str: "m61_40_35_00".to_string() },
+                RouteDefect::NonFiniteCoordinate { index: 3 },
+            ]
+        );
+        // Equal timestamps are fine
+        assert!(validate_route(&[point(0.0, 0.0, 1000), point(1.0, 0.0, 1000)]).is_empty());
+    }
+
+    #[test]
+    fn test_save_route_summary() {
+        // 30 units in 3s, warp, then 10 units in 1s
+        let mut route: Vec<RoutePoint> = (0..4).map(|i| point(i as f32 * 10.0, 0.0, i * 1000)).collect();
+        route.push(point(5000.0, 0.0, 10_000));
+        route.push(point(5010.0, 0.0, 11_000));
+        route[4].map_id = 0x3C2A2300;
+        route[4].map_id_str = "m60_42_35_00".to_string();
+        route[5].map_id = 0x3C2A2300;
+        
+        let path = std::env::temp_dir().join(format!("route_summary_test_{}.json", std::process::id()));
+        save_route_summary(&route, TELEPORT_SPEED, &path).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+        
+        assert_eq!(json["stats"]["duration_ms"], 11_000);
+        assert_eq!(json["stats"]["distance"], 40.0);
+        assert_eq!(json["stats"]["teleport_count"], 1);
+        assert_eq!(json["tiles_visited"], 2);
+        assert_eq!(json["splits"].as_array().unwrap().len(), 2);
+        assert_eq!(json["splits"][1]["map_id_str"], "m60_42_35_00");
+        assert_eq!(json["splits"][1]["duration_ms"], 1000);
+        assert!(json.get("points").is_none());
+    }
+
+    #[test]
+    fn test_slice_by_time() {
+        // One point per second from t=100s to t=200s
+        let route: Vec<RoutePoint> = (0..=100).map(|i| point(i as f32, 0.0, 100_000 + i * 1000)).collect();
+        
+        let graces = [
+            GraceVisit { id: 1, name: "Before".to_string(), timestamp_ms: 120_000 },
+            GraceVisit { id: 2, name: "Inside".to_string(), timestamp_ms: 150_000 },
+        ];
+        let deaths = [DeathEvent::at(&route[10], 1), DeathEvent::at(&route[70], 2)];
+        let waypoints = RouteWaypoints { graces_visited: &graces, deaths: &deaths };
+        
+        // Last 60 seconds
+        let clip = slice_by_time(&route, &waypoints, 140_000, 200_000);
+        assert_eq!(clip.points.len(), 61);
+        assert_eq!(clip.points[0].timestamp_ms, 0);
+        assert_eq!(clip.points[0].global_x, 40.0);
+        assert_eq!(clip.points.last().unwrap().timestamp_ms, 60_000);
+        assert_eq!(clip.graces_visited.len(), 1);
+        assert_eq!((clip.graces_visited[0].id, clip.graces_visited[0].timestamp_ms), (2, 10_000));
+        assert_eq!(clip.deaths.len(), 1);
+        assert_eq!((clip.deaths[0].death_count, clip.deaths[0].timestamp_ms), (2, 30_000));
+        
+        // Window edges between points
+        let clip = slice_by_time(&route, &waypoints, 150_500, 152_500);
+        let timestamps: Vec<u64> = clip.points.iter().map(|p| p.timestamp_ms).collect();
+        assert_eq!(timestamps, vec![500, 1500]);
+        assert!(clip.graces_visited.is_empty());
+        
+        let clip = slice_by_time(&route, &waypoints, 300_000, 400_000);
+        assert!(clip.points.is_empty() && clip.graces_visited.is_empty() && clip.deaths.is_empty());
+    }
+
+    #[test]
+    fn test_tile_sequence_keeps_revisits() {
+        let on_tile = |map_id: u32, timestamp_ms: u64| RoutePoint { map_id, ..point(0.0, 0.0, timestamp_ms) };
+        let (a, b) = (0x3C282300, 0x3C292300);
+        let route = vec![on_tile(a, 0), on_tile(a, 100), on_tile(b, 200), on_tile(b, 300), on_tile(a, 400)];
+
+        assert_eq!(tile_sequence(&route), vec![a, b, a]);
+        assert!(tile_sequence(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_waypoints_by_distance() {
+        // 100 units along X in 10 steps, then a teleport and 50 more units
+        let mut route: Vec<RoutePoint> = (0..=10).map(|i| point(i as f32 * 10.0, 0.0, i * 1000)).collect();
+        route.push(point(5000.0, 0.0, 10_000));
+        route.push(point(5050.0, 0.0, 15_000));
+
+        let waypoints = waypoints_by_distance(&route, 25.0, TELEPORT_SPEED);
+        let xs: Vec<f32> = waypoints.iter().map(|p| p.global_x).collect();
+        assert_eq!(xs, vec![0.0, 25.0, 50.0, 75.0, 100.0, 5025.0, 5050.0]);
+        assert_eq!(waypoints[1].timestamp_ms, 2500);
+        assert_eq!(waypoints[5].timestamp_ms, 12_500);
+
+        assert!(waypoints_by_distance(&route, 0.0, TELEPORT_SPEED).is_empty());
+        assert!(waypoints_by_distance(&[], 25.0, TELEPORT_SPEED).is_empty());
+    }
+
+    #[test]
+    fn test_position_at() {
+        let route = vec![
+            point(0.0, 0.0, 1000),
+            point(10.0, 20.0, 2000),
+            // Teleport: snapped, not interpolated
+            point(5000.0, 20.0, 3000),
+        ];
+
+        assert_eq!(position_at(&route, 1500, TELEPORT_SPEED), Some((5.0, 0.0, 10.0)));
+        assert_eq!(position_at(&route, 2000, TELEPORT_SPEED), Some((10.0, 0.0, 20.0)));
+        assert_eq!(position_at(&route, 2400, TELEPORT_SPEED), Some((10.0, 0.0, 20.0)));
+        assert_eq!(position_at(&route, 2600, TELEPORT_SPEED), Some((5000.0, 0.0, 20.0)));
+        assert_eq!(position_at(&route, 999, TELEPORT_SPEED), None);
+        assert_eq!(position_at(&route, 3001, TELEPORT_SPEED), None);
+        assert_eq!(position_at(&[], 0, TELEPORT_SPEED), None);
+
+        // A long move over a long gap is walked, not a teleport (same rule as split_at_teleports)
+        let slow = vec![point(0.0, 0.0, 0), point(300.0, 0.0, 10_000)];
+        assert_eq!(position_at(&slow, 5000, TELEPORT_SPEED), Some((150.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn test_speed_by_map() {
+        let (a, b) = (0x3C282300, 0x3C292300);
+        let on_tile = |map_id: u32, global_x: f32, timestamp_ms: u64| RoutePoint { map_id, ..point(global_x, 0.0, timestamp_ms) };
+        let route = vec![
+            // 20 units in 2s on A
+            on_tile(a, 0.0, 0),
+            on_tile(a, 10.0, 1000),
+            on_tile(a, 20.0, 2000),
+            // Teleport to B, then 30 units in 4s
+            on_tile(b, 5000.0, 2000),
+            on_tile(b, 5010.0, 4000),
+            on_tile(b, 5030.0, 6000),
+        ];
+
+        let speeds = speed_by_map(&route, TELEPORT_SPEED);
+        assert_eq!(speeds.len(), 2);
+        assert_eq!(speeds[&a], 10.0);
+        assert_eq!(speeds[&b], 7.5);
+        assert!(speed_by_map(&route[..1], TELEPORT_SPEED).is_empty());
+    }
+
+    #[test]
+    fn test_recent_speed_skips_teleports() {
+        let route = vec![
+            point(0.0, 0.0, 0),
+            point(10.0, 0.0, 1000),
+            // Same global map, but 5000 units in 1s
+            point(5010.0, 0.0, 2000),
+            point(5030.0, 0.0, 3000),
+        ];
+
+        // 30 units walked in the 2s outside the teleport
+        assert_eq!(recent_speed(&route, 4, TELEPORT_SPEED), Some(15.0));
+        assert_eq!(recent_speed(&route, 2, TELEPORT_SPEED), Some(20.0));
+        assert_eq!(recent_speed(&route[1..3], 2, TELEPORT_SPEED), None);
+        assert_eq!(recent_speed(&route[..1], 4, TELEPORT_SPEED), None);
+    }
+
+    #[test]
+    fn test_nearest_route_point() {
+        let route: Vec<RoutePoint> = (0..5).map(|i| point(i as f32 * 100.0, 0.0, i * 1000)).collect();
+
+        // 3 units beside and 4 above point 2
+        let (index, distance) = nearest_route_point(&route, 200.0, 4.0, 3.0).unwrap();
+        assert_eq!(index, 2);
+        assert!((distance - 5.0).abs() < 1e-4);
+
+        assert_eq!(nearest_route_point(&[], 0.0, 0.0, 0.0), None);
+    }
+
+    #[test]
+    fn test_decimate_route() {
+        let route: Vec<RoutePoint> = (0..10).map(|i| point(i as f32, 0.0, i)).collect();
+        let deaths = [DeathEvent::at(&route[4], 1)];
+        let waypoints = RouteWaypoints { graces_visited: &[], deaths: &deaths };
+        let kept = |route: &[RoutePoint]| {
+            decimate_route(route, &waypoints, 3).points.iter().map(|p| p.timestamp_ms).collect::<Vec<_>>()
+        };
+
+        assert_eq!(kept(&route), vec![0, 3, 6, 9]);
+        let clip = decimate_route(&route, &waypoints, 4);
+        assert_eq!(clip.points.iter().map(|p| p.timestamp_ms).collect::<Vec<_>>(), vec![0, 4, 8, 9]);
+        assert_eq!(clip.deaths, deaths);
+        assert_eq!(decimate_route(&route, &waypoints, 1).points.len(), 10);
+
+        // Both sides of a tile change survive
+        let mut moved = route.clone();
+        for point in &mut moved[5..] {
+            point.map_id = 0x3C292300;
+        }
+        assert_eq!(kept(&moved), vec![0, 3, 4, 5, 6, 9]);
+    }
+
+    #[test]
+    fn test_route_fingerprint() {
+        let route: Vec<RoutePoint> = (0..20).map(|i| point(i as f32 * 3.0, 1.0, i * 100)).collect();
+
+        // Same path recorded at another time
+        let replay: Vec<RoutePoint> = route
+            .iter()
+            .map(|p| RoutePoint { timestamp_ms: p.timestamp_ms + 60_000, epoch_ms: 123, ..p.clone() })
+            .collect();
+        assert_eq!(route_fingerprint(&route), route_fingerprint(&replay));
+
+        let mut moved = route.clone();
+        moved[10].global_z += 0.5;
+        assert_ne!(route_fingerprint(&route), route_fingerprint(&moved));
+
+        let mut other_tile = route.clone();
+        other_tile[0].map_id = 0x3C292300;
+        assert_ne!(route_fingerprint(&route), route_fingerprint(&other_tile));
+    }
+
+    #[test]
+    fn test_detect_revisits() {
+        // Out along X, up along Z, then back towards the start
+        let mut route: Vec<RoutePoint> = (0..5).map(|i| point(i as f32 * 10.0, 0.0, i * 1000)).collect();
+        route.push(point(40.0, 10.0, 5000));
+        route.push(point(20.0, 10.0, 6000));
+        route.push(point(1.0, 2.0, 7000));
+
+        // Only the return next to the start, not the neighbours along the way
+        assert_eq!(detect_revisits(&route, 5.0, 3), vec![(0, 7)]);
+
+        // A larger radius also catches the pass beside point 2
+        assert_eq!(detect_revisits(&route, 12.0, 3), vec![(0, 7), (1, 7), (2, 6)]);
+
+        // Same place on another global map is not a revisit
+        route[7].global_map_id = 61;
+        assert!(detect_revisits(&route, 5.0, 3).is_empty());
+    }
+}
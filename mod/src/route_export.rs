@@ -0,0 +1,301 @@
+// Route export formats for external viewers
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::route::{split_at_teleports, RoutePoint, RouteWaypoints};
+
+// =============================================================================
+// AXIS MAPPING
+// =============================================================================
+
+/// How game axes map to (east, north, up) in exported files
+///
+/// In TOML: `axis_mapping = "east_south_up"` for a preset, or
+/// `axis_mapping = { custom = [[1, 0, 0], [0, 0, 1], [0, 1, 0]] }` for a matrix.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AxisMapping {
+    /// X east, Z north, Y up (default)
+    #[default]
+    EastNorthUp,
+    /// X east, Z south (flipped), Y up
+    EastSouthUp,
+    /// Z east, X north, Y up
+    NorthEastUp,
+    /// Rows give east, north and up as combinations of the game (x, y, z)
+    Custom([[f32; 3]; 3]),
+}
+
+impl AxisMapping {
+    /// Map global game coordinates to (east, north, up)
+    pub fn apply(&self, x: f32, y: f32, z: f32) -> (f64, f64, f64) {
+        let (x, y, z) = (x as f64, y as f64, z as f64);
+        match self {
+            AxisMapping::EastNorthUp => (x, z, y),
+            AxisMapping::EastSouthUp => (x, -z, y),
+            AxisMapping::NorthEastUp => (z, x, y),
+            AxisMapping::Custom(matrix) => {
+                let row = |r: [f32; 3]| r[0] as f64 * x + r[1] as f64 * y + r[2] as f64 * z;
+                (row(matrix[0]), row(matrix[1]), row(matrix[2]))
+            }
+        }
+    }
+}
+
+// =============================================================================
+// KML EXPORT
+// =============================================================================
+
+/// Game units per degree when mapping global coordinates onto lon/lat
+/// (one game unit is treated as one metre at the equator)
+const KML_UNITS_PER_DEGREE: f64 = 111_320.0;
+
+/// Format a point as a KML "lon,lat,alt" tuple
+///
+/// With the default axis mapping:
+/// - longitude = global_x / 111320 (east)
+/// - latitude  = global_z / 111320 (north)
+/// - altitude  = global_y (metres)
+fn kml_coordinate(global: (f32, f32, f32), axes: &AxisMapping) -> String {
+    let (east, north, up) = axes.apply(global.0, global.1, global.2);
+    format!(
+        "{:.8},{:.8},{:.2}",
+        east / KML_UNITS_PER_DEGREE,
+        north / KML_UNITS_PER_DEGREE,
+        up
+    )
+}
+
+/// Global position of a route point
+fn global_position(point: &RoutePoint) -> (f32, f32, f32) {
+    (point.global_x, point.global_y, point.global_z)
+}
+
+/// Escape text for an XML element
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Write a `<Placemark>` with a single `<Point>`
+fn write_point_placemark<W: Write>(
+    writer: &mut W,
+    name: &str,
+    global: (f32, f32, f32),
+    axes: &AxisMapping,
+) -> io::Result<()> {
+    writeln!(writer, "    <Placemark>")?;
+    writeln!(writer, "      <name>{}</name>", xml_escape(name))?;
+    writeln!(writer, "      <Point>")?;
+    writeln!(writer, "        <altitudeMode>absolute</altitudeMode>")?;
+    writeln!(writer, "        <coordinates>{}</coordinates>", kml_coordinate(global, axes))?;
+    writeln!(writer, "      </Point>")?;
+    writeln!(writer, "    </Placemark>")
+}
+
+/// Export a route as KML (for Google Earth)
+///
+/// Each continuous segment becomes a `<Placemark>` with its own `<LineString>`,
+/// so teleports are not drawn as lines. Segments made of a single point are
+/// written as a `<Point>` placemark instead. Every grace visited and every death
+/// in `waypoints` follows as a `<Point>` placemark; graces have no position of
+/// their own, so they sit on the first route point snapped to them. Coordinates
+/// follow `axes`.
+pub fn export_kml<W: Write>(
+    route: &[RoutePoint],
+    waypoints: &RouteWaypoints,
+    axes: &AxisMapping,
+    teleport_speed: f32,
+    writer: &mut W,
+) -> io::Result<()> {
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, r#"<kml xmlns="http://www.opengis.net/kml/2.2">"#)?;
+    writeln!(writer, "  <Document>")?;
+    writeln!(writer, "    <name>Elden Ring Route</name>")?;
+    
+    for (index, segment) in split_at_teleports(route, teleport_speed).iter().enumerate() {
+        let first = &segment[0];
+        let name = format!("Segment {} ({})", index + 1, first.map_id_str);
+        if segment.len() == 1 {
+            write_point_placemark(writer, &name, global_position(first), axes)?;
+            continue;
+        }
+        
+        writeln!(writer, "    <Placemark>")?;
+        writeln!(writer, "      <name>{}</name>", name)?;
+        writeln!(writer, "      <LineString>")?;
+        writeln!(writer, "        <altitudeMode>absolute</altitudeMode>")?;
+        writeln!(writer, "        <coordinates>")?;
+        for point in segment.iter() {
+            writeln!(writer, "          {}", kml_coordinate(global_position(point), axes))?;
+        }
+        writeln!(writer, "        </coordinates>")?;
+        writeln!(writer, "      </LineString>")?;
+        writeln!(writer, "    </Placemark>")?;
+    }
+    
+    for visit in waypoints.graces_visited {
+        let index = route.partition_point(|point| point.timestamp_ms < visit.timestamp_ms);
+        let Some(point) = route.get(index) else {
+            continue;
+        };
+        let name = if visit.name.is_empty() { "Site of Grace" } else { &visit.name };
+        write_point_placemark(writer, name, global_position(point), axes)?;
+    }
+    
+    for death in waypoints.deaths {
+        let name = format!("Death {} ({})", death.death_count, death.map_id_str);
+        write_point_placemark(writer, &name, (death.global_x, death.global_y, death.global_z), axes)?;
+    }
+    
+    writeln!(writer, "  </Document>")?;
+    writeln!(writer, "</kml>")?;
+    Ok(())
+}
+
+// =============================================================================
+// HEATMAP
+// =============================================================================
+
+/// Bin a route into a 2D grid of east/north cells with the number of points in each
+///
+/// Cell `(i, j)` covers `[i * cell_size, (i + 1) * cell_size)` on the east axis and the
+/// same on north, as given by `axes` (global X/Z with the default mapping).
+/// Returns an empty grid if `cell_size` is not positive.
+pub fn to_heatmap(route: &[RoutePoint], cell_size: f32, axes: &AxisMapping) -> HashMap<(i32, i32), u32> {
+    let mut grid = HashMap::new();
+    if cell_size <= 0.0 {
+        return grid;
+    }
+    
+    let cell_size = cell_size as f64;
+    for point in route {
+        let (east, north, _) = axes.apply(point.global_x, point.global_y, point.global_z);
+        let cell = (
+            (east / cell_size).floor() as i32,
+            (north / cell_size).floor() as i32,
+        );
+        *grid.entry(cell).or_insert(0) += 1;
+    }
+    grid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::route::{test_point, DeathEvent, GraceVisit, TELEPORT_SPEED};
+
+    fn point(global_x: f32, global_z: f32) -> RoutePoint {
+        RoutePoint { global_x, global_y: 10.0, global_z, ..test_point(0) }
+    }
+
+    #[test]
+    fn test_export_kml_splits_teleports() {
+        let route: Vec<RoutePoint> = [
+            (10000.0, 9000.0),
+            (10010.0, 9000.0),
+            (10020.0, 9000.0),
+            // Teleport
+            (12000.0, 11000.0),
+            (12005.0, 11000.0),
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, &(x, z))| RoutePoint { timestamp_ms: i as u64 * 100, ..point(x, z) })
+        .collect();
+
+        let mut output = Vec::new();
+        export_kml(&route, &RouteWaypoints::default(), &AxisMapping::default(), TELEPORT_SPEED, &mut output).unwrap();
+        let kml = String::from_utf8(output).unwrap();
+
+        assert_eq!(kml.matches("<LineString>").count(), 2);
+
+        let tuple_counts: Vec<usize> = kml
+            .split("<coordinates>")
+            .skip(1)
+            .map(|s| s.split("</coordinates>").next().unwrap().split_whitespace().count())
+            .collect();
+        assert_eq!(tuple_counts, vec![3, 2]);
+    }
+
+    #[test]
+    fn test_export_kml_waypoints() {
+        let route: Vec<RoutePoint> = (0..3)
+            .map(|i| RoutePoint { timestamp_ms: i * 1_000_000, ..point(111320.0 * i as f32, 0.0) })
+            .collect();
+        let graces = [GraceVisit { id: 1, name: "Church of Elleh".to_string(), timestamp_ms: 1_000_000 }];
+        let deaths = [DeathEvent {
+            global_x: 0.0,
+            global_y: 5.0,
+            global_z: 111320.0,
+            map_id: 0x3C282300,
+            map_id_str: "m60_40_35_00".to_string(),
+            global_map_id: 60,
+            timestamp_ms: 2_500_000,
+            death_count: 3,
+        }];
+        let waypoints = RouteWaypoints { graces_visited: &graces, deaths: &deaths };
+
+        let mut output = Vec::new();
+        export_kml(&route, &waypoints, &AxisMapping::default(), TELEPORT_SPEED, &mut output).unwrap();
+        let kml = String::from_utf8(output).unwrap();
+
+        assert_eq!(kml.matches("<LineString>").count(), 1);
+        assert_eq!(kml.matches("<Point>").count(), 2);
+        // The grace sits on the route point at its timestamp, the death on its own position
+        assert!(kml.contains(
+            "<name>Church of Elleh</name>\n      <Point>\n        <altitudeMode>absolute</altitudeMode>\n        \
+             <coordinates>1.00000000,0.00000000,10.00</coordinates>"
+        ));
+        assert!(kml.contains(
+            "<name>Death 3 (m60_40_35_00)</name>\n      <Point>\n        <altitudeMode>absolute</altitudeMode>\n        \
+             <coordinates>0.00000000,1.00000000,5.00</coordinates>"
+        ));
+    }
+
+    #[test]
+    fn test_axis_mapping() {
+        let route = vec![point(111320.0, 222640.0)];
+        let coordinates = |axes: AxisMapping| {
+            let mut output = Vec::new();
+            export_kml(&route, &RouteWaypoints::default(), &axes, TELEPORT_SPEED, &mut output).unwrap();
+            let kml = String::from_utf8(output).unwrap();
+            kml.split("<coordinates>").nth(1).unwrap().split("</coordinates>").next().unwrap().to_string()
+        };
+
+        assert_eq!(coordinates(AxisMapping::EastNorthUp), "1.00000000,2.00000000,10.00");
+        assert_eq!(coordinates(AxisMapping::EastSouthUp), "1.00000000,-2.00000000,10.00");
+        // Y as latitude, Z as altitude
+        let custom = AxisMapping::Custom([[1.0, 0.0, 0.0], [0.0, 111320.0, 0.0], [0.0, 0.0, 1.0]]);
+        assert_eq!(coordinates(custom), "1.00000000,10.00000000,222640.00");
+    }
+
+    #[test]
+    fn test_to_heatmap() {
+        let route = vec![
+            point(10001.0, 9001.0),
+            point(10005.0, 9009.0),
+            point(10009.5, 9000.0),
+            point(10011.0, 9000.0),
+            point(-5.0, 3.0),
+        ];
+
+        let heatmap = to_heatmap(&route, 10.0, &AxisMapping::default());
+        assert_eq!(heatmap.len(), 3);
+        assert_eq!(heatmap[&(1000, 900)], 3);
+        assert_eq!(heatmap[&(1001, 900)], 1);
+        assert_eq!(heatmap[&(-1, 0)], 1);
+
+        // Z flipped: north cells are mirrored
+        let heatmap = to_heatmap(&route, 10.0, &AxisMapping::EastSouthUp);
+        assert_eq!(heatmap.len(), 4);
+        assert_eq!(heatmap[&(1000, -901)], 2);
+        assert_eq!(heatmap[&(1000, -900)], 1);
+        assert_eq!(heatmap[&(1001, -900)], 1);
+        assert_eq!(heatmap[&(-1, -1)], 1);
+
+        assert!(to_heatmap(&route, 0.0, &AxisMapping::default()).is_empty());
+    }
+}
@@ -1,24 +1,178 @@
 // Route Tracker - Main tracking logic
 
+use std::borrow::Cow;
+use std::collections::HashSet;
 use std::path::PathBuf;
-use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use hudhook::tracing::{info, warn};
+use hudhook::tracing::{debug, info, warn};
 use libeldenring::prelude::*;
 use windows::Win32::Foundation::HINSTANCE;
 
-use crate::config::Config;
-use crate::coordinate_transformer::WorldPositionTransformer;
+use crate::config::{Config, PositionSource, RealtimeTransport, RecordMode};
+use crate::coordinate_transformer::{TransformError, WorldPositionTransformer, DEFAULT_GLOBAL_AREAS};
+use crate::map_icons::{self, OutputMapIcon};
+use crate::position_pump::{ConvertedSample, PositionPump, RawSample, SampleTarget};
 use crate::realtime_client::RealtimeClient;
-use crate::route::{save_route_to_file, RoutePoint};
+use crate::route_analysis;
+use crate::route_export;
+use crate::route::{epoch_ms_now, generate_timestamp, global_map_label, iso8601_now, round_coordinates, route_to_json_string, DeathEvent, GraceVisit, save_route_to_file, MapTransition, PointAccuracy, RouteFileSplit, RouteMetadata, RoutePoint, RouteWaypoints};
+
+// =============================================================================
+// POSITION SOURCES
+// =============================================================================
+
+/// Something a local position and map tile can be read from
+/// 
+/// The tracker reads all its positions through this trait, so that tests and
+/// replays can feed samples without a running game.
+pub trait PositionProvider: Send + Sync {
+    /// Read the local position [x, y, z] and map_id (None if unavailable)
+    fn read_position(&self) -> Option<([f32; 3], u32)>;
+    
+    /// Whether the player is in gameplay (None if unknown)
+    fn in_gameplay(&self) -> Option<bool> {
+        None
+    }
+    
+    /// Player orientation as a quaternion `[x, y, z, w]` (None if unavailable)
+    fn read_orientation(&self) -> Option<[f32; 4]> {
+        None
+    }
+    
+    /// Number of times the player died (None if unavailable)
+    fn read_death_count(&self) -> Option<u32> {
+        None
+    }
+    
+    /// The game's internal frame counter (None if unavailable)
+    fn read_frame_counter(&self) -> Option<u64> {
+        None
+    }
+    
+    /// The full position array as read from memory, unknown components included
+    /// (None if unavailable)
+    fn read_raw_position(&self) -> Option<[f32; 5]> {
+        None
+    }
+}
+
+impl PositionProvider for Position {
+    fn read_position(&self) -> Option<([f32; 3], u32)> {
+        read_consistent(|| self.read_map_id(), || self.read())
+    }
+    
+    fn read_raw_position(&self) -> Option<[f32; 5]> {
+        self.read()
+    }
+}
+
+/// Read the position between two reads of the map id, dropping the sample on a tile change
+/// 
+/// The position and map id are separate reads: during a tile transition the position may
+/// already be local to the new tile while the map id is still the old one (or the reverse),
+/// which would convert it with the wrong anchor.
+pub(crate) fn read_consistent(
+    read_map_id: impl Fn() -> Option<u32>,
+    read_position: impl Fn() -> Option<[f32; 5]>,
+) -> Option<([f32; 3], u32)> {
+    let map_id = read_map_id()?;
+    let [x, y, z, _, _] = read_position()?;
+    (read_map_id()? == map_id).then_some(([x, y, z], map_id))
+}
+
+/// Live positions read from game memory
+pub struct PointersProvider {
+    pointers: Pointers,
+    source: PositionSource,
+}
+
+impl PointersProvider {
+    pub fn new(pointers: Pointers, source: PositionSource) -> Self {
+        Self { pointers, source }
+    }
+}
+
+impl PositionProvider for PointersProvider {
+    fn read_position(&self) -> Option<([f32; 3], u32)> {
+        match self.source {
+            PositionSource::Player => self.pointers.global_position.read_position(),
+        }
+    }
+    
+    /// Uses the same menu timer as the startup wait (positive once a game is loaded)
+    fn in_gameplay(&self) -> Option<bool> {
+        self.pointers.menu_timer.read().map(|menu_timer| menu_timer > 0.)
+    }
+    
+    /// Always the player position, whatever `position_source` is
+    fn read_raw_position(&self) -> Option<[f32; 5]> {
+        self.pointers.global_position.read_raw_position()
+    }
+    
+    // No orientation: libeldenring's Pointers only exposes the position (and yaw)
+    // No death count either: deaths are only detected with providers that read one
+    // No frame counter: points are only tagged with one by providers that read it
+}
+
+/// No game at all: every read is unavailable
+/// 
+/// Backs a tracker in headless runs (replays, streaming tests) where nothing is
+/// recorded from memory.
+pub struct NullProvider;
+
+impl PositionProvider for NullProvider {
+    fn read_position(&self) -> Option<([f32; 3], u32)> {
+        None
+    }
+}
+
+/// Scripted positions for tests and replays
+/// 
+/// Each sample is returned once, in order; reads past the end return None.
+pub struct VecProvider {
+    samples: Vec<([f32; 3], u32)>,
+    next: AtomicUsize,
+}
+
+impl VecProvider {
+    pub fn new(samples: Vec<([f32; 3], u32)>) -> Self {
+        Self {
+            samples,
+            next: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl PositionProvider for VecProvider {
+    fn read_position(&self) -> Option<([f32; 3], u32)> {
+        let index = self.next.fetch_add(1, Ordering::Relaxed);
+        self.samples.get(index).copied()
+    }
+}
 
 // =============================================================================
 // ROUTE TRACKER
 // =============================================================================
 
+/// Longest wait for the realtime client to send queued points in `finalize`
+const FINALIZE_FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Longest wait for the position pump to convert the samples already taken
+const PUMP_FLUSH_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Stream interval multiplier while the realtime backlog is above `throttle_queue_depth`
+const STREAM_THROTTLE_FACTOR: u32 = 4;
+
+/// Recorded points used to estimate the speed in adaptive record mode
+const ADAPTIVE_SPEED_SAMPLES: usize = 5;
+
 /// Route tracking state
 pub struct RouteTracker {
-    pub(crate) pointers: Pointers,
+    /// Where positions are read from (game memory, or scripted samples)
+    pub(crate) provider: Box<dyn PositionProvider>,
     pub(crate) route: Vec<RoutePoint>,
     pub(crate) is_recording: bool,
     pub(crate) is_streaming: bool,
@@ -33,9 +187,27 @@ pub struct RouteTracker {
     pub(crate) config: Config,
     pub(crate) base_dir: PathBuf,
     pub(crate) status_message: Option<(String, Instant)>,
-    pub(crate) transformer: WorldPositionTransformer,
+    pub(crate) transformer: Arc<WorldPositionTransformer>,
+    /// Converts the samples taken by `tick` off the hook thread
+    pub(crate) pump: PositionPump,
+    /// Description of the loaded coordinate dataset (None if it failed to load)
+    pub(crate) dataset_version: Option<String>,
     /// Real-time streaming client (None if disabled)
     pub(crate) realtime_client: Option<RealtimeClient>,
+    /// Last point sent to the backend (for map transition detection)
+    pub(crate) last_streamed_point: Option<RoutePoint>,
+    /// Streaming slowed down because the realtime client is behind
+    pub(crate) stream_throttled: bool,
+    /// Sites of Grace used for `snap_to_bonfires_radius`
+    pub(crate) bonfires: Vec<OutputMapIcon>,
+    /// Sites of Grace passed by during the recording, in order
+    pub(crate) graces_visited: Vec<GraceVisit>,
+    /// Last death counter value read (baseline for death detection)
+    pub(crate) last_death_count: Option<u32>,
+    /// Deaths during the recording, in order
+    pub(crate) deaths: Vec<DeathEvent>,
+    /// Tiles whose conversion failure was already logged this session
+    pub(crate) warned_tiles: HashSet<u32>,
 }
 
 impl RouteTracker {
@@ -68,12 +240,62 @@ impl RouteTracker {
         let base_dir = Config::get_dll_directory(hmodule)
             .unwrap_or_else(|| PathBuf::from("."));
         
-        // Load coordinate transformer CSV
-        let csv_path = base_dir.join("WorldMapLegacyConvParam.csv");
-        let transformer = match WorldPositionTransformer::from_csv(&csv_path) {
+        let provider = PointersProvider::new(Pointers::new(), config.recording.position_source);
+        
+        // Wait for the game to be loaded
+        let poll_interval = Duration::from_millis(100);
+        while provider.in_gameplay() != Some(true) {
+            std::thread::sleep(poll_interval);
+        }
+        
+        let tracker = Self::with_config_and_provider(config, base_dir, Box::new(provider));
+        
+        info!("Route Tracker initialized!");
+        
+        Some(tracker)
+    }
+    
+    /// Create a RouteTracker from an already loaded config and game pointers
+    /// 
+    /// Unlike `new`, this neither reads the config file nor waits for the game
+    /// to be loaded; both are left to the caller. Data files (coordinate CSV,
+    /// processed map data) are still read from `base_dir`.
+    pub fn with_config(config: Config, base_dir: PathBuf, pointers: Pointers) -> Self {
+        let provider = PointersProvider::new(pointers, config.recording.position_source);
+        Self::with_config_and_provider(config, base_dir, Box::new(provider))
+    }
+    
+    /// Same as `with_config`, reading positions from `provider` instead of game memory
+    /// 
+    /// Every game read goes through the provider, so with a `VecProvider` or
+    /// `NullProvider` this runs without the game (headless tests and tools).
+    pub fn with_config_and_provider(
+        config: Config,
+        base_dir: PathBuf,
+        provider: Box<dyn PositionProvider>,
+    ) -> Self {
+        // Load coordinate transformer CSV (or its gzip-compressed version)
+        let mut csv_path = base_dir.join("WorldMapLegacyConvParam.csv");
+        if !csv_path.exists() && base_dir.join("WorldMapLegacyConvParam.csv.gz").exists() {
+            csv_path = base_dir.join("WorldMapLegacyConvParam.csv.gz");
+        }
+        let mut dataset_version = None;
+        let borrow_neighbor_anchors = config.conversion.borrow_neighbor_anchors;
+        let allowed_global_maps = config.conversion.allowed_global_maps.clone();
+        let transformer = match WorldPositionTransformer::from_csv_with_options(
+            &csv_path,
+            DEFAULT_GLOBAL_AREAS,
+            config.conversion.parallel_precompute,
+        ) {
             Ok(t) => {
                 info!("Loaded coordinate transformer: {} maps, {} anchors",
                     t.map_count(), t.anchor_count());
+                dataset_version = Some(format!(
+                    "{} ({} maps, {} anchors)",
+                    csv_path.file_name().unwrap_or_default().to_string_lossy(),
+                    t.map_count(),
+                    t.anchor_count()
+                ));
                 t
             }
             Err(e) => {
@@ -85,48 +307,90 @@ impl RouteTracker {
                     WorldPositionTransformer::empty()
                 })
             }
-        };
+        }
+        .with_neighbor_fallback(borrow_neighbor_anchors)
+        .with_allowed_global_maps(&allowed_global_maps);
         
-        let pointers = Pointers::new();
+        let snap_to_bonfires = config.recording.snap_to_bonfires_radius > 0.0;
+        let mut tracker = Self::with_provider(config, base_dir, transformer, provider);
+        tracker.dataset_version = dataset_version;
         
-        // Wait for the game to be loaded
-        let poll_interval = Duration::from_millis(100);
-        loop {
-            if let Some(menu_timer) = pointers.menu_timer.read() {
-                if menu_timer > 0. {
-                    break;
-                }
+        // Load Sites of Grace for snapping
+        if snap_to_bonfires {
+            let map_data_path = tracker.base_dir.join("map_data_processed.json");
+            match map_icons::load_map_data(&map_data_path) {
+                Ok(map_data) => tracker.attach_bonfires(map_data.bonfires),
+                Err(e) => warn!("Failed to load bonfires from {:?}: {}. Grace snapping disabled.", map_data_path, e),
             }
-            std::thread::sleep(poll_interval);
         }
         
-        info!("Route Tracker initialized!");
-        
+        tracker
+    }
+    
+    /// Create a RouteTracker reading its positions from `provider`
+    /// 
+    /// Does not touch the game or read any file: used by `with_config` once the
+    /// data files are loaded, and by tests and replays with scripted positions.
+    pub fn with_provider(
+        config: Config,
+        base_dir: PathBuf,
+        transformer: WorldPositionTransformer,
+        provider: Box<dyn PositionProvider>,
+    ) -> Self {
         let record_interval = Duration::from_millis(config.recording.record_interval_ms);
+        let transformer = Arc::new(transformer);
+        let pump = PositionPump::new(Arc::clone(&transformer));
         
         // Initialize real-time client if enabled
         let realtime_client = if config.realtime.enabled {
-            if let Some(ref push_key) = config.realtime.push_key {
-                if !push_key.is_empty() {
+            // Push key to send (None without authentication), or why streaming can't start
+            let push_key = match config.realtime.push_key.as_deref() {
+                _ if !config.realtime.auth_required => Ok(None),
+                Some("") => Err("push_key is empty"),
+                Some(push_key) => Ok(Some(push_key.to_string())),
+                None => Err("push_key is not set"),
+            };
+            
+            match push_key {
+                Ok(push_key) => {
                     info!("Real-time streaming enabled: backend={}", config.realtime.backend_url);
-                    Some(RealtimeClient::new(
-                        config.realtime.backend_url.clone(),
-                        push_key.clone(),
-                    ))
-                } else {
-                    warn!("Real-time streaming enabled but push_key is empty. Disabling.");
+                    let max_retry_after = Duration::from_secs(config.realtime.max_retry_after_secs);
+                    Some(match &config.realtime.transport {
+                        RealtimeTransport::Batch => RealtimeClient::new(
+                            config.realtime.backend_url.clone(),
+                            push_key,
+                            config.realtime.auth_header,
+                            config.realtime.delta_encoding,
+                            config.realtime.payload_format,
+                            max_retry_after,
+                            None,
+                        ),
+                        RealtimeTransport::HttpStream => RealtimeClient::new_http_stream(
+                            config.realtime.backend_url.clone(),
+                            push_key,
+                            config.realtime.auth_header,
+                            max_retry_after,
+                        ),
+                        RealtimeTransport::Ipc { path } => RealtimeClient::new_ipc(
+                            PathBuf::from(path),
+                            config.realtime.backend_url.clone(),
+                            push_key,
+                            config.realtime.auth_header,
+                            max_retry_after,
+                        ),
+                    })
+                }
+                Err(reason) => {
+                    warn!("Real-time streaming enabled but {}. Disabling.", reason);
                     None
                 }
-            } else {
-                warn!("Real-time streaming enabled but push_key is not set. Disabling.");
-                None
             }
         } else {
             None
         };
         
-        Some(Self {
-            pointers,
+        Self {
+            provider,
             route: Vec::new(),
             is_recording: false,
             is_streaming: false,
@@ -140,174 +404,636 @@ impl RouteTracker {
             base_dir,
             status_message: None,
             transformer,
+            pump,
+            dataset_version: None,
             realtime_client,
-        })
+            last_streamed_point: None,
+            stream_throttled: false,
+            bonfires: Vec::new(),
+            graces_visited: Vec::new(),
+            last_death_count: None,
+            deaths: Vec::new(),
+            warned_tiles: HashSet::new(),
+        }
+    }
+    
+    /// Set the Sites of Grace that recorded points are snapped to
+    pub fn attach_bonfires(&mut self, icons: Vec<OutputMapIcon>) {
+        info!("Attached {} bonfires for grace snapping", icons.len());
+        self.bonfires = icons;
+    }
+    
+    /// Record the Site of Grace near a point, if any (consecutive visits are merged)
+    fn snap_to_bonfire(&mut self, point: &RoutePoint) {
+        let radius = self.config.recording.snap_to_bonfires_radius;
+        if radius <= 0.0 {
+            return;
+        }
+        
+        let position = (point.global_x, point.global_y, point.global_z);
+        let Some(bonfire) = map_icons::nearest_icon(&self.bonfires, point.global_map_id, position, radius) else {
+            return;
+        };
+        
+        if self.graces_visited.last().is_some_and(|visit| visit.id == bonfire.id) {
+            return;
+        }
+        
+        self.graces_visited.push(GraceVisit {
+            id: bonfire.id,
+            name: bonfire.primary_text().unwrap_or_default().to_string(),
+            timestamp_ms: point.timestamp_ms,
+        });
+    }
+    
+    /// Name of the last visited Site of Grace and the global offset of `point` from it
+    /// 
+    /// None before any grace was visited, or once the point is on another global map.
+    fn offset_from_last_grace(&self, point: &RoutePoint) -> Option<(String, f32, f32, f32)> {
+        let visit = self.graces_visited.last()?;
+        let bonfire = self.bonfires.iter()
+            .find(|icon| icon.id == visit.id && icon.is_on_global_map(point.global_map_id))?;
+        Some((
+            visit.name.clone(),
+            point.global_x - bonfire.global_x,
+            point.global_y - bonfire.global_y,
+            point.global_z - bonfire.global_z,
+        ))
     }
     
     /// Start recording
     pub fn start_recording(&mut self) {
         self.route.clear();
+        self.graces_visited.clear();
+        self.deaths.clear();
+        self.warned_tiles.clear();
         self.recording_start_time = Some(Instant::now());
         self.is_recording = true;
         info!("Recording started!");
     }
     
     /// Stop recording
+    /// 
+    /// With `recording.save_on_stop`, a non-empty route is saved and queued
+    /// realtime points are flushed.
     pub fn stop_recording(&mut self) {
+        self.flush_pump();
         self.is_recording = false;
         info!("Recording stopped! {} points recorded.", self.route.len());
+        
+        if self.config.recording.save_on_stop && !self.route.is_empty() {
+            if let Err(e) = self.save_route() {
+                warn!("Failed to save route on stop: {}", e);
+            }
+            if let Some(ref client) = self.realtime_client {
+                client.flush();
+            }
+        }
+    }
+    
+    /// End the run: stop recording and streaming, flush the realtime client, then save
+    /// 
+    /// Waits up to `FINALIZE_FLUSH_TIMEOUT` for queued points to be sent (a timeout is
+    /// logged, the route is saved anyway). Returns the saved file paths.
+    pub fn finalize(&mut self) -> Result<Vec<PathBuf>, String> {
+        self.flush_pump();
+        if self.is_recording {
+            self.is_recording = false;
+            info!("Recording stopped! {} points recorded.", self.route.len());
+        }
+        if self.is_streaming {
+            self.stop_streaming();
+        }
+        
+        if let Some(ref client) = self.realtime_client {
+            if !client.flush_and_wait(FINALIZE_FLUSH_TIMEOUT) {
+                warn!("Realtime points not all sent after {}s", FINALIZE_FLUSH_TIMEOUT.as_secs());
+            }
+        }
+        
+        self.save_route()
+    }
+    
+    /// Discard the recorded route without touching the streaming session
+    ///
+    /// If recording is active it keeps going from a fresh start time.
+    pub fn clear_route(&mut self) {
+        self.route.clear();
+        self.graces_visited.clear();
+        self.deaths.clear();
+        self.recording_start_time = self.is_recording.then(Instant::now);
+        self.last_record_time = Instant::now();
+        info!("Route cleared!");
     }
     
     /// Start streaming
     pub fn start_streaming(&mut self) {
         self.stream_start_time = Some(Instant::now());
+        self.last_streamed_point = None;
         self.is_streaming = true;
         info!("Streaming started!");
     }
     
     /// Stop streaming
     pub fn stop_streaming(&mut self) {
+        self.flush_pump();
         self.is_streaming = false;
         info!("Streaming stopped!");
     }
     
-    /// Record current position if the interval has elapsed
-    pub fn record_position(&mut self) {
-        if !self.is_recording {
+    /// Duration of the active recording session (None when not recording)
+    pub fn elapsed(&self) -> Option<Duration> {
+        Self::session_elapsed(self.is_recording, self.recording_start_time)
+    }
+    
+    /// Duration of the active streaming session (None when not streaming)
+    pub fn stream_elapsed(&self) -> Option<Duration> {
+        Self::session_elapsed(self.is_streaming, self.stream_start_time)
+    }
+    
+    /// Global map of the latest point (None before any point)
+    /// 
+    /// Uses the last recorded point, or the last streamed one when not recording.
+    pub fn last_global_map_id(&self) -> Option<u8> {
+        self.route
+            .last()
+            .or(self.last_streamed_point.as_ref())
+            .map(|point| point.global_map_id)
+    }
+    
+    /// Whether the latest point is on the Shadow Realm map (None before any point)
+    pub fn is_in_dlc(&self) -> Option<bool> {
+        self.last_global_map_id().map(|global_map_id| global_map_id == 61)
+    }
+    
+    /// Time since `start_time`, only while the session is active
+    pub(crate) fn session_elapsed(active: bool, start_time: Option<Instant>) -> Option<Duration> {
+        start_time.filter(|_| active).map(|start| start.elapsed())
+    }
+    
+    /// Read the position once for both recording and streaming, converting it off this thread
+    /// 
+    /// Called every frame from the hook. Each side keeps its own interval: the
+    /// sample is only taken when recording and/or streaming is due, then handed to
+    /// the position pump. Points converted since the previous tick are recorded and
+    /// streamed first, so a sample taken for both streams exactly the recorded point
+    /// (or nothing if recording rejected it). A point dropped by `world_bounds`
+    /// still restarts the interval. In manual mode nothing is recorded here:
+    /// points are only added by `capture_point`.
+    pub fn tick(&mut self) {
+        self.collect_pumped();
+        
+        let record = self.record_due();
+        let stream = self.stream_due();
+        if !record && !stream {
             return;
         }
         
-        if self.last_record_time.elapsed() < self.record_interval {
+        let Some(sample) = self.read_sample() else {
+            return;
+        };
+        if record {
+            self.last_record_time = Instant::now();
+        }
+        if stream {
+            self.last_stream_time = Instant::now();
+        }
+        self.pump.push(sample, SampleTarget { record, stream });
+    }
+    
+    /// Record and stream the points the pump has converted so far
+    fn collect_pumped(&mut self) {
+        let converted = self.pump.drain();
+        self.apply_pumped(converted);
+    }
+    
+    /// Wait for the pump to convert every sample taken, then record and stream them
+    /// 
+    /// Used before stopping or finalizing so that the last samples are not lost.
+    fn flush_pump(&mut self) {
+        if self.pump.in_flight() == 0 {
             return;
         }
+        let converted = self.pump.wait_all(PUMP_FLUSH_TIMEOUT);
+        self.apply_pumped(converted);
+    }
+    
+    /// Record and/or stream converted samples, according to what they were taken for
+    /// 
+    /// A sample taken for both is streamed as recorded (grace offset and snapping
+    /// applied), and not at all if recording rejected it.
+    fn apply_pumped(&mut self, converted: Vec<ConvertedSample>) {
+        for sample in converted {
+            if let Some(e) = &sample.error {
+                Self::warn_unconvertible_once(&mut self.warned_tiles, sample.point.map_id, e);
+            }
+            // Recording or streaming may have stopped since the sample was taken
+            let mut point = sample.point;
+            if sample.target.record && self.is_recording {
+                if !self.push_point(point) {
+                    continue;
+                }
+                point = self.route[self.route.len() - 1].clone();
+            }
+            if sample.target.stream && self.is_streaming {
+                self.send_streamed_point(point);
+            }
+        }
+    }
+    
+    /// Whether an interval-based sample should be recorded now
+    fn record_due(&self) -> bool {
+        self.is_recording
+            && self.config.recording.mode != RecordMode::Manual
+            && self.last_record_time.elapsed() >= self.effective_record_interval()
+            && Self::gameplay_allows_recording(self.config.recording.require_ingame, self.in_gameplay())
+    }
+    
+    /// Interval until the next sample in interval-based modes
+    /// 
+    /// Fixed `record_interval_ms`, except in adaptive mode where it follows the
+    /// speed over the last few recorded points (densest until the speed is known).
+    pub(crate) fn effective_record_interval(&self) -> Duration {
+        let RecordMode::Adaptive { min_ms, max_ms, speed_scale } = self.config.recording.mode else {
+            return self.record_interval;
+        };
         
-        if let (Some([x, y, z, _, _]), Some(map_id)) = (
-            self.pointers.global_position.read(),
-            self.pointers.global_position.read_map_id(),
-        ) {
+        let Some(speed) = route_analysis::recent_speed(
+            &self.route,
+            ADAPTIVE_SPEED_SAMPLES,
+            self.config.recording.teleport_speed,
+        ) else {
+            return Duration::from_millis(min_ms);
+        };
+        let interval_ms = max_ms as f32 / (1.0 + speed / speed_scale.max(f32::EPSILON));
+        Duration::from_millis((interval_ms as u64).clamp(min_ms.min(max_ms), max_ms))
+    }
+    
+    /// Append exactly one point at the current position, regardless of the interval
+    /// 
+    /// Returns false if the position could not be read or is outside `world_bounds`.
+    pub fn capture_point(&mut self) -> bool {
+        let captured = self.append_current_point();
+        if captured {
+            info!("Captured point #{}", self.route.len());
+        }
+        captured
+    }
+    
+    /// Read and convert the current position on this thread (None if it could not be read)
+    fn current_point(&mut self) -> Option<RoutePoint> {
+        let (point, error) = self.read_sample()?.convert(&self.transformer);
+        if let Some(e) = &error {
+            Self::warn_unconvertible_once(&mut self.warned_tiles, point.map_id, e);
+        }
+        Some(point)
+    }
+    
+    /// Read everything a point needs from the provider, without converting it
+    fn read_sample(&self) -> Option<RawSample> {
+        let (position, map_id) = self.read_position()?;
+        
+        Some(RawSample {
+            position,
+            map_id,
             // Use absolute Unix timestamp (milliseconds since epoch)
-            let timestamp_ms = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .map(|d| d.as_millis() as u64)
-                .unwrap_or(0);
-            
-            // Convert to global coordinates and get the global map ID
-            let (global_x, global_y, global_z, global_map_id) = self.transformer
-                .local_to_world_with_global_map(map_id, x, y, z)
-                .unwrap_or_else(|_| {
-                    // Fallback: if conversion fails, determine global map from map_id
-                    let (area_no, _, _, _) = WorldPositionTransformer::parse_map_id(map_id);
-                    let fallback_global_map = if area_no == 12 {
-                        62 // Underground (m62)
-                    } else if area_no == 60 || area_no == 61 {
-                        area_no
-                    } else {
-                        60 // Default to m60 if unknown
-                    };
-                    (x, y, z, fallback_global_map)
-                });
-            
-            let map_id_str = WorldPositionTransformer::format_map_id(map_id);
-            
-            self.route.push(RoutePoint {
-                x,
-                y,
-                z,
-                global_x,
-                global_y,
-                global_z,
-                map_id,
-                map_id_str,
-                global_map_id,
-                timestamp_ms,
-            });
-            
-            self.last_record_time = Instant::now();
+            // This ensures timestamps are always increasing across game restarts
+            timestamp_ms: epoch_ms_now(),
+            orientation: self.provider.read_orientation(),
+            frame: self.provider.read_frame_counter(),
+            raw: if self.config.recording.capture_raw_array { self.provider.read_raw_position() } else { None },
+        })
+    }
+    
+    /// Read, convert and append the current position to the route
+    fn append_current_point(&mut self) -> bool {
+        match self.current_point() {
+            Some(point) => self.push_point(point),
+            None => false,
+        }
+    }
+    
+    /// Append an already converted point to the route (false if out of `world_bounds`)
+    fn push_point(&mut self, mut point: RoutePoint) -> bool {
+        // Glitched reads can land millions of units away: drop them
+        if let Some(bounds) = self.config.recording.world_bounds {
+            if !bounds.contains(point.global_x, point.global_z) {
+                debug!(
+                    "Dropped out-of-bounds point ({}, {}) on {}",
+                    point.global_x, point.global_z, point.map_id_str
+                );
+                return false;
+            }
         }
+        
+        self.snap_to_bonfire(&point);
+        point.relative_to_grace = self.offset_from_last_grace(&point);
+        self.route.push(point);
+        true
+    }
+    
+    /// Read the local position and map_id from the position provider
+    pub fn read_position(&self) -> Option<([f32; 3], u32)> {
+        self.provider.read_position()
+    }
+    
+    /// Whether the player is currently in gameplay
+    /// 
+    /// Returns None if the provider cannot tell.
+    pub fn in_gameplay(&self) -> Option<bool> {
+        self.provider.in_gameplay()
+    }
+    
+    /// Decide whether a sample may be recorded given the gameplay state
+    /// 
+    /// An unknown state counts as in gameplay, so that an unreadable pointer
+    /// does not silently stop recording.
+    pub(crate) fn gameplay_allows_recording(require_ingame: bool, in_gameplay: Option<bool>) -> bool {
+        !require_ingame || in_gameplay.unwrap_or(true)
     }
     
-    /// Stream current position to real-time backend if enabled
-    /// This is independent of recording - streams position even when not recording
-    pub fn stream_position(&mut self) {
+    /// Whether a point should be streamed now (updates the backlog throttle state)
+    fn stream_due(&mut self) -> bool {
         // Only stream if streaming is enabled and client is configured
-        if !self.is_streaming {
-            return;
+        if !self.is_streaming || self.streaming_paused() {
+            return false;
         }
         
+        let Some(ref client) = self.realtime_client else {
+            return false;
+        };
+        
+        // The sender thread is gone: nothing would be sent (the overlay shows it)
+        if !client.is_healthy() {
+            return false;
+        }
+        
+        // Slow down while the sender thread is behind, back to normal once half drained
+        let depth = client.queue_depth();
+        let high_water = self.config.realtime.throttle_queue_depth;
+        if high_water > 0 && depth >= high_water && !self.stream_throttled {
+            warn!("Realtime backlog at {} points, streaming {}x slower", depth, STREAM_THROTTLE_FACTOR);
+            self.stream_throttled = true;
+        } else if self.stream_throttled && depth <= high_water / 2 {
+            info!("Realtime backlog drained, streaming at the normal interval");
+            self.stream_throttled = false;
+        }
+        
+        // Respect the same interval as recording (longer while throttled)
+        let interval = if self.stream_throttled {
+            self.record_interval * STREAM_THROTTLE_FACTOR
+        } else {
+            self.record_interval
+        };
+        self.last_stream_time.elapsed() >= interval
+    }
+    
+    /// Send a point to the realtime backend, preceded by a map transition if any
+    fn send_streamed_point(&mut self, point: RoutePoint) {
         let Some(ref client) = self.realtime_client else {
             return;
         };
         
-        // Respect the same interval as recording
-        if self.last_stream_time.elapsed() < self.record_interval {
+        // Notify the backend of map changes before the point itself
+        if let Some(transition) = self.last_streamed_point.as_ref()
+            .and_then(|previous| MapTransition::between(previous, &point))
+        {
+            info!("Map transition: {} -> {}", transition.old_map_id_str, transition.new_map_id_str);
+            client.send_map_transition(transition);
+        }
+        
+        // Send to real-time backend
+        client.send_point(&point);
+        
+        self.last_streamed_point = Some(point);
+    }
+    
+    /// Detect a death from the provider's death counter
+    /// 
+    /// When the counter goes up, a death waypoint is added to the route while
+    /// recording and `POST /api/Death` is queued while streaming. The first read
+    /// only sets the baseline. Returns whether a death was detected.
+    pub fn check_death(&mut self) -> bool {
+        let Some(death_count) = self.provider.read_death_count() else {
+            return false;
+        };
+        if self.last_death_count.is_none_or(|previous| death_count <= previous) {
+            self.last_death_count = Some(death_count);
+            return false;
+        }
+        
+        // Keep the old baseline until the death is built, so a failed read retries next frame
+        let Some(point) = self.current_point() else {
+            return false;
+        };
+        self.last_death_count = Some(death_count);
+        let death = DeathEvent::at(&point, death_count);
+        info!("Player died on {} (death #{})", death.map_id_str, death_count);
+        
+        if self.is_streaming && !self.streaming_paused() {
+            if let Some(ref client) = self.realtime_client {
+                client.send_death(death.clone());
+            }
+        }
+        if self.is_recording {
+            self.deaths.push(death);
+        }
+        true
+    }
+    
+    /// Whether streaming is temporarily suppressed (overlay hidden with `pause_when_ui_hidden`)
+    pub fn streaming_paused(&self) -> bool {
+        self.config.realtime.pause_when_ui_hidden && !self.show_ui
+    }
+    
+    /// Stream a burst of samples at once (e.g. when catching up after a stall)
+    /// 
+    /// Each sample is `([x, y, z], map_id, timestamp_ms)`. All samples are converted
+    /// with the batch API and queued in order; map transitions are queued between
+    /// the points they separate.
+    pub fn stream_burst(&mut self, samples: &[([f32; 3], u32, u64)]) {
+        if !self.is_streaming || self.streaming_paused() || samples.is_empty() {
             return;
         }
         
-        if let (Some([x, y, z, _, _]), Some(map_id)) = (
-            self.pointers.global_position.read(),
-            self.pointers.global_position.read_map_id(),
-        ) {
-            // Use absolute Unix timestamp (milliseconds since epoch)
-            // This ensures timestamps are always increasing across game restarts
-            let timestamp_ms = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .map(|d| d.as_millis() as u64)
-                .unwrap_or(0);
-            
-            // Convert to global coordinates and get the global map ID
-            let (global_x, global_y, global_z, global_map_id) = self.transformer
-                .local_to_world_with_global_map(map_id, x, y, z)
-                .unwrap_or_else(|_| {
-                    // Fallback: if conversion fails, determine global map from map_id
-                    let (area_no, _, _, _) = WorldPositionTransformer::parse_map_id(map_id);
-                    let fallback_global_map = if area_no == 12 {
-                        62 // Underground (m62)
-                    } else if area_no == 60 || area_no == 61 {
-                        area_no
-                    } else {
-                        60 // Default to m60 if unknown
-                    };
-                    (x, y, z, fallback_global_map)
-                });
-            
-            let map_id_str = WorldPositionTransformer::format_map_id(map_id);
-            
-            let point = RoutePoint {
-                x,
-                y,
-                z,
-                global_x,
-                global_y,
-                global_z,
-                map_id,
-                map_id_str,
-                global_map_id,
-                timestamp_ms,
-            };
+        let Some(ref client) = self.realtime_client else {
+            return;
+        };
+        
+        let batch: Vec<(u32, f32, f32, f32)> = samples
+            .iter()
+            .map(|&([x, y, z], map_id, _)| (map_id, x, y, z))
+            .collect();
+        let converted = self.transformer.local_to_world_batch(&batch);
+        
+        let mut pending: Vec<RoutePoint> = Vec::with_capacity(samples.len());
+        for (&(position, map_id, timestamp_ms), result) in samples.iter().zip(converted) {
+            if let Err(e) = &result {
+                Self::warn_unconvertible_once(&mut self.warned_tiles, map_id, e);
+            }
+            let hops = self.transformer.conversion_hops(map_id);
+            let point = Self::build_point(position, map_id, timestamp_ms, result, hops);
             
-            // Send to real-time backend
-            client.send_point(&point);
+            let previous = pending.last().or(self.last_streamed_point.as_ref());
+            if let Some(transition) = previous.and_then(|p| MapTransition::between(p, &point)) {
+                // Queue the points before the transition, then the transition itself
+                client.send_points(&pending);
+                if let Some(last) = pending.pop() {
+                    self.last_streamed_point = Some(last);
+                }
+                pending.clear();
+                client.send_map_transition(transition);
+            }
             
-            self.last_stream_time = Instant::now();
+            pending.push(point);
+        }
+        
+        client.send_points(&pending);
+        self.last_streamed_point = pending.pop();
+        self.last_stream_time = Instant::now();
+    }
+    
+    /// Log a failed conversion, once per tile and session (returns whether it was logged)
+    /// 
+    /// With partial data the same tile fails on every tick; a single line per
+    /// tile keeps the log readable.
+    fn warn_unconvertible_once(warned_tiles: &mut HashSet<u32>, map_id: u32, error: &TransformError) -> bool {
+        if !warned_tiles.insert(map_id) {
+            return false;
+        }
+        warn!("Cannot convert {} to global coordinates ({}), keeping local coordinates", WorldPositionTransformer::format_map_id(map_id), error);
+        true
+    }
+    
+    /// Build a route point from a local position and its conversion result
+    /// 
+    /// If the conversion failed, the local coordinates are kept and the global
+    /// map is guessed from the map_id. `hops` is the number of anchors applied
+    /// by the conversion (see `conversion_hops`) and sets the point accuracy.
+    pub(crate) fn build_point(
+        [x, y, z]: [f32; 3],
+        map_id: u32,
+        timestamp_ms: u64,
+        converted: Result<(f32, f32, f32, u8), TransformError>,
+        hops: Option<usize>,
+    ) -> RoutePoint {
+        let accuracy = if converted.is_ok() {
+            PointAccuracy::from_hops(hops)
+        } else {
+            PointAccuracy::Fallback
+        };
+        
+        let (global_x, global_y, global_z, global_map_id) = converted.unwrap_or_else(|_| {
+            // Fallback: if conversion fails, determine global map from map_id
+            (x, y, z, WorldPositionTransformer::fallback_global_map(map_id))
+        });
+        
+        RoutePoint {
+            x,
+            y,
+            z,
+            global_x,
+            global_y,
+            global_z,
+            map_id,
+            map_id_str: WorldPositionTransformer::format_map_id(map_id),
+            global_map_id,
+            global_map_label: global_map_label(global_map_id),
+            timestamp_ms,
+            // Recorded timestamps are already wall-clock time; kept as is when they get rebased
+            epoch_ms: timestamp_ms,
+            frame: None,
+            accuracy,
+            qx: None,
+            qy: None,
+            qz: None,
+            qw: None,
+            raw: None,
+            relative_to_grace: None,
         }
     }
     
-    /// Save the recorded route to a JSON file
-    pub fn save_route(&self) -> Result<PathBuf, String> {
+    /// Save the recorded route to one or more JSON files
+    /// 
+    /// With `output.coordinate_decimals`, a rounded copy is saved (the recorded
+    /// route keeps full precision).
+    pub fn save_route(&self) -> Result<Vec<PathBuf>, String> {
+        let split = if self.config.output.split_by_map {
+            RouteFileSplit::ByMap
+        } else {
+            RouteFileSplit::Minutes(self.config.output.split_minutes)
+        };
         let result = save_route_to_file(
-            &self.route,
+            &self.output_route(),
             &self.base_dir,
             &self.config.output.routes_directory,
             self.config.recording.record_interval_ms,
+            split,
+            &self.route_metadata(),
+            RouteWaypoints { graces_visited: &self.graces_visited, deaths: &self.deaths },
         );
         
-        if let Ok(ref path) = result {
-            info!("Route saved to: {}", path.display());
+        if let Ok(ref paths) = result {
+            for path in paths {
+                info!("Route saved to: {}", path.display());
+            }
         }
         
         result
     }
     
+    /// The recorded route as saved-route JSON, for copying without writing a file
+    /// 
+    /// Same document as a single-file `save_route`: rounding, metadata and waypoints.
+    pub fn route_json(&self) -> Result<String, String> {
+        let now = generate_timestamp();
+        route_to_json_string(
+            &self.output_route(),
+            format!("Route {}", now),
+            &now,
+            self.config.recording.record_interval_ms,
+            &self.route_metadata(),
+            RouteWaypoints { graces_visited: &self.graces_visited, deaths: &self.deaths },
+        )
+    }
+    
+    /// The route as it is written out (rounded copy with `output.coordinate_decimals`)
+    fn output_route(&self) -> Cow<'_, [RoutePoint]> {
+        match self.config.output.coordinate_decimals {
+            Some(decimals) => {
+                let mut rounded = self.route.clone();
+                round_coordinates(&mut rounded, decimals);
+                Cow::Owned(rounded)
+            }
+            None => Cow::Borrowed(&self.route),
+        }
+    }
+    
+    /// Export the recorded route and its waypoints as KML, using the configured
+    /// axis mapping and teleport speed
+    pub fn export_route_kml<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        route_export::export_kml(
+            &self.route,
+            &RouteWaypoints { graces_visited: &self.graces_visited, deaths: &self.deaths },
+            &self.config.export.axis_mapping,
+            self.config.recording.teleport_speed,
+            writer,
+        )
+    }
+    
+    /// Build the metadata header for saved routes
+    /// 
+    /// The game version and character name are not exposed by the pointers
+    /// used here, so they are left out of the header.
+    pub fn route_metadata(&self) -> RouteMetadata {
+        RouteMetadata {
+            game_version: None,
+            character_name: None,
+            recorded_at: Some(iso8601_now()),
+            dataset_version: self.dataset_version.clone(),
+        }
+    }
+    
     /// Set a status message that will be displayed temporarily
     pub fn set_status(&mut self, message: String) {
         self.status_message = Some((message, Instant::now()));
@@ -327,21 +1053,876 @@ impl RouteTracker {
     /// Returns the player's current position (local and global)
     /// Returns: (local_x, local_y, local_z, global_x, global_y, global_z, map_id)
     pub fn get_current_position(&self) -> Option<(f32, f32, f32, f32, f32, f32, u32)> {
-        if let (Some([x, y, z, _, _]), Some(map_id)) = (
-            self.pointers.global_position.read(),
-            self.pointers.global_position.read_map_id(),
-        ) {
-            // Convert to global coordinates
-            let (gx, gy, gz) = self.transformer
-                .local_to_world_first(map_id, x, y, z)
-                .unwrap_or((x, y, z));
+        if let Some(([x, y, z], map_id)) = self.read_position() {
+            // Convert to global coordinates (local ones if the tile is unknown)
+            let (gx, gy, gz, _, _) = self.transformer.local_to_world_or_local(map_id, x, y, z);
             
             Some((x, y, z, gx, gy, gz, map_id))
         } else {
             None
         }
     }
+    
+    /// Returns the position exactly as read, without any conversion
+    /// Returns: (local_x, local_y, local_z, map_id)
+    /// 
+    /// For comparing against the game's own debug display: a bad read can't be
+    /// hidden by the transformer.
+    pub fn raw_position(&self) -> Option<(f32, f32, f32, u32)> {
+        self.read_position().map(|([x, y, z], map_id)| (x, y, z, map_id))
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use crate::coordinate_transformer::MapBounds;
+
+    #[test]
+    fn test_gameplay_gate() {
+        // Gate disabled: always record
+        assert!(RouteTracker::gameplay_allows_recording(false, Some(false)));
+
+        // Gate enabled: only record while in gameplay
+        let states = [Some(true), Some(false), Some(true), None];
+        let recorded: Vec<bool> = states
+            .iter()
+            .map(|&state| RouteTracker::gameplay_allows_recording(true, state))
+            .collect();
+        assert_eq!(recorded, vec![true, false, true, true]);
+    }
+
+    /// Scripted positions with a gameplay state the test can switch
+    struct GameplayProvider {
+        positions: VecProvider,
+        in_gameplay: Arc<Mutex<Option<bool>>>,
+    }
+
+    impl PositionProvider for GameplayProvider {
+        fn read_position(&self) -> Option<([f32; 3], u32)> {
+            self.positions.read_position()
+        }
+
+        fn in_gameplay(&self) -> Option<bool> {
+            *self.in_gameplay.lock().unwrap()
+        }
+    }
+
+    #[test]
+    fn test_gameplay_gate_on_tick() {
+        let in_gameplay = Arc::new(Mutex::new(None));
+        let mut config = Config::default();
+        config.recording.record_interval_ms = 0;
+        config.recording.require_ingame = true;
+        let mut tracker = RouteTracker::with_provider(
+            config,
+            std::env::temp_dir(),
+            WorldPositionTransformer::empty(),
+            Box::new(GameplayProvider {
+                positions: VecProvider::new((0..4).map(|i| ([i as f32, 0.0, 0.0], 0x3C282300)).collect()),
+                in_gameplay: in_gameplay.clone(),
+            }),
+        );
+        tracker.start_recording();
+        
+        // Recorded while in gameplay or unknown, skipped (position not even read) otherwise
+        for state in [Some(true), Some(false), None, Some(false), Some(true)] {
+            *in_gameplay.lock().unwrap() = state;
+            tick_flushed(&mut tracker);
+        }
+        let xs: Vec<f32> = tracker.route.iter().map(|point| point.x).collect();
+        assert_eq!(xs, vec![0.0, 1.0, 2.0]);
+    }
+
+    #[test]
+    fn test_point_accuracy() {
+        // One anchor m10_00_00 -> m60_40_35
+        let csv_path = std::env::temp_dir().join(format!("accuracy_test_{}.csv", std::process::id()));
+        std::fs::write(
+            &csv_path,
+            "header\n0,0,0,0,0,10,0,0,0,1.0,2.0,3.0,60,40,35,0,100.0,50.0,100.0\n",
+        ).unwrap();
+        let transformer = WorldPositionTransformer::from_csv(&csv_path).unwrap();
+        std::fs::remove_file(&csv_path).unwrap();
+        
+        let accuracy = |map_id: u32| {
+            let converted = transformer.local_to_world_with_global_map(map_id, 1.0, 2.0, 3.0);
+            let hops = transformer.conversion_hops(map_id);
+            RouteTracker::build_point([1.0, 2.0, 3.0], map_id, 0, converted, hops).accuracy
+        };
+        
+        assert_eq!(accuracy(0x3C282300), PointAccuracy::ExactGlobal); // m60_40_35_00
+        assert_eq!(accuracy(0x0A000000), PointAccuracy::DirectAnchor); // m10_00_00_00
+        assert_eq!(accuracy(0x0B000000), PointAccuracy::Fallback); // m11_00_00_00
+    }
+
+    #[test]
+    fn test_session_elapsed() {
+        let start = Instant::now() - Duration::from_secs(90);
+        
+        let elapsed = RouteTracker::session_elapsed(true, Some(start)).unwrap();
+        assert!(elapsed >= Duration::from_secs(90));
+        assert!(elapsed < Duration::from_secs(95));
+        
+        // Stopped or never started sessions have no duration
+        assert_eq!(RouteTracker::session_elapsed(false, Some(start)), None);
+        assert_eq!(RouteTracker::session_elapsed(true, None), None);
+    }
+
+    #[test]
+    fn test_read_consistent_drops_tile_changes() {
+        let map_ids = [0x3C282300, 0x3C282300, 0x3C282300, 0x3C292300];
+        let reads = AtomicUsize::new(0);
+        let read_map_id = || Some(map_ids[reads.fetch_add(1, Ordering::Relaxed)]);
+        let read_position = || Some([1.0, 2.0, 3.0, 0.0, 0.0]);
+        
+        // Stable map id
+        assert_eq!(read_consistent(read_map_id, read_position), Some(([1.0, 2.0, 3.0], 0x3C282300)));
+        
+        // Map id changed between the two reads: sample dropped
+        assert_eq!(read_consistent(read_map_id, read_position), None);
+    }
+
+    /// One hook frame: sample if due, then record/stream the sample once converted
+    fn tick_flushed(tracker: &mut RouteTracker) {
+        tracker.tick();
+        tracker.flush_pump();
+    }
+
+    #[test]
+    fn test_record_from_scripted_provider() {
+        let script = vec![
+            ([10.0, 5.0, 20.0], 0x3C282300), // m60_40_35_00
+            ([11.0, 5.0, 21.0], 0x3C282300),
+            ([1.0, 2.0, 3.0], 0x3D2D2900), // m61_45_41_00
+        ];
+        
+        let mut config = Config::default();
+        config.recording.record_interval_ms = 0;
+        let mut tracker = RouteTracker::with_provider(
+            config,
+            std::env::temp_dir(),
+            WorldPositionTransformer::empty(),
+            Box::new(VecProvider::new(script.clone())),
+        );
+        
+        tracker.start_recording();
+        for _ in 0..script.len() + 2 {
+            tick_flushed(&mut tracker);
+        }
+        
+        // One point per sample, nothing once the script is exhausted
+        let recorded: Vec<([f32; 3], u32)> = tracker.route
+            .iter()
+            .map(|point| ([point.x, point.y, point.z], point.map_id))
+            .collect();
+        assert_eq!(recorded, script);
+        assert_eq!(tracker.route[0].global_x, 10.0 + 40.0 * 256.0);
+        assert_eq!(tracker.route[2].global_map_id, 61);
+    }
+
+    #[test]
+    fn test_record_interval() {
+        let mut config = Config::default();
+        config.recording.record_interval_ms = 60_000;
+        let mut tracker = RouteTracker::with_provider(
+            config,
+            std::env::temp_dir(),
+            WorldPositionTransformer::empty(),
+            Box::new(VecProvider::new(vec![([1.0, 2.0, 3.0], 0x3C282300); 3])),
+        );
+        
+        tracker.start_recording();
+        tick_flushed(&mut tracker);
+        assert!(tracker.route.is_empty());
+        
+        // Once the interval has passed, one point, then nothing until the next one
+        tracker.last_record_time -= Duration::from_secs(61);
+        tick_flushed(&mut tracker);
+        assert_eq!(tracker.route.len(), 1);
+        tick_flushed(&mut tracker);
+        assert_eq!(tracker.route.len(), 1);
+    }
+
+    #[test]
+    fn test_manual_mode_captures_on_demand() {
+        let script = vec![
+            ([10.0, 5.0, 20.0], 0x3C282300), // m60_40_35_00
+            ([11.0, 5.0, 21.0], 0x3C282300),
+            ([12.0, 5.0, 22.0], 0x3C282300),
+        ];
+        
+        let mut config = Config::default();
+        config.recording.record_interval_ms = 0;
+        config.recording.mode = RecordMode::Manual;
+        let mut tracker = RouteTracker::with_provider(
+            config,
+            std::env::temp_dir(),
+            WorldPositionTransformer::empty(),
+            Box::new(VecProvider::new(script)),
+        );
+        
+        tracker.start_recording();
+        for _ in 0..5 {
+            tick_flushed(&mut tracker);
+        }
+        assert!(tracker.route.is_empty());
+        
+        assert!(tracker.capture_point());
+        assert_eq!(tracker.route.len(), 1);
+        assert!(tracker.capture_point());
+        assert_eq!(tracker.route.len(), 2);
+        assert_eq!(tracker.route[1].x, 11.0);
+        assert_eq!(tracker.route[1].global_x, 11.0 + 40.0 * 256.0);
+    }
+    
+    #[test]
+    fn test_graces_visited() {
+        let bonfire: OutputMapIcon = serde_json::from_value(serde_json::json!({
+            "id": 100000, "iconId": 1, "eventFlagId": 71000,
+            "areaNo": 60, "gridXNo": 40, "gridZNo": 35,
+            "posX": 10.0, "posY": 5.0, "posZ": 20.0,
+            "globalX": 10250.0, "globalY": 5.0, "globalZ": 8980.0,
+            "mapId": "m60",
+            "texts": [{ "TextId": 100000, "TextType": 0, "Text": "Godrick the Grafted", "Source": "Title_Locations" }]
+        })).unwrap();
+        
+        let mut config = Config::default();
+        config.recording.record_interval_ms = 0;
+        config.recording.snap_to_bonfires_radius = 10.0;
+        let mut tracker = RouteTracker::with_provider(
+            config,
+            std::env::temp_dir(),
+            WorldPositionTransformer::empty(),
+            Box::new(VecProvider::new(vec![
+                ([100.0, 5.0, 100.0], 0x3C282300), // far away
+                ([12.0, 5.0, 21.0], 0x3C282300),   // ~2.2 from the grace
+                ([11.0, 5.0, 20.0], 0x3C282300),   // same grace again
+            ])),
+        );
+        tracker.attach_bonfires(vec![bonfire]);
+        
+        tracker.start_recording();
+        for _ in 0..3 {
+            tick_flushed(&mut tracker);
+        }
+        
+        assert_eq!(tracker.graces_visited.len(), 1);
+        assert_eq!(tracker.graces_visited[0].name, "Godrick the Grafted");
+        assert_eq!(tracker.graces_visited[0].timestamp_ms, tracker.route[1].timestamp_ms);
+        
+        // Offsets from the last visited grace, none before reaching it
+        assert!(tracker.route[0].relative_to_grace.is_none());
+        assert_eq!(tracker.route[1].relative_to_grace, Some(("Godrick the Grafted".to_string(), 2.0, 0.0, 1.0)));
+        assert_eq!(tracker.route[2].relative_to_grace, Some(("Godrick the Grafted".to_string(), 1.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn test_with_config_loads_data_from_base_dir() {
+        // One anchor m10_00_00 -> m60_40_35
+        let base_dir = std::env::temp_dir().join(format!("with_config_test_{}", std::process::id()));
+        std::fs::create_dir_all(&base_dir).unwrap();
+        std::fs::write(
+            base_dir.join("WorldMapLegacyConvParam.csv"),
+            "header\n0,0,0,0,0,10,0,0,0,1.0,2.0,3.0,60,40,35,0,100.0,50.0,100.0\n",
+        ).unwrap();
+        
+        let mut config = Config::default();
+        config.recording.record_interval_ms = 0;
+        let mut tracker = RouteTracker::with_config_and_provider(
+            config,
+            base_dir.clone(),
+            Box::new(VecProvider::new(vec![([1.0, 2.0, 3.0], 0x0A000000)])), // m10_00_00_00
+        );
+        std::fs::remove_dir_all(&base_dir).unwrap();
+        
+        assert!(tracker.dataset_version.is_some());
+        
+        tracker.start_recording();
+        tick_flushed(&mut tracker);
+        
+        assert_eq!(tracker.route.len(), 1);
+        assert_eq!(tracker.route[0].accuracy, PointAccuracy::DirectAnchor);
+        assert_eq!(tracker.route[0].global_x, 100.0 + 40.0 * 256.0);
+    }
+
+    #[test]
+    fn test_pause_streaming_when_ui_hidden() {
+        let mut config = Config::default();
+        config.recording.record_interval_ms = 0;
+        config.realtime.pause_when_ui_hidden = true;
+        let mut tracker = RouteTracker::with_provider(
+            config,
+            std::env::temp_dir(),
+            WorldPositionTransformer::empty(),
+            Box::new(VecProvider::new(vec![([1.0, 2.0, 3.0], 0x3C282300); 2])),
+        );
+        let poster = OkPoster::default();
+        tracker.realtime_client = Some(streaming_client(&poster));
+        tracker.start_recording();
+        tracker.start_streaming();
+        let sent_points = |tracker: &RouteTracker| {
+            let client = tracker.realtime_client.as_ref().unwrap();
+            assert!(client.flush_and_wait(Duration::from_secs(5)));
+            (client.queue_depth(), poster.posted_to("/api/RoutePoints"))
+        };
+        
+        // Hiding the overlay pauses streaming only: recorded, nothing queued or sent
+        tracker.show_ui = false;
+        assert!(tracker.streaming_paused());
+        tick_flushed(&mut tracker);
+        assert_eq!(tracker.route.len(), 1);
+        assert!(tracker.last_streamed_point.is_none());
+        assert_eq!(sent_points(&tracker), (0, 0));
+        
+        // Shown again: streaming resumes
+        tracker.show_ui = true;
+        assert!(!tracker.streaming_paused());
+        tick_flushed(&mut tracker);
+        assert_eq!(tracker.route.len(), 2);
+        assert!(tracker.last_streamed_point.is_some());
+        assert_eq!(sent_points(&tracker), (0, 1));
+    }
+
+    #[test]
+    fn test_save_on_stop() {
+        let base_dir = std::env::temp_dir().join(format!("save_on_stop_test_{}", std::process::id()));
+        let mut config = Config::default();
+        config.recording.record_interval_ms = 0;
+        config.recording.save_on_stop = true;
+        let mut tracker = RouteTracker::with_provider(
+            config,
+            base_dir.clone(),
+            WorldPositionTransformer::empty(),
+            Box::new(VecProvider::new(vec![([1.0, 2.0, 3.0], 0x3C282300)])),
+        );
+        let routes_dir = base_dir.join(&tracker.config.output.routes_directory);
+        let saved_files = || std::fs::read_dir(&routes_dir).map(|dir| dir.count()).unwrap_or(0);
+        
+        // Empty route: nothing written
+        tracker.start_recording();
+        tracker.stop_recording();
+        assert_eq!(saved_files(), 0);
+        
+        tracker.start_recording();
+        tick_flushed(&mut tracker);
+        tracker.stop_recording();
+        assert_eq!(saved_files(), 1);
+        
+        std::fs::remove_dir_all(&base_dir).unwrap();
+    }
+
+    #[test]
+    fn test_finalize() {
+        let base_dir = std::env::temp_dir().join(format!("finalize_test_{}", std::process::id()));
+        let mut config = Config::default();
+        config.recording.record_interval_ms = 0;
+        let mut tracker = RouteTracker::with_provider(
+            config,
+            base_dir.clone(),
+            WorldPositionTransformer::empty(),
+            Box::new(VecProvider::new(vec![([1.0, 2.0, 3.0], 0x3C282300); 3])),
+        );
+        
+        tracker.start_recording();
+        tracker.start_streaming();
+        for _ in 0..3 {
+            tick_flushed(&mut tracker);
+        }
+        
+        let paths = tracker.finalize().unwrap();
+        assert!(!tracker.is_recording);
+        assert!(!tracker.is_streaming);
+        assert_eq!(paths.len(), 1);
+        assert!(paths[0].exists());
+        
+        std::fs::remove_dir_all(&base_dir).unwrap();
+    }
+    
+    #[test]
+    fn test_is_in_dlc() {
+        let mut config = Config::default();
+        config.recording.record_interval_ms = 0;
+        let mut tracker = RouteTracker::with_provider(
+            config,
+            std::env::temp_dir(),
+            WorldPositionTransformer::empty(),
+            Box::new(VecProvider::new(vec![
+                ([1.0, 2.0, 3.0], 0x3C282300), // m60_40_35_00
+                ([1.0, 2.0, 3.0], 0x3D2D2800), // m61_45_40_00
+            ])),
+        );
+        assert_eq!(tracker.is_in_dlc(), None);
+        assert_eq!(tracker.last_global_map_id(), None);
+        
+        tracker.start_recording();
+        tick_flushed(&mut tracker);
+        assert_eq!(tracker.is_in_dlc(), Some(false));
+        assert_eq!(tracker.last_global_map_id(), Some(60));
+        tick_flushed(&mut tracker);
+        assert_eq!(tracker.is_in_dlc(), Some(true));
+        assert_eq!(tracker.last_global_map_id(), Some(61));
+    }
+
+    #[test]
+    fn test_raw_position_is_not_converted() {
+        // m60_40_35_00 would convert to global coordinates
+        let tracker = RouteTracker::with_provider(
+            Config::default(),
+            std::env::temp_dir(),
+            WorldPositionTransformer::empty(),
+            Box::new(VecProvider::new(vec![([10.5, -3.25, 20.0], 0x3C282300)])),
+        );
+        
+        assert_eq!(tracker.raw_position(), Some((10.5, -3.25, 20.0, 0x3C282300)));
+        assert_eq!(tracker.raw_position(), None);
+    }
+
+    #[test]
+    fn test_points_carry_wall_clock_time() {
+        // Catch-up samples keep their own capture time, not the time they are built
+        let point = RouteTracker::build_point([1.0, 2.0, 3.0], 0x3C282300, 1_700_000_000_042, Ok((1.0, 2.0, 3.0, 60)), Some(0));
+        assert_eq!(point.timestamp_ms, 1_700_000_000_042);
+        assert_eq!(point.epoch_ms, 1_700_000_000_042);
+        
+        let json = serde_json::to_value(&point).unwrap();
+        assert_eq!(json["epoch_ms"], point.epoch_ms);
+        
+        // Recorded points are stamped from the clock
+        let before = epoch_ms_now();
+        let mut tracker = RouteTracker::with_provider(
+            Config::default(),
+            std::env::temp_dir(),
+            WorldPositionTransformer::empty(),
+            Box::new(VecProvider::new(vec![([1.0, 2.0, 3.0], 0x3C282300)])),
+        );
+        tracker.start_recording();
+        assert!(tracker.capture_point());
+        let point = &tracker.route[0];
+        assert!(point.epoch_ms >= before && point.epoch_ms <= epoch_ms_now());
+        assert_eq!(point.epoch_ms, point.timestamp_ms);
+    }
+
+    #[test]
+    fn test_world_bounds_drop_glitched_points() {
+        let script = vec![
+            ([10.0, 5.0, 20.0], 0x3C282300), // m60_40_35_00
+            ([5_000_000.0, 5.0, 20.0], 0x3C282300), // Glitched read
+            ([11.0, 5.0, 21.0], 0x3C282300),
+        ];
+        
+        let mut config = Config::default();
+        config.recording.record_interval_ms = 0;
+        config.recording.world_bounds = Some(MapBounds { min_x: 0.0, max_x: 30000.0, min_z: 0.0, max_z: 30000.0 });
+        let mut tracker = RouteTracker::with_provider(
+            config,
+            std::env::temp_dir(),
+            WorldPositionTransformer::empty(),
+            Box::new(VecProvider::new(script)),
+        );
+        
+        tracker.start_recording();
+        for _ in 0..3 {
+            tick_flushed(&mut tracker);
+        }
+        
+        let xs: Vec<f32> = tracker.route.iter().map(|p| p.x).collect();
+        assert_eq!(xs, vec![10.0, 11.0]);
+    }
 
+    #[test]
+    fn test_adaptive_interval_follows_speed() {
+        let mut config = Config::default();
+        config.recording.mode = RecordMode::Adaptive { min_ms: 50, max_ms: 1000, speed_scale: 5.0 };
+        let mut tracker = RouteTracker::with_provider(
+            config,
+            std::env::temp_dir(),
+            WorldPositionTransformer::empty(),
+            Box::new(VecProvider::new(Vec::new())),
+        );
+        let push = |tracker: &mut RouteTracker, x: f32, timestamp_ms: u64| {
+            let point = RouteTracker::build_point([x, 0.0, 0.0], 0x3C282300, timestamp_ms, Ok((x, 0.0, 0.0, 60)), Some(0));
+            tracker.route.push(point);
+        };
+        
+        // Nothing recorded yet: densest
+        assert_eq!(tracker.effective_record_interval(), Duration::from_millis(50));
+        
+        // Galloping: 20 units/s
+        for i in 0..5 {
+            push(&mut tracker, i as f32 * 2.0, i * 100);
+        }
+        let fast = tracker.effective_record_interval();
+        assert_eq!(fast, Duration::from_millis(200));
+        
+        // Walking: 1 unit/s
+        for i in 0..5 {
+            push(&mut tracker, 8.0 + i as f32, 1000 + i * 1000);
+        }
+        let slow = tracker.effective_record_interval();
+        assert!(slow > fast);
+        assert_eq!(slow, Duration::from_millis(833));
+        
+        // Standing still: sparsest
+        for i in 0..5 {
+            push(&mut tracker, 12.0, 6000 + i * 1000);
+        }
+        assert_eq!(tracker.effective_record_interval(), Duration::from_millis(1000));
+    }
 
+    /// Scripted positions with a death counter read alongside
+    struct DyingProvider {
+        positions: VecProvider,
+        death_counts: Vec<u32>,
+        next_count: AtomicUsize,
+    }
+
+    impl PositionProvider for DyingProvider {
+        fn read_position(&self) -> Option<([f32; 3], u32)> {
+            self.positions.read_position()
+        }
+        
+        fn read_death_count(&self) -> Option<u32> {
+            let index = self.next_count.fetch_add(1, Ordering::Relaxed);
+            self.death_counts.get(index).copied()
+        }
+    }
+
+    #[test]
+    fn test_death_detected_once_per_increment() {
+        let provider = DyingProvider {
+            positions: VecProvider::new(vec![
+                ([10.0, 5.0, 20.0], 0x3C282300),
+                ([11.0, 5.0, 21.0], 0x3C282300),
+            ]),
+            // Baseline 3, one death, unchanged, another death, then unreadable
+            death_counts: vec![3, 4, 4, 4, 5],
+            next_count: AtomicUsize::new(0),
+        };
+        let mut tracker = RouteTracker::with_provider(
+            Config::default(),
+            std::env::temp_dir(),
+            WorldPositionTransformer::empty(),
+            Box::new(provider),
+        );
+        tracker.start_recording();
+        
+        let detected: Vec<bool> = (0..6).map(|_| tracker.check_death()).collect();
+        assert_eq!(detected, vec![false, true, false, false, true, false]);
+        
+        let counts: Vec<u32> = tracker.deaths.iter().map(|death| death.death_count).collect();
+        assert_eq!(counts, vec![4, 5]);
+        assert_eq!(tracker.deaths[0].global_x, 10.0 + 40.0 * 256.0);
+        assert_eq!(tracker.deaths[1].map_id_str, "m60_40_35_00");
+    }
+    
+    /// Deaths whose first position read fails
+    struct BlindFirstRead {
+        inner: DyingProvider,
+        reads: AtomicUsize,
+    }
+    
+    impl PositionProvider for BlindFirstRead {
+        fn read_position(&self) -> Option<([f32; 3], u32)> {
+            if self.reads.fetch_add(1, Ordering::Relaxed) == 0 {
+                return None;
+            }
+            self.inner.read_position()
+        }
+        
+        fn read_death_count(&self) -> Option<u32> {
+            self.inner.read_death_count()
+        }
+    }
+    
+    #[test]
+    fn test_death_retried_after_failed_position_read() {
+        let provider = BlindFirstRead {
+            inner: DyingProvider {
+                positions: VecProvider::new(vec![([10.0, 5.0, 20.0], 0x3C282300)]),
+                death_counts: vec![3, 4, 4],
+                next_count: AtomicUsize::new(0),
+            },
+            reads: AtomicUsize::new(0),
+        };
+        let mut tracker = RouteTracker::with_provider(
+            Config::default(),
+            std::env::temp_dir(),
+            WorldPositionTransformer::empty(),
+            Box::new(provider),
+        );
+        tracker.start_recording();
+        
+        let detected: Vec<bool> = (0..3).map(|_| tracker.check_death()).collect();
+        assert_eq!(detected, vec![false, false, true]);
+        assert_eq!(tracker.deaths.len(), 1);
+        assert_eq!(tracker.deaths[0].death_count, 4);
+    }
+
+    #[test]
+    fn test_saved_coordinates_rounded() {
+        let base_dir = std::env::temp_dir().join(format!("coordinate_decimals_test_{}", std::process::id()));
+        let mut config = Config::default();
+        config.recording.record_interval_ms = 0;
+        config.output.coordinate_decimals = Some(1);
+        let mut tracker = RouteTracker::with_provider(
+            config,
+            base_dir.clone(),
+            WorldPositionTransformer::empty(),
+            Box::new(VecProvider::new(vec![([-11.5137, 90.6049, -56.8812], 0x3C282300)])),
+        );
+        tracker.start_recording();
+        tick_flushed(&mut tracker);
+        
+        let paths = tracker.save_route().unwrap();
+        let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&paths[0]).unwrap()).unwrap();
+        let point = &json["points"][0];
+        assert_eq!(point["x"].to_string(), "-11.5");
+        assert_eq!(point["y"].to_string(), "90.6");
+        assert_eq!(point["z"].to_string(), "-56.9");
+        assert_eq!(point["global_x"].to_string(), "10228.5");
+        
+        // The recorded route keeps full precision
+        assert_eq!(tracker.route[0].x, -11.5137);
+        
+        std::fs::remove_dir_all(&base_dir).unwrap();
+    }
+    
+    #[test]
+    fn test_route_json_matches_saved_file() {
+        let base_dir = std::env::temp_dir().join(format!("route_json_test_{}", std::process::id()));
+        let mut config = Config::default();
+        config.recording.record_interval_ms = 0;
+        let mut tracker = RouteTracker::with_provider(
+            config,
+            base_dir.clone(),
+            WorldPositionTransformer::empty(),
+            Box::new(VecProvider::new(vec![([1.0, 2.0, 3.0], 0x3C282300), ([4.0, 5.0, 6.0], 0x3C282300)])),
+        );
+        tracker.start_recording();
+        tick_flushed(&mut tracker);
+        tick_flushed(&mut tracker);
+        tracker.deaths.push(DeathEvent::at(&tracker.route[1], 1));
+        
+        let without_name = |json: &str| {
+            let mut value: serde_json::Value = serde_json::from_str(json).unwrap();
+            let object = value.as_object_mut().unwrap();
+            object.remove("name");
+            object.remove("recorded_at");
+            value
+        };
+        let copied = without_name(&tracker.route_json().unwrap());
+        let paths = tracker.save_route().unwrap();
+        assert_eq!(copied, without_name(&std::fs::read_to_string(&paths[0]).unwrap()));
+        assert_eq!(copied["deaths"][0]["death_count"], 1);
+        
+        std::fs::remove_dir_all(&base_dir).unwrap();
+    }
+    
+    /// Scripted positions that also expose the raw 5-element array
+    struct RawProvider(VecProvider);
+    
+    impl PositionProvider for RawProvider {
+        fn read_position(&self) -> Option<([f32; 3], u32)> {
+            self.0.read_position()
+        }
+        
+        fn read_raw_position(&self) -> Option<[f32; 5]> {
+            Some([10.0, 5.0, 20.0, 0.25, -1.5])
+        }
+    }
+    
+    #[test]
+    fn test_capture_raw_array() {
+        let mut config = Config::default();
+        config.recording.capture_raw_array = true;
+        let mut tracker = RouteTracker::with_provider(
+            config,
+            std::env::temp_dir(),
+            WorldPositionTransformer::empty(),
+            Box::new(RawProvider(VecProvider::new(vec![([10.0, 5.0, 20.0], 0x3C282300)]))),
+        );
+        tracker.start_recording();
+        
+        assert!(tracker.capture_point());
+        assert_eq!(tracker.route[0].raw, Some([10.0, 5.0, 20.0, 0.25, -1.5]));
+        let json = serde_json::to_value(&tracker.route[0]).unwrap();
+        assert_eq!(json["raw"], serde_json::json!([10.0, 5.0, 20.0, 0.25, -1.5]));
+        
+        // Off by default: the raw array is not read
+        let mut tracker = RouteTracker::with_provider(
+            Config::default(),
+            std::env::temp_dir(),
+            WorldPositionTransformer::empty(),
+            Box::new(RawProvider(VecProvider::new(vec![([10.0, 5.0, 20.0], 0x3C282300)]))),
+        );
+        tracker.start_recording();
+        assert!(tracker.capture_point());
+        assert_eq!(tracker.route[0].raw, None);
+    }
+    
+    #[test]
+    fn test_headless_record_and_save() {
+        let base_dir = std::env::temp_dir().join(format!("headless_tracker_test_{}", std::process::id()));
+        std::fs::create_dir_all(&base_dir).unwrap();
+        std::fs::write(
+            base_dir.join("WorldMapLegacyConvParam.csv"),
+            "header\n0,0,0,0,0,10,0,0,0,0.0,0.0,0.0,60,40,35,0,100.0,0.0,100.0\n",
+        )
+        .unwrap();
+        let mut config = Config::default();
+        config.recording.record_interval_ms = 0;
+        
+        // Full construction path (data files from base_dir), no game
+        let mut tracker = RouteTracker::with_config_and_provider(
+            config.clone(),
+            base_dir.clone(),
+            Box::new(VecProvider::new(vec![
+                ([1.0, 2.0, 3.0], 0x3C282300),
+                ([5.0, 2.0, 5.0], 0x0A000000),
+            ])),
+        );
+        tracker.start_recording();
+        for _ in 0..3 {
+            tick_flushed(&mut tracker);
+        }
+        tracker.stop_recording();
+        
+        let paths = tracker.save_route().unwrap();
+        let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&paths[0]).unwrap()).unwrap();
+        assert_eq!(json["point_count"], 2);
+        assert_eq!(json["points"][0]["global_x"], 1.0 + 40.0 * 256.0);
+        // Converted through the anchor loaded from base_dir
+        assert_eq!(json["points"][1]["global_x"], 105.0 + 40.0 * 256.0);
+        assert_eq!(json["points"][1]["map_id_str"], "m10_00_00_00");
+        
+        // Without any position source nothing is recorded
+        let mut tracker = RouteTracker::with_config_and_provider(config, base_dir.clone(), Box::new(NullProvider));
+        tracker.start_recording();
+        tick_flushed(&mut tracker);
+        assert!(tracker.route.is_empty());
+        assert!(tracker.save_route().is_err());
+        
+        std::fs::remove_dir_all(&base_dir).unwrap();
+    }
+    
+    #[test]
+    fn test_unconvertible_tile_warned_once() {
+        let mut config = Config::default();
+        config.recording.record_interval_ms = 0;
+        let unknown = 0x0A000000;
+        let mut tracker = RouteTracker::with_provider(
+            config,
+            std::env::temp_dir(),
+            WorldPositionTransformer::empty(),
+            Box::new(VecProvider::new(vec![([1.0, 0.0, 1.0], unknown); 5])),
+        );
+        tracker.start_recording();
+        for _ in 0..5 {
+            tick_flushed(&mut tracker);
+        }
+        
+        assert_eq!(tracker.route.len(), 5);
+        assert_eq!(tracker.warned_tiles, HashSet::from([unknown]));
+        let error = TransformError::UnknownMap("m10_00_00_00".to_string());
+        assert!(!RouteTracker::warn_unconvertible_once(&mut tracker.warned_tiles, unknown, &error));
+        
+        // Warned again in the next session
+        tracker.start_recording();
+        assert!(RouteTracker::warn_unconvertible_once(&mut tracker.warned_tiles, unknown, &error));
+    }
+    
+    /// Accepts every request and keeps the URLs posted to, for tests that stream
+    /// through a real `RealtimeClient`
+    #[derive(Clone, Default)]
+    struct OkPoster(Arc<Mutex<Vec<String>>>);
+    
+    impl OkPoster {
+        /// Requests posted to an endpoint ending with `path`
+        fn posted_to(&self, path: &str) -> usize {
+            self.0.lock().unwrap().iter().filter(|url| url.ends_with(path)).count()
+        }
+    }
+    
+    impl crate::realtime_client::HttpPoster for OkPoster {
+        fn post_json(&self, url: &str, _headers: &[(&str, &str)], _body: &str) -> Result<crate::realtime_client::HttpResponse, String> {
+            self.0.lock().unwrap().push(url.to_string());
+            Ok(crate::realtime_client::HttpResponse { status: 200, retry_after: None, body: String::new() })
+        }
+        
+        fn post_bytes(&self, url: &str, _headers: &[(&str, &str)], _body: &[u8]) -> Result<crate::realtime_client::HttpResponse, String> {
+            self.0.lock().unwrap().push(url.to_string());
+            Ok(crate::realtime_client::HttpResponse { status: 200, retry_after: None, body: String::new() })
+        }
+    }
+    
+    /// Batch client posting through `poster`
+    fn streaming_client(poster: &OkPoster) -> RealtimeClient {
+        RealtimeClient::new(
+            "http://localhost".to_string(),
+            None,
+            crate::config::AuthHeader::default(),
+            false,
+            crate::config::PayloadFormat::default(),
+            Duration::from_secs(1),
+            Some(Box::new(poster.clone())),
+        )
+    }
+    
+    #[test]
+    fn test_tick_records_and_streams_same_point() {
+        let bonfire: OutputMapIcon = serde_json::from_value(serde_json::json!({
+            "id": 100000, "iconId": 1, "eventFlagId": 71000,
+            "areaNo": 60, "gridXNo": 40, "gridZNo": 35,
+            "posX": 0.0, "posY": 2.0, "posZ": 0.0,
+            "globalX": 10240.0, "globalY": 2.0, "globalZ": 8960.0,
+            "mapId": "m60",
+            "texts": [{ "TextId": 100000, "TextType": 0, "Text": "Stormhill Shack", "Source": "Title_Locations" }]
+        })).unwrap();
+        
+        let mut config = Config::default();
+        config.recording.record_interval_ms = 0;
+        config.recording.snap_to_bonfires_radius = 10.0;
+        config.recording.world_bounds = Some(MapBounds { min_x: 1000.0, max_x: 20000.0, min_z: 1000.0, max_z: 20000.0 });
+        let mut tracker = RouteTracker::with_provider(
+            config,
+            std::env::temp_dir(),
+            WorldPositionTransformer::empty(),
+            Box::new(VecProvider::new(vec![
+                ([1.0, 2.0, 3.0], 0x3C282300),
+                ([1.0, 2.0, 3.0], 0x3C000000), // m60_00_00: outside world_bounds
+                ([4.0, 5.0, 6.0], 0x3C282300),
+            ])),
+        );
+        tracker.attach_bonfires(vec![bonfire]);
+        tracker.realtime_client = Some(streaming_client(&OkPoster::default()));
+        
+        tracker.start_recording();
+        tracker.start_streaming();
+        tracker.tick();
+        
+        // Converted on the pump thread, recorded and streamed once collected
+        assert_eq!(tracker.pump.in_flight(), 1);
+        tracker.flush_pump();
+        assert_eq!(tracker.route.len(), 1);
+        
+        // Streamed as recorded, offset from the grace included
+        let streamed = tracker.last_streamed_point.clone().expect("point streamed");
+        assert_eq!(
+            serde_json::to_value(&streamed).unwrap(),
+            serde_json::to_value(&tracker.route[0]).unwrap()
+        );
+        assert_eq!(streamed.x, 1.0);
+        assert_eq!(streamed.relative_to_grace, Some(("Stormhill Shack".to_string(), 1.0, 0.0, 3.0)));
+        
+        // Rejected by world_bounds: neither recorded nor streamed
+        tracker.tick();
+        tracker.flush_pump();
+        assert_eq!(tracker.route.len(), 1);
+        assert_eq!(tracker.last_streamed_point.as_ref().map(|point| point.map_id), Some(0x3C282300));
+        
+        // Only one sample was read for both each time
+        assert_eq!(tracker.read_position(), Some(([4.0, 5.0, 6.0], 0x3C282300)));
+    }
+}
@@ -4,6 +4,7 @@ use hudhook::imgui::{Condition, WindowFlags};
 use hudhook::tracing::info;
 use hudhook::ImguiRenderLoop;
 
+use crate::config::RecordMode;
 use crate::tracker::RouteTracker;
 
 // =============================================================================
@@ -15,11 +16,11 @@ impl ImguiRenderLoop for RouteTracker {
         // Handle keyboard shortcuts
         self.handle_hotkeys();
         
-        // Record position each frame if recording is active
-        self.record_position();
+        // Detect deaths (death waypoint / streamed event)
+        self.check_death();
         
-        // Stream position to backend if real-time mode is enabled (independent of recording)
-        self.stream_position();
+        // Record and/or stream the position (one read shared by both, each on its own interval)
+        self.tick();
         
         // NOTE: Hudhook crashes if render() doesn't draw anything.
         // We must always call window().build() even when hidden.
@@ -60,12 +61,14 @@ impl ImguiRenderLoop for RouteTracker {
 impl RouteTracker {
     /// Handle keyboard shortcuts
     fn handle_hotkeys(&mut self) {
-        if self.config.keybindings.toggle_ui.is_just_pressed() {
+        let pressed = self.config.keybindings.poll_pressed();
+        
+        if self.config.keybindings.toggle_ui.is_just_pressed(&pressed) {
             self.show_ui = !self.show_ui;
             info!("UI toggled: show_ui={}", self.show_ui);
         }
         
-        if self.config.keybindings.toggle_recording.is_just_pressed() {
+        if self.config.keybindings.toggle_recording.is_just_pressed(&pressed) {
             if self.is_recording {
                 self.stop_recording();
             } else {
@@ -73,7 +76,7 @@ impl RouteTracker {
             }
         }
         
-        if self.config.keybindings.toggle_streaming.is_just_pressed() {
+        if self.config.keybindings.toggle_streaming.is_just_pressed(&pressed) {
             if self.is_streaming {
                 self.stop_streaming();
             } else {
@@ -81,15 +84,29 @@ impl RouteTracker {
             }
         }
         
-        if self.config.keybindings.clear_route.is_just_pressed() {
-            self.route.clear();
+        if self.config.keybindings.clear_route.is_just_pressed(&pressed) {
+            self.clear_route();
             self.set_status("Route cleared!".to_string());
-            info!("Route cleared!");
         }
         
-        if self.config.keybindings.save_route.is_just_pressed() {
+        if self.config.keybindings.save_route.is_just_pressed(&pressed) {
             self.do_save_route();
         }
+        
+        if self.config.keybindings.finalize.is_just_pressed(&pressed) {
+            let result = self.finalize();
+            self.report_saved(result);
+        }
+        
+        if self.config.recording.mode == RecordMode::Manual
+            && self.config.keybindings.capture_point.is_just_pressed(&pressed)
+        {
+            if self.capture_point() {
+                self.set_status(format!("Captured point #{}", self.route.len()));
+            } else {
+                self.set_status("Error: Position not available".to_string());
+            }
+        }
     }
     
     /// Render current position section
@@ -125,8 +142,7 @@ impl RouteTracker {
             ui.text_colored([0.0, 1.0, 0.0, 1.0], "● RECORDING");
             ui.text(format!("Points: {}", self.route.len()));
             
-            if let Some(start) = self.recording_start_time {
-                let elapsed = start.elapsed();
+            if let Some(elapsed) = self.elapsed() {
                 let secs = elapsed.as_secs();
                 let mins = secs / 60;
                 let secs = secs % 60;
@@ -147,7 +163,7 @@ impl RouteTracker {
             ui.same_line();
             
             if ui.button("Clear") {
-                self.route.clear();
+                self.clear_route();
                 self.set_status("Route cleared!".to_string());
             }
             
@@ -175,17 +191,31 @@ impl RouteTracker {
             return;
         }
         
+        if self.realtime_client.as_ref().is_some_and(|client| !client.is_healthy()) {
+            ui.text_colored([1.0, 0.0, 0.0, 1.0], "✕ Streaming failed");
+            ui.text_disabled("The sender thread crashed, see the log");
+            return;
+        }
+        
         if self.is_streaming {
             ui.text_colored([0.0, 1.0, 0.0, 1.0], "● STREAMING");
             
-            if let Some(stream_start) = self.stream_start_time {
-                let elapsed = stream_start.elapsed();
+            if let Some(elapsed) = self.stream_elapsed() {
                 let secs = elapsed.as_secs();
                 let mins = secs / 60;
                 let secs = secs % 60;
                 ui.text(format!("Duration: {:02}:{:02}", mins, secs));
             }
             
+            if let Some(ref client) = self.realtime_client {
+                let queued = format!("Queued: {} points", client.queue_depth());
+                if self.stream_throttled {
+                    ui.text_colored([1.0, 0.5, 0.0, 1.0], format!("{} (throttled)", queued));
+                } else {
+                    ui.text(queued);
+                }
+            }
+            
             if ui.button("Stop Streaming") {
                 self.stop_streaming();
             }
@@ -214,16 +244,32 @@ impl RouteTracker {
         ui.text_disabled(format!("{}: Start/Stop Streaming", self.config.keybindings.toggle_streaming.name()));
         ui.text_disabled(format!("{}: Clear Route", self.config.keybindings.clear_route.name()));
         ui.text_disabled(format!("{}: Save Route", self.config.keybindings.save_route.name()));
+        ui.text_disabled(format!("{}: Stop, Flush & Save", self.config.keybindings.finalize.name()));
+        if self.config.recording.mode == RecordMode::Manual {
+            ui.text_disabled(format!("{}: Capture Point", self.config.keybindings.capture_point.name()));
+        }
     }
     
     /// Save route and update status
     fn do_save_route(&mut self) {
-        match self.save_route() {
-            Ok(path) => {
-                self.set_status(format!(
-                    "Saved: {}",
-                    path.file_name().unwrap_or_default().to_string_lossy()
-                ));
+        let result = self.save_route();
+        self.report_saved(result);
+    }
+    
+    /// Show the outcome of a save in the status line
+    fn report_saved(&mut self, result: Result<Vec<std::path::PathBuf>, String>) {
+        match result {
+            Ok(paths) if paths.len() > 1 => {
+                self.set_status(format!("Saved: {} files", paths.len()));
+            }
+            Ok(paths) => {
+                let name = paths
+                    .first()
+                    .and_then(|p| p.file_name())
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string();
+                self.set_status(format!("Saved: {}", name));
             }
             Err(e) => {
                 self.set_status(format!("Error: {}", e));